@@ -0,0 +1,251 @@
+use std::time::Instant;
+
+use crate::game::{Board, Cell, Player, Position, BOARD_SIZE, CENTER};
+use super::pattern::Difficulty;
+
+/// 连六获胜分数
+const WIN_SCORE: i32 = 1_000_000;
+/// 六格窗口内只有一方棋子时，按棋子数计分
+const WINDOW_SCORES: [i32; 7] = [0, 1, 10, 100, 1000, 10000, WIN_SCORE];
+
+/// 四个方向：横、竖、斜右下、斜左下
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// 六子棋搜索：每回合以两子为一个整体搜索
+pub struct Connect6Solver;
+
+impl Connect6Solver {
+    /// 为 `player` 选出本回合要下的 `stones` 个棋子（1 或 2）
+    pub fn best_stones(
+        board: &Board,
+        player: Player,
+        stones: usize,
+        difficulty: Difficulty,
+        deadline: Option<Instant>,
+    ) -> Vec<Position> {
+        if board.count_pieces() == 0 && board.is_empty(CENTER, CENTER) {
+            return vec![Position { x: CENTER, y: CENTER }];
+        }
+
+        let candidates = Self::candidate_stones(board, player, difficulty.max_candidates());
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+        if stones < 2 || candidates.len() < 2 {
+            return Self::best_single(board, player, &candidates).into_iter().collect();
+        }
+
+        // 先按一层评估排序，再对前几组考虑对方的最佳应对
+        let mut pairs = Vec::new();
+        for i in 0..candidates.len() {
+            for j in (i + 1)..candidates.len() {
+                let mut new_board = board.clone();
+                if Self::place_all(&mut new_board, &[&candidates[i], &candidates[j]], player).is_err() {
+                    continue;
+                }
+                let score = Self::evaluate(&new_board, player, player.opponent());
+                pairs.push(((i, j), score));
+            }
+        }
+        pairs.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+        let Some(&((i, j), first_score)) = pairs.first() else {
+            return Vec::new();
+        };
+        let mut best = (vec![candidates[i].clone(), candidates[j].clone()], first_score);
+        if first_score >= WIN_SCORE || difficulty == Difficulty::Easy {
+            return best.0;
+        }
+
+        let deep_pairs = match difficulty {
+            Difficulty::Hard => 12,
+            _ => 6,
+        };
+        best.1 = i32::MIN;
+        for &((i, j), _) in pairs.iter().take(deep_pairs) {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            let mut new_board = board.clone();
+            let _ = Self::place_all(&mut new_board, &[&candidates[i], &candidates[j]], player);
+            let score = Self::worst_reply(&new_board, player, difficulty);
+            if score > best.1 {
+                best = (vec![candidates[i].clone(), candidates[j].clone()], score);
+            }
+        }
+
+        best.0
+    }
+
+    /// 从 `player` 视角评估局面（`to_move` 为下一回合行棋方）
+    pub fn evaluate(board: &Board, player: Player, to_move: Player) -> i32 {
+        let (black, white) = Self::scan_windows(board);
+        let (own, opponent) = match player {
+            Player::Black => (black, white),
+            Player::White => (white, black),
+        };
+
+        if own.best == 6 {
+            return WIN_SCORE;
+        }
+        if opponent.best == 6 {
+            return -WIN_SCORE;
+        }
+        // 轮到走棋的一方只要有四子或五子的窗口，就能一回合两子连六
+        if to_move == player && own.best >= 4 {
+            return WIN_SCORE / 2;
+        }
+        if to_move != player && opponent.best >= 4 {
+            return -WIN_SCORE / 2;
+        }
+
+        own.score - (opponent.score as f32 * 1.1) as i32
+    }
+
+    /// 对方以最佳两子应对后的局面分数
+    fn worst_reply(board: &Board, player: Player, difficulty: Difficulty) -> i32 {
+        let opponent = player.opponent();
+        let replies = Self::candidate_stones(board, opponent, difficulty.max_candidates() / 2 + 2);
+        let mut worst = Self::evaluate(board, player, opponent);
+
+        for i in 0..replies.len() {
+            for j in (i + 1)..replies.len() {
+                let mut new_board = board.clone();
+                if Self::place_all(&mut new_board, &[&replies[i], &replies[j]], opponent).is_err() {
+                    continue;
+                }
+                worst = worst.min(Self::evaluate(&new_board, player, player));
+                if worst <= -WIN_SCORE {
+                    return worst;
+                }
+            }
+        }
+        worst
+    }
+
+    /// 只剩一子时选择评估最高的位置
+    fn best_single(board: &Board, player: Player, candidates: &[Position]) -> Option<Position> {
+        candidates
+            .iter()
+            .filter_map(|pos| {
+                let mut new_board = board.clone();
+                new_board.set(pos.x, pos.y, player).ok()?;
+                Some((pos.clone(), Self::evaluate(&new_board, player, player.opponent())))
+            })
+            .max_by_key(|(_, score)| *score)
+            .map(|(pos, _)| pos)
+    }
+
+    /// 生成候选落子：已有棋子周围 2 格内、攻防价值最高的 `max_count` 个位置
+    fn candidate_stones(board: &Board, player: Player, max_count: usize) -> Vec<Position> {
+        let mut candidates = Vec::new();
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                let pos = Position { x, y };
+                if board.is_empty(x, y) && board.has_stone_near(x, y, 2) {
+                    let score = Self::stone_value(board, &pos, player)
+                        + Self::stone_value(board, &pos, player.opponent());
+                    candidates.push((pos, score));
+                }
+            }
+        }
+        candidates.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+        candidates.into_iter().take(max_count).map(|(pos, _)| pos).collect()
+    }
+
+    /// `player` 在 `pos` 落子使经过该点的窗口增加的分数
+    fn stone_value(board: &Board, pos: &Position, player: Player) -> i32 {
+        let own = Self::cell_of(player);
+        let mut value = 0;
+
+        for (dx, dy) in DIRECTIONS {
+            for start in -5..=0 {
+                let cells: Option<Vec<Cell>> = (start..start + 6)
+                    .map(|k| {
+                        let x = pos.x as isize + dx * k;
+                        let y = pos.y as isize + dy * k;
+                        if (0..BOARD_SIZE as isize).contains(&x) && (0..BOARD_SIZE as isize).contains(&y) {
+                            board.get(x as usize, y as usize).ok()
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                let Some(cells) = cells else {
+                    continue;
+                };
+                if cells.iter().any(|&cell| cell != own && cell != Cell::Empty) {
+                    continue;
+                }
+                let count = cells.iter().filter(|&&cell| cell == own).count();
+                value += WINDOW_SCORES[count + 1] - WINDOW_SCORES[count];
+            }
+        }
+        value
+    }
+
+    /// 统计双方所有六格窗口的分数
+    fn scan_windows(board: &Board) -> (WindowSummary, WindowSummary) {
+        let mut black = WindowSummary::default();
+        let mut white = WindowSummary::default();
+
+        for x in 0..BOARD_SIZE as isize {
+            for y in 0..BOARD_SIZE as isize {
+                for (dx, dy) in DIRECTIONS {
+                    let end_x = x + dx * 5;
+                    let end_y = y + dy * 5;
+                    if !(0..BOARD_SIZE as isize).contains(&end_x) || !(0..BOARD_SIZE as isize).contains(&end_y) {
+                        continue;
+                    }
+
+                    let mut black_count = 0;
+                    let mut white_count = 0;
+                    for k in 0..6 {
+                        match board.get((x + dx * k) as usize, (y + dy * k) as usize) {
+                            Ok(Cell::Black) => black_count += 1,
+                            Ok(Cell::White) => white_count += 1,
+                            _ => {}
+                        }
+                    }
+
+                    if white_count == 0 {
+                        black.add(black_count);
+                    } else if black_count == 0 {
+                        white.add(white_count);
+                    }
+                }
+            }
+        }
+
+        (black, white)
+    }
+
+    fn place_all(board: &mut Board, stones: &[&Position], player: Player) -> Result<(), String> {
+        for pos in stones {
+            board.set(pos.x, pos.y, player)?;
+        }
+        Ok(())
+    }
+
+    fn cell_of(player: Player) -> Cell {
+        match player {
+            Player::Black => Cell::Black,
+            Player::White => Cell::White,
+        }
+    }
+}
+
+/// 一方所有窗口的汇总
+#[derive(Default)]
+struct WindowSummary {
+    score: i32,
+    /// 单个窗口内的最多棋子数
+    best: usize,
+}
+
+impl WindowSummary {
+    fn add(&mut self, count: usize) {
+        self.score = self.score.saturating_add(WINDOW_SCORES[count]);
+        self.best = self.best.max(count);
+    }
+}
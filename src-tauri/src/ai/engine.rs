@@ -1,18 +1,39 @@
-use crate::game::{Board, Player, Position};
+use std::time::{Duration, Instant};
+
+use crate::game::{Board, CaptureCount, Player, Position, RuleSet, RulesValidator};
+use super::connect6::Connect6Solver;
+use super::evaluator::PatternEvaluator;
 use super::minimax::MinimaxSolver;
+use super::pente::PenteSolver;
 use super::pattern::Difficulty;
 
+/// 即使走出最佳一手，对方仍能连五时认输
+const RESIGN_THRESHOLD: i32 = -100000;
+/// 局面评估不高于此值时接受和棋
+const DRAW_ACCEPT_THRESHOLD: i32 = 50;
+
 pub struct AIEngine {
     difficulty: Difficulty,
+    rule_set: RuleSet,
 }
 
 impl AIEngine {
     pub fn new(difficulty: Difficulty) -> Self {
-        AIEngine { difficulty }
+        AIEngine {
+            difficulty,
+            rule_set: RuleSet::Freestyle,
+        }
     }
 
-    /// 获取 AI 的最佳落子位置
-    pub fn get_best_move(&self, board: &Board, player: Player) -> Option<Position> {
+    /// 获取 AI 的最佳落子位置（`captures` 仅提子规则使用）
+    pub fn get_best_move(&self, board: &Board, player: Player, captures: CaptureCount) -> Option<Position> {
+        if self.rule_set == RuleSet::Connect6 {
+            return self.get_best_stones(board, player, None).into_iter().next();
+        }
+        if self.rule_set == RuleSet::Pente {
+            return self.get_capture_move(board, player, captures, None);
+        }
+
         let depth = self.difficulty.search_depth();
         let (_, best_move) = MinimaxSolver::minimax(
             board,
@@ -23,11 +44,156 @@ impl AIEngine {
             player,
         );
 
-        best_move
+        self.avoid_forbidden(board, player, best_move)
+    }
+
+    /// 在时间预算内获取最佳落子（迭代加深，超时则使用上一层的结果）
+    pub fn get_best_move_within(
+        &self,
+        board: &Board,
+        player: Player,
+        captures: CaptureCount,
+        budget: Option<Duration>,
+    ) -> Option<Position> {
+        if self.rule_set == RuleSet::Connect6 {
+            return self.get_best_stones(board, player, budget).into_iter().next();
+        }
+        if self.rule_set == RuleSet::Pente {
+            return self.get_capture_move(board, player, captures, budget);
+        }
+        let Some(budget) = budget else {
+            return self.get_best_move(board, player, captures);
+        };
+
+        let best_move = self.deepen(budget, |depth, deadline| {
+            MinimaxSolver::minimax_until(board, depth, i32::MIN, i32::MAX, true, player, deadline)
+        });
+        self.avoid_forbidden(board, player, best_move)
+    }
+
+    /// 提子规则：在时间预算内获取最佳落子（需要双方当前的提子数）
+    pub fn get_capture_move(
+        &self,
+        board: &Board,
+        player: Player,
+        captures: CaptureCount,
+        budget: Option<Duration>,
+    ) -> Option<Position> {
+        let search = |depth, deadline| {
+            PenteSolver::minimax_until(board, captures, depth, i32::MIN, i32::MAX, true, player, deadline)
+        };
+        match budget {
+            Some(budget) => self.deepen(budget, search),
+            None => search(self.difficulty.search_depth(), None).and_then(|(_, pos)| pos),
+        }
+    }
+
+    /// 迭代加深，超时则使用上一层的结果
+    fn deepen<F>(&self, budget: Duration, search: F) -> Option<Position>
+    where
+        F: Fn(u8, Option<Instant>) -> Option<(i32, Option<Position>)>,
+    {
+        let start = Instant::now();
+        let deadline = start + budget;
+        let mut best_move = None;
+
+        for depth in 1..=self.difficulty.search_depth() {
+            // 下一层通常耗时更久，剩余时间不足一半时不再加深
+            if depth > 1 && start.elapsed() > budget / 2 {
+                break;
+            }
+            match search(depth, Some(deadline)) {
+                Some((_, Some(pos))) => best_move = Some(pos),
+                Some((_, None)) => break,
+                None => break,
+            }
+        }
+
+        // 时间太紧连第一层都没搜完时，退回快速选点
+        best_move.or_else(|| search(1, None).and_then(|(_, pos)| pos))
+    }
+
+    /// 六子棋：获取本回合剩余的全部落子（按落子顺序）
+    pub fn get_best_stones(
+        &self,
+        board: &Board,
+        player: Player,
+        budget: Option<Duration>,
+    ) -> Vec<Position> {
+        let stones = self.rule_set.stones_left_in_turn(board.count_pieces());
+        let deadline = budget.map(|budget| Instant::now() + budget);
+        Connect6Solver::best_stones(board, player, stones, self.difficulty, deadline)
+    }
+
+    /// 从 `player` 视角评估局面（`to_move` 为下一步行棋方，`captures` 仅提子规则使用）
+    pub fn evaluate(&self, board: &Board, player: Player, to_move: Player, captures: CaptureCount) -> i32 {
+        if self.rule_set == RuleSet::Connect6 {
+            return Connect6Solver::evaluate(board, player, to_move);
+        }
+        let (score, _) = if self.rule_set == RuleSet::Pente {
+            PenteSolver::minimax_until(board, captures, 2, i32::MIN, i32::MAX, true, to_move, None)
+                .expect("search without deadline never times out")
+        } else {
+            MinimaxSolver::minimax(board, 2, i32::MIN, i32::MAX, true, to_move)
+        };
+        if to_move == player {
+            score
+        } else {
+            score.saturating_neg()
+        }
+    }
+
+    /// 轮到 AI 走棋时，局面是否已无望
+    pub fn should_resign(&self, board: &Board, player: Player, captures: CaptureCount) -> bool {
+        self.evaluate(board, player, player, captures) <= RESIGN_THRESHOLD
+    }
+
+    /// 是否接受对方的和棋提议
+    pub fn accepts_draw(&self, board: &Board, player: Player, to_move: Player, captures: CaptureCount) -> bool {
+        self.evaluate(board, player, to_move, captures) <= DRAW_ACCEPT_THRESHOLD
+    }
+
+    /// 连珠规则下黑棋避开禁手点，改选评估最高的合法位置
+    fn avoid_forbidden(&self, board: &Board, player: Player, best_move: Option<Position>) -> Option<Position> {
+        if self.rule_set != RuleSet::Renju || player != Player::Black {
+            return best_move;
+        }
+        let is_forbidden = |pos: &Position| {
+            let mut new_board = board.clone();
+            new_board.set(pos.x, pos.y, player).is_err()
+                || RulesValidator::check_forbidden(&new_board, pos).is_some()
+        };
+        if let Some(pos) = &best_move {
+            if !is_forbidden(pos) {
+                return best_move;
+            }
+        }
+
+        let mut best: Option<(Position, i32)> = None;
+        for x in 0..15 {
+            for y in 0..15 {
+                let pos = Position { x, y };
+                if !board.is_empty(x, y) || is_forbidden(&pos) {
+                    continue;
+                }
+                let mut new_board = board.clone();
+                let _ = new_board.set(x, y, player);
+                let score = PatternEvaluator::evaluate_board(&new_board, player);
+                if best.as_ref().is_none_or(|(_, best_score)| score > *best_score) {
+                    best = Some((pos, score));
+                }
+            }
+        }
+        best.map(|(pos, _)| pos)
     }
 
     /// 更改难度
     pub fn set_difficulty(&mut self, difficulty: Difficulty) {
         self.difficulty = difficulty;
     }
+
+    /// 更改规则
+    pub fn set_rule_set(&mut self, rule_set: RuleSet) {
+        self.rule_set = rule_set;
+    }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use crate::game::{Board, Player, Position};
 use super::evaluator::PatternEvaluator;
 
@@ -6,22 +8,40 @@ pub struct MinimaxSolver;
 impl MinimaxSolver {
     /// Minimax 算法 with Alpha-Beta 剪枝
     pub fn minimax(
+        board: &Board,
+        depth: u8,
+        alpha: i32,
+        beta: i32,
+        maximizing: bool,
+        player: Player,
+    ) -> (i32, Option<Position>) {
+        Self::minimax_until(board, depth, alpha, beta, maximizing, player, None)
+            .expect("search without deadline never times out")
+    }
+
+    /// 带截止时间的 Minimax，超时返回 None
+    pub fn minimax_until(
         board: &Board,
         depth: u8,
         mut alpha: i32,
         mut beta: i32,
         maximizing: bool,
         player: Player,
-    ) -> (i32, Option<Position>) {
+        deadline: Option<Instant>,
+    ) -> Option<(i32, Option<Position>)> {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+
         // 终止条件：达到最大深度或游戏结束
         if depth == 0 || Self::is_game_over(board) {
             let score = PatternEvaluator::evaluate_board(board, player);
-            return (score, None);
+            return Some((score, None));
         }
 
         let candidates = Self::generate_candidate_moves(board, 20);
         if candidates.is_empty() {
-            return (0, None);
+            return Some((0, None));
         }
 
         let mut best_move = None;
@@ -36,14 +56,15 @@ impl MinimaxSolver {
                     continue;
                 }
 
-                let (eval, _) = Self::minimax(
+                let (eval, _) = Self::minimax_until(
                     &new_board,
                     depth - 1,
                     alpha,
                     beta,
                     false,
                     player,
-                );
+                    deadline,
+                )?;
 
                 if eval > max_eval {
                     max_eval = eval;
@@ -56,7 +77,7 @@ impl MinimaxSolver {
                 }
             }
 
-            (max_eval, best_move)
+            Some((max_eval, best_move))
         } else {
             let mut min_eval = i32::MAX;
 
@@ -68,14 +89,15 @@ impl MinimaxSolver {
                     continue;
                 }
 
-                let (eval, _) = Self::minimax(
+                let (eval, _) = Self::minimax_until(
                     &new_board,
                     depth - 1,
                     alpha,
                     beta,
                     true,
                     player,
-                );
+                    deadline,
+                )?;
 
                 if eval < min_eval {
                     min_eval = eval;
@@ -88,12 +110,12 @@ impl MinimaxSolver {
                 }
             }
 
-            (min_eval, best_move)
+            Some((min_eval, best_move))
         }
     }
 
     /// 生成候选落子位置（启发式搜索）
-    fn generate_candidate_moves(board: &Board, max_count: usize) -> Vec<Position> {
+    pub(crate) fn generate_candidate_moves(board: &Board, max_count: usize) -> Vec<Position> {
         let mut candidates = Vec::new();
 
         for x in 0..15 {
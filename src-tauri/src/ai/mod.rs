@@ -1,9 +1,15 @@
+pub mod connect6;
 pub mod engine;
 pub mod evaluator;
 pub mod minimax;
+pub mod opening;
 pub mod pattern;
+pub mod pente;
 
+pub use connect6::Connect6Solver;
 pub use engine::AIEngine;
 pub use evaluator::PatternEvaluator;
 pub use minimax::MinimaxSolver;
-pub use pattern::{Pattern, Difficulty};
\ No newline at end of file
+pub use opening::{OpeningAction, OpeningAdvisor};
+pub use pattern::{Pattern, Difficulty};
+pub use pente::PenteSolver;
\ No newline at end of file
@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Board, BOARD_SIZE, CENTER, OpeningDecision, OpeningPhase, OpeningState, Player, Position};
+use super::evaluator::PatternEvaluator;
+use super::minimax::MinimaxSolver;
+
+/// 局面差距在此范围内视为均衡
+const BALANCE_MARGIN: i32 = 200;
+
+/// AI 在开局阶段的行动
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpeningAction {
+    Place { position: Position },
+    Decide { decision: OpeningDecision },
+    Offer { positions: Vec<Position> },
+}
+
+pub struct OpeningAdvisor;
+
+impl OpeningAdvisor {
+    /// 根据开局阶段决定 AI 的行动
+    pub fn choose_action(board: &Board, opening: &OpeningState) -> Option<OpeningAction> {
+        match opening.phase()? {
+            OpeningPhase::PlaceStone { stone, zone, .. } => {
+                Self::balanced_stone(board, stone, zone)
+                    .map(|position| OpeningAction::Place { position })
+            }
+            OpeningPhase::SwapChoice { by } => {
+                let decision = if Self::advantage(board, by) < 0 {
+                    OpeningDecision::SwapColor
+                } else {
+                    OpeningDecision::KeepColor
+                };
+                Some(OpeningAction::Decide { decision })
+            }
+            OpeningPhase::Swap2Choice { by } => {
+                let advantage = Self::advantage(board, by);
+                let decision = if advantage > BALANCE_MARGIN {
+                    OpeningDecision::KeepColor
+                } else if advantage < -BALANCE_MARGIN {
+                    OpeningDecision::SwapColor
+                } else {
+                    // 局面均衡时再下两子，把选择权交给对方
+                    OpeningDecision::PlaceTwo
+                };
+                Some(OpeningAction::Decide { decision })
+            }
+            OpeningPhase::DeclareOffers { by, max } => {
+                // 黑棋越占优，给对方的选择越多，使交换前的局面尽量均衡
+                let black_advantage = -Self::advantage(board, by);
+                let count = (4 + black_advantage / BALANCE_MARGIN).clamp(1, max as i32) as usize;
+                Some(OpeningAction::Decide { decision: OpeningDecision::DeclareOffers { count } })
+            }
+            OpeningPhase::FifthMoveChoice { by, .. } => {
+                // 优势明显时打点（对方不能再交换），否则正常落子后让对方选择
+                let decision = if Self::advantage(board, by) > BALANCE_MARGIN {
+                    OpeningDecision::OfferMany
+                } else {
+                    OpeningDecision::PlaceOne
+                };
+                Some(OpeningAction::Decide { decision })
+            }
+            OpeningPhase::OfferFifth { count, .. } => {
+                let positions = Self::fifth_move_offers(board, count);
+                if positions.len() < count {
+                    return None;
+                }
+                Some(OpeningAction::Offer { positions })
+            }
+            OpeningPhase::ChooseOffer { by } => {
+                Self::choose_offer(board, &opening.offers, by)
+                    .map(|position| OpeningAction::Place { position })
+            }
+        }
+    }
+
+    /// 生成 `count` 个互不对称的第五手打点
+    ///
+    /// 对方会选择其中对黑棋最不利的一个，所以给出评估最高的几手，
+    /// 使最差的那一手尽量好。
+    pub fn fifth_move_offers(board: &Board, count: usize) -> Vec<Position> {
+        let mut scored = Vec::new();
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                let pos = Position { x, y };
+                if !board.is_empty(x, y) || !board.has_stone_near(x, y, 2) {
+                    continue;
+                }
+                let mut new_board = board.clone();
+                if new_board.set(x, y, Player::Black).is_err() {
+                    continue;
+                }
+                let score = PatternEvaluator::evaluate_board(&new_board, Player::Black);
+                scored.push((pos, score));
+            }
+        }
+        scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+        let mut offers: Vec<Position> = Vec::new();
+        for (pos, _) in scored {
+            if offers.len() == count {
+                break;
+            }
+            if !offers.iter().any(|other| OpeningState::equivalent_moves(board, &pos, other)) {
+                offers.push(pos);
+            }
+        }
+        offers
+    }
+
+    /// 从打点中选出对 `by` 一方最有利的一手
+    pub fn choose_offer(board: &Board, offers: &[Position], by: Player) -> Option<Position> {
+        offers
+            .iter()
+            .filter_map(|pos| {
+                let mut new_board = board.clone();
+                new_board.set(pos.x, pos.y, Player::Black).ok()?;
+                Some((pos.clone(), Self::advantage(&new_board, by)))
+            })
+            .max_by_key(|(_, score)| *score)
+            .map(|(pos, _)| pos)
+    }
+
+    /// 选择一个让局面尽量均衡的开局落子（对方随后可以选择交换）
+    pub fn balanced_stone(board: &Board, stone: Player, zone: Option<usize>) -> Option<Position> {
+        // 第一手下在天元
+        if board.count_pieces() == 0 && board.is_empty(CENTER, CENTER) {
+            return Some(Position { x: CENTER, y: CENTER });
+        }
+
+        let mut best: Option<(Position, i32)> = None;
+
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                let pos = Position { x, y };
+                if !board.is_empty(x, y) || !board.has_stone_near(x, y, 2) {
+                    continue;
+                }
+                if let Some(zone) = zone {
+                    if !OpeningState::in_zone(&pos, zone) {
+                        continue;
+                    }
+                }
+
+                let mut new_board = board.clone();
+                if new_board.set(x, y, stone).is_err() {
+                    continue;
+                }
+                // 均衡优先，其次靠近中心
+                let imbalance = PatternEvaluator::evaluate_board(&new_board, Player::Black).abs();
+                let center_distance = (x.abs_diff(CENTER) + y.abs_diff(CENTER)) as i32;
+                let score = imbalance + center_distance * 10;
+                if best.as_ref().is_none_or(|(_, best_score)| score < *best_score) {
+                    best = Some((pos, score));
+                }
+            }
+        }
+
+        best.map(|(pos, _)| pos)
+    }
+
+    /// 轮到下一手时，`color` 一方的局面优势（浅层搜索）
+    pub fn advantage(board: &Board, color: Player) -> i32 {
+        let to_move = if board.count_pieces().is_multiple_of(2) {
+            Player::Black
+        } else {
+            Player::White
+        };
+        let (score, _) = MinimaxSolver::minimax(board, 2, i32::MIN, i32::MAX, true, to_move);
+        if to_move == color {
+            score
+        } else {
+            -score
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::OpeningRule;
+
+    /// 让 AI 替双方走完整个开局，返回最终的棋盘和开局状态
+    fn play_out(rule: OpeningRule) -> (Board, OpeningState) {
+        let mut board = Board::new();
+        let mut opening = OpeningState::new(rule);
+        for _ in 0..20 {
+            let Some(phase) = opening.phase() else { break };
+            match OpeningAdvisor::choose_action(&board, &opening).expect("开局阶段应有行动") {
+                OpeningAction::Place { position } => {
+                    let stone = match phase {
+                        OpeningPhase::PlaceStone { stone, .. } => stone,
+                        OpeningPhase::ChooseOffer { .. } => Player::Black,
+                        other => panic!("{:?} 阶段不应落子", other),
+                    };
+                    opening.place_stone(&position, stone).unwrap();
+                    board.set(position.x, position.y, stone).unwrap();
+                }
+                OpeningAction::Decide { decision } => opening.decide(decision).unwrap(),
+                OpeningAction::Offer { positions } => {
+                    opening.offer_fifth_moves(&board, &positions).unwrap()
+                }
+            }
+        }
+        (board, opening)
+    }
+
+    #[test]
+    fn first_stone_goes_to_the_center() {
+        let opening = OpeningState::new(OpeningRule::Swap);
+        match OpeningAdvisor::choose_action(&Board::new(), &opening) {
+            Some(OpeningAction::Place { position }) => {
+                assert_eq!((position.x, position.y), (CENTER, CENTER))
+            }
+            other => panic!("unexpected action {:?}", other),
+        }
+    }
+
+    #[test]
+    fn no_action_without_an_opening() {
+        let opening = OpeningState::new(OpeningRule::Standard);
+        assert!(OpeningAdvisor::choose_action(&Board::new(), &opening).is_none());
+    }
+
+    #[test]
+    fn completes_every_opening_protocol() {
+        for (rule, stones) in [
+            (OpeningRule::Swap, &[3][..]),
+            (OpeningRule::Swap2, &[3, 5][..]),
+            (OpeningRule::Soosorv, &[5][..]),
+            (OpeningRule::Taraguchi, &[5][..]),
+            (OpeningRule::Renju, &[5][..]),
+        ] {
+            let (board, opening) = play_out(rule);
+            assert!(opening.is_finished(), "{:?} 开局未完成", rule);
+            assert!(stones.contains(&opening.stones_placed), "{:?}", rule);
+            assert_eq!(board.count_pieces(), opening.stones_placed, "{:?}", rule);
+        }
+    }
+
+    #[test]
+    fn fifth_move_offers_are_distinct_and_not_equivalent() {
+        let (mut board, mut opening) = (Board::new(), OpeningState::new(OpeningRule::Renju));
+        for (x, y, stone) in [(7, 7, Player::Black), (7, 8, Player::White), (8, 8, Player::Black)] {
+            opening.place_stone(&Position { x, y }, stone).unwrap();
+            board.set(x, y, stone).unwrap();
+        }
+        opening.decide(OpeningDecision::KeepColor).unwrap();
+        opening.place_stone(&Position { x: 6, y: 6 }, Player::White).unwrap();
+        board.set(6, 6, Player::White).unwrap();
+
+        let offers = OpeningAdvisor::fifth_move_offers(&board, 2);
+        assert_eq!(offers.len(), 2);
+        opening.offer_fifth_moves(&board, &offers).unwrap();
+        let chosen = OpeningAdvisor::choose_offer(&board, &offers, Player::White).unwrap();
+        assert!(offers.contains(&chosen));
+    }
+}
@@ -0,0 +1,195 @@
+use std::time::Instant;
+
+use crate::game::{Board, CaptureCount, Cell, Player, Position, RulesValidator, CAPTURES_TO_WIN};
+use super::evaluator::PatternEvaluator;
+use super::minimax::MinimaxSolver;
+
+/// 连五或提五对获胜
+const WIN_SCORE: i32 = 100000;
+/// 已提对数对应的分数
+const CAPTURE_SCORES: [i32; 5] = [0, 300, 800, 2000, 6000];
+/// 一对棋子可被提走（或可以提走对方一对）的威胁分
+const CAPTURE_THREAT: i32 = 150;
+
+/// 提子规则下的搜索：落子后执行提子，评估时考虑提子数和提子威胁
+pub struct PenteSolver;
+
+impl PenteSolver {
+    /// 带截止时间的 Minimax，超时返回 None
+    #[allow(clippy::too_many_arguments)]
+    pub fn minimax_until(
+        board: &Board,
+        captures: CaptureCount,
+        depth: u8,
+        mut alpha: i32,
+        mut beta: i32,
+        maximizing: bool,
+        player: Player,
+        deadline: Option<Instant>,
+    ) -> Option<(i32, Option<Position>)> {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+
+        if depth == 0 || Self::is_game_over(board, captures) {
+            return Some((Self::evaluate(board, captures, player), None));
+        }
+
+        let mover = if maximizing { player } else { player.opponent() };
+        let candidates = Self::candidate_moves(board, mover);
+        if candidates.is_empty() {
+            return Some((0, None));
+        }
+
+        let mut best_move = None;
+        let mut best_eval = if maximizing { i32::MIN } else { i32::MAX };
+
+        for pos in candidates {
+            let mut new_board = board.clone();
+            if new_board.set(pos.x, pos.y, mover).is_err() {
+                continue;
+            }
+            let mut new_captures = captures;
+            let captured = RulesValidator::apply_captures(&mut new_board, &pos);
+            *new_captures.get_mut(mover) += (captured.len() / 2) as u32;
+
+            // 连五即结束，不再继续搜索
+            let eval = if RulesValidator::check_five_in_row(&new_board, &pos).is_some() {
+                if mover == player { WIN_SCORE } else { -WIN_SCORE }
+            } else {
+                Self::minimax_until(
+                    &new_board,
+                    new_captures,
+                    depth - 1,
+                    alpha,
+                    beta,
+                    !maximizing,
+                    player,
+                    deadline,
+                )?
+                .0
+            };
+
+            if maximizing {
+                if eval > best_eval {
+                    best_eval = eval;
+                    best_move = Some(pos);
+                }
+                alpha = alpha.max(eval);
+            } else {
+                if eval < best_eval {
+                    best_eval = eval;
+                    best_move = Some(pos);
+                }
+                beta = beta.min(eval);
+            }
+            if beta <= alpha {
+                break;
+            }
+        }
+
+        Some((best_eval, best_move))
+    }
+
+    /// 从 `player` 视角评估局面：棋型 + 提子数 + 提子威胁
+    pub fn evaluate(board: &Board, captures: CaptureCount, player: Player) -> i32 {
+        let opponent = player.opponent();
+        if RulesValidator::wins_by_captures(captures.get(player)) {
+            return WIN_SCORE;
+        }
+        if RulesValidator::wins_by_captures(captures.get(opponent)) {
+            return -WIN_SCORE;
+        }
+
+        let pattern_score = PatternEvaluator::evaluate_board(board, player);
+        let capture_score = Self::capture_score(captures.get(player))
+            - (Self::capture_score(captures.get(opponent)) as f32 * 1.1) as i32;
+        let threat_score = (Self::capture_threats(board, player) - Self::capture_threats(board, opponent))
+            * CAPTURE_THREAT;
+
+        pattern_score + capture_score + threat_score
+    }
+
+    /// 候选落子：能提子的位置优先
+    fn candidate_moves(board: &Board, mover: Player) -> Vec<Position> {
+        let mut candidates: Vec<(Position, usize)> = MinimaxSolver::generate_candidate_moves(board, 20)
+            .into_iter()
+            .map(|pos| {
+                let mut probe = board.clone();
+                let captured = match probe.set(pos.x, pos.y, mover) {
+                    Ok(()) => RulesValidator::find_captures(&probe, &pos).len(),
+                    Err(_) => 0,
+                };
+                (pos, captured)
+            })
+            .collect();
+        candidates.sort_by_key(|(_, captured)| std::cmp::Reverse(*captured));
+        candidates.into_iter().map(|(pos, _)| pos).collect()
+    }
+
+    /// `player` 下一步能提走的对方棋子对数
+    fn capture_threats(board: &Board, player: Player) -> i32 {
+        let mut threats = 0;
+        for x in 0..15 {
+            for y in 0..15 {
+                if !board.is_empty(x, y) || !Self::near_opponent(board, x, y, player) {
+                    continue;
+                }
+                let mut probe = board.clone();
+                if probe.set(x, y, player).is_ok() {
+                    threats += (RulesValidator::find_captures(&probe, &Position { x, y }).len() / 2) as i32;
+                }
+            }
+        }
+        threats
+    }
+
+    /// 相邻位置有对方棋子（提子的必要条件）
+    fn near_opponent(board: &Board, x: usize, y: usize, player: Player) -> bool {
+        let target = match player {
+            Player::Black => Cell::White,
+            Player::White => Cell::Black,
+        };
+        (x.saturating_sub(1)..=(x + 1).min(14))
+            .any(|nx| (y.saturating_sub(1)..=(y + 1).min(14)).any(|ny| board.get(nx, ny).ok() == Some(target)))
+    }
+
+    fn capture_score(pairs: u32) -> i32 {
+        CAPTURE_SCORES[(pairs as usize).min(CAPTURE_SCORES.len() - 1)]
+    }
+
+    fn is_game_over(board: &Board, captures: CaptureCount) -> bool {
+        board.is_full()
+            || captures.black >= CAPTURES_TO_WIN
+            || captures.white >= CAPTURES_TO_WIN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completing_five_ends_the_search() {
+        let mut board = Board::new();
+        for y in 3..7 {
+            board.set(7, y, Player::Black).unwrap();
+        }
+        board.set(7, 2, Player::White).unwrap();
+        board.set(8, 8, Player::White).unwrap();
+
+        let (score, best) = PenteSolver::minimax_until(
+            &board,
+            CaptureCount::default(),
+            3,
+            i32::MIN,
+            i32::MAX,
+            true,
+            Player::Black,
+            None,
+        )
+        .unwrap();
+        assert_eq!(score, WIN_SCORE);
+        assert_eq!(best, Some(Position { x: 7, y: 7 }));
+    }
+}
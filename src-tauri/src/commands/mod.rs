@@ -1,12 +1,226 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
 use tauri::State;
 use serde::{Deserialize, Serialize};
 use chrono::Utc;
 
 use crate::GameState;
 use crate::game::{Position, MoveResult, GameStatus, RulesValidator, Player, Cell};
-use crate::ai::{AIEngine, Difficulty};
+use crate::game::{Board, CaptureCount, GameOutcome, RuleSet, SetupPosition, SetupStone};
+use crate::game::{Annotation, Markup};
+use crate::game::{EditorState, GameReplay, GameTree, ReplayCursor, GameTreeState, NodeId, PositionEditor, PositionNotation};
+use crate::game::{OpeningDecision, OpeningPhase, OpeningRule, OpeningState};
+use crate::game::{ClockSnapshot, EndReason, GameClock, SideClock, TimeControl};
+use crate::game::{canonical_hash, indexed_positions, Rating};
+use crate::ai::{AIEngine, Difficulty, OpeningAction, OpeningAdvisor};
 use crate::game::GameMode;
-use crate::storage::{SavedGame, SavedMove};
+use crate::storage::{Database, PlayerStats, SavedClock, SavedGame, SavedMove, SavedNode, SavedOffer, SavedOpening, SavedPlayer, SavedRatingChange, SavedSetupStone};
+use crate::storage::{AppSettings, PositionMatch, SavedCollection, SavedTag};
+use crate::storage::{exchange, explorer, ExplorerPosition, GameFormat, GamePage, GameQuery, GameRecord, ImportedGame};
+
+fn status_str(status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::InProgress => "in_progress",
+        GameStatus::BlackWin => "black_win",
+        GameStatus::WhiteWin => "white_win",
+        GameStatus::Draw => "draw",
+        GameStatus::Idle => "idle",
+    }
+}
+
+fn parse_status(s: &str) -> GameStatus {
+    match s {
+        "black_win" => GameStatus::BlackWin,
+        "white_win" => GameStatus::WhiteWin,
+        "draw" => GameStatus::Draw,
+        _ => GameStatus::InProgress,
+    }
+}
+
+fn winner_str(status: GameStatus) -> Option<String> {
+    match status {
+        GameStatus::BlackWin => Some("black".to_string()),
+        GameStatus::WhiteWin => Some("white".to_string()),
+        GameStatus::Draw => Some("draw".to_string()),
+        _ => None,
+    }
+}
+
+/// 保存初始局面和变化树中的所有落子记录，返回节点对应的落子记录
+fn store_setup_and_moves(
+    db: &Database,
+    game_id: i64,
+    setup: &SetupPosition,
+    rule_set: RuleSet,
+    tree: &GameTree,
+) -> Result<HashMap<NodeId, SavedNode>, String> {
+    for stone in &setup.stones {
+        let saved_stone = SavedSetupStone {
+            id: None,
+            game_id,
+            player: stone.player.as_str().to_string(),
+            position_x: stone.position.x as i32,
+            position_y: stone.position.y as i32,
+        };
+        db.save_setup_stone(&saved_stone)
+            .map_err(|e| format!("Failed to save setup: {}", e))?;
+    }
+
+    let mut saved = HashMap::new();
+    store_moves(db, game_id, setup, rule_set, tree, &mut saved)?;
+    Ok(saved)
+}
+
+/// 从第 `first` 手起重写主线之后各手的局面索引，用于按局面查找对局
+fn index_positions(
+    db: &Database,
+    game_id: i64,
+    setup: &SetupPosition,
+    rule_set: RuleSet,
+    main_line: &[Position],
+    first: usize,
+) -> Result<(), String> {
+    let positions = indexed_positions(setup.clone(), rule_set, main_line, first);
+    db.save_position_index(game_id, first, &positions)
+        .map_err(|e| format!("Failed to index positions: {}", e))
+}
+
+/// 把变化树同步到数据库：新节点追加保存，树中已删除的节点连同记录一起删除，
+/// 已保存的节点只在主线标记或评注变化时更新。
+/// 返回主线开头沿用上次保存的手数，之后的局面索引需要重写
+fn store_moves(
+    db: &Database,
+    game_id: i64,
+    setup: &SetupPosition,
+    rule_set: RuleSet,
+    tree: &GameTree,
+    saved: &mut HashMap<NodeId, SavedNode>,
+) -> Result<usize, String> {
+    let removed: Vec<NodeId> = saved.keys().copied().filter(|&id| tree.node(id).is_none()).collect();
+    for node_id in removed {
+        if let Some(saved_node) = saved.remove(&node_id) {
+            db.delete_move(saved_node.move_id)
+                .map_err(|e| format!("Failed to delete move: {}", e))?;
+        }
+    }
+
+    let main_line_nodes = tree.main_line_nodes();
+    let unchanged = main_line_nodes
+        .iter()
+        .take_while(|id| saved.get(id).is_some_and(|saved_node| saved_node.is_main_line))
+        .count();
+
+    // 先序保存，上一手总在后续之前
+    let main_line: HashSet<NodeId> = main_line_nodes.into_iter().collect();
+    let mut depths: HashMap<NodeId, usize> = HashMap::new();
+    for node in tree.preorder() {
+        let index = node.parent.and_then(|parent| depths.get(&parent)).map_or(0, |depth| depth + 1);
+        depths.insert(node.id, index);
+        let is_main_line = main_line.contains(&node.id);
+
+        let move_id = match saved.get_mut(&node.id) {
+            Some(saved_node) => {
+                if saved_node.is_main_line != is_main_line {
+                    db.set_move_main_line(saved_node.move_id, is_main_line)
+                        .map_err(|e| format!("Failed to save move: {}", e))?;
+                    saved_node.is_main_line = is_main_line;
+                }
+                if saved_node.notes == node.notes {
+                    continue;
+                }
+                db.delete_notes(saved_node.move_id)
+                    .map_err(|e| format!("Failed to save annotations: {}", e))?;
+                saved_node.notes = node.notes.clone();
+                saved_node.move_id
+            }
+            None => {
+                let player = setup.player_for_move(rule_set, index);
+                let saved_move = SavedMove {
+                    id: None,
+                    game_id,
+                    move_number: (index + 1) as i32,
+                    turn_number: (rule_set.turn_of_move(index) + 1) as i32,
+                    player: player.as_str().to_string(),
+                    position_x: node.position.x as i32,
+                    position_y: node.position.y as i32,
+                    timestamp: Utc::now().timestamp(),
+                    parent_move_id: node.parent.and_then(|parent| saved.get(&parent)).map(|parent| parent.move_id),
+                    is_main_line,
+                };
+                let move_id = db.save_move(&saved_move)
+                    .map_err(|e| format!("Failed to save move: {}", e))?;
+                saved.insert(node.id, SavedNode {
+                    move_id,
+                    is_main_line,
+                    notes: node.notes.clone(),
+                });
+                move_id
+            }
+        };
+
+        let (annotation, markup) = exchange::saved_notes(move_id, &node.notes);
+        if let Some(annotation) = annotation {
+            db.save_annotation(&annotation)
+                .map_err(|e| format!("Failed to save annotation: {}", e))?;
+        }
+        for markup in &markup {
+            db.save_markup(markup)
+                .map_err(|e| format!("Failed to save markup: {}", e))?;
+        }
+    }
+    Ok(unchanged)
+}
+
+/// 当前应该行动（计时）的一方：开局阶段由阶段决定，否则为当前玩家
+fn next_actor(state: &GameState) -> Player {
+    let phase = state.opening.lock().unwrap().phase();
+    match phase {
+        Some(phase) => phase.actor(),
+        None => *state.current_player.lock().unwrap(),
+    }
+}
+
+/// 超时判负，返回新的游戏状态
+fn apply_timeout(state: &GameState, flagged: Player) -> GameStatus {
+    let game_status = match flagged {
+        Player::Black => GameStatus::WhiteWin,
+        Player::White => GameStatus::BlackWin,
+    };
+    *state.game_status.lock().unwrap() = game_status;
+    *state.end_reason.lock().unwrap() = Some(EndReason::Timeout);
+    *state.draw_offer.lock().unwrap() = None;
+    game_status
+}
+
+/// 行动前检查行动方是否已超时
+fn check_flag(state: &GameState) -> Result<(), String> {
+    let flagged = state.clock.lock().unwrap().check_flag(Instant::now());
+    if let Some(flagged) = flagged {
+        if matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress) {
+            apply_timeout(state, flagged);
+            end_game(state);
+        }
+        return Err("Time is up".to_string());
+    }
+    Ok(())
+}
+
+/// 结算本次行动的用时，并为下一位行动者开始计时
+fn switch_clock(state: &GameState) -> Result<(), Player> {
+    let now = Instant::now();
+    let in_progress = matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress);
+    let next = next_actor(state);
+
+    let mut clock = state.clock.lock().unwrap();
+    clock.stop(now)?;
+    if in_progress {
+        clock.start(next, now);
+    }
+    Ok(())
+}
 
 #[tauri::command]
 pub async fn place_stone(
@@ -22,56 +236,136 @@ pub async fn place_stone(
 
     println!("Backend place_stone called: x={}, y={}, current_player={:?}", x, y, current_player);
 
-    // 2. 尝试落子
-    {
-        let mut board = state.board.lock().unwrap();
-        board.set(x, y, current_player)?;
+    if !matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress) {
+        return Err("Game is already over".to_string());
+    }
+
+    // 超时则本步无效，直接判负
+    if check_flag(&state).is_err() {
+        return Ok(MoveResult {
+            success: false,
+            game_status: *state.game_status.lock().unwrap(),
+            winning_line: None,
+            next_player: current_player,
+            end_reason: Some(EndReason::Timeout),
+            captured: Vec::new(),
+        });
     }
 
-    // 3. 检查游戏状态
+    // 2. 开局阶段需要符合开局规则
+    let mut opening = state.opening.lock().unwrap().clone();
+    if !opening.is_finished() {
+        opening.place_stone(&Position { x, y }, current_player)?;
+    }
+
+    // 3. 尝试落子，提子规则下同时提走被夹住的棋子
     let position = Position { x, y };
+    let rule_set = *state.rule_set.lock().unwrap();
+    let captured = {
+        let mut board = state.board.lock().unwrap();
+        board.set(x, y, current_player)?;
+        if rule_set == RuleSet::Pente {
+            RulesValidator::apply_captures(&mut board, &position)
+        } else {
+            Vec::new()
+        }
+    };
+    *state.opening.lock().unwrap() = opening;
+    let capture_pairs = {
+        let mut captures = state.captures.lock().unwrap();
+        *captures.get_mut(current_player) += (captured.len() / 2) as u32;
+        captures.get(current_player)
+    };
+    state.capture_history.lock().unwrap().push(captured.clone());
+
+    // 4. 检查游戏状态
     let winning_line = {
         let board = state.board.lock().unwrap();
-        RulesValidator::check_five_in_row(&board, &position)
+        RulesValidator::check_win(&board, &position, rule_set)
+    };
+    let forbidden = if winning_line.is_none() && rule_set == RuleSet::Renju && current_player == Player::Black {
+        let board = state.board.lock().unwrap();
+        RulesValidator::check_forbidden(&board, &position)
+    } else {
+        None
     };
 
-    let game_status = if winning_line.is_some() {
-        match current_player {
+    let (mut game_status, mut end_reason) = if winning_line.is_some() {
+        let status = match current_player {
             Player::Black => GameStatus::BlackWin,
             Player::White => GameStatus::WhiteWin,
-        }
+        };
+        let reason = if rule_set == RuleSet::Connect6 {
+            EndReason::SixInRow
+        } else {
+            EndReason::FiveInRow
+        };
+        (status, Some(reason))
+    } else if forbidden.is_some() {
+        // 连珠规则下黑棋禁手判负
+        (GameStatus::WhiteWin, Some(EndReason::ForbiddenMove))
+    } else if RulesValidator::wins_by_captures(capture_pairs) {
+        let status = match current_player {
+            Player::Black => GameStatus::BlackWin,
+            Player::White => GameStatus::WhiteWin,
+        };
+        (status, Some(EndReason::Captures))
     } else {
         let board = state.board.lock().unwrap();
         if RulesValidator::is_draw(&board) {
-            GameStatus::Draw
+            (GameStatus::Draw, Some(EndReason::BoardFull))
         } else {
-            GameStatus::InProgress
+            (GameStatus::InProgress, None)
         }
     };
 
-    // 4. 更新状态
+    // 5. 更新状态
     {
         let mut status = state.game_status.lock().unwrap();
         *status = game_status.clone();
     }
+    *state.end_reason.lock().unwrap() = end_reason;
 
-    {
+    let moves_played = {
         let mut history = state.move_history.lock().unwrap();
-        history.push(position);
-    }
+        history.push(position.clone());
+        history.len()
+    };
+    state.move_tree.lock().unwrap().play(position);
+    *state.replay.lock().unwrap() = None;
 
-    // 获取下一个玩家（切换当前玩家）
+    // 落子视为拒绝对方的和棋提议
+    *state.draw_offer.lock().unwrap() = None;
+
+    // 获取下一个玩家（六子棋一回合两子，回合结束才切换）
+    let setup = state.setup.lock().unwrap().clone();
     let next_player = {
         let mut player = state.current_player.lock().unwrap();
-        *player = current_player.opponent();
+        *player = setup.player_for_move(rule_set, moves_played);
         *player
     };
 
+    // 6. 回合结束或对局结束时结算用时
+    let turn_over = next_player != current_player || !matches!(game_status, GameStatus::InProgress);
+    if turn_over {
+        if let Err(flagged) = switch_clock(&state) {
+            game_status = apply_timeout(&state, flagged);
+            end_reason = Some(EndReason::Timeout);
+        }
+    }
+    if matches!(game_status, GameStatus::InProgress) {
+        autosave(&state);
+    } else {
+        end_game(&state);
+    }
+
     Ok(MoveResult {
         success: true,
         game_status,
         winning_line,
         next_player,
+        end_reason,
+        captured,
     })
 }
 
@@ -80,417 +374,2626 @@ pub async fn new_game(state: State<'_, GameState>) -> Result<(), String> {
     let mut board = state.board.lock().unwrap();
     board.clear();
 
+    let mut setup = state.setup.lock().unwrap();
+    *setup = SetupPosition::empty();
+
     let mut player = state.current_player.lock().unwrap();
     *player = Player::Black;
 
     let mut status = state.game_status.lock().unwrap();
     *status = GameStatus::InProgress;
 
+    let mut end_reason = state.end_reason.lock().unwrap();
+    *end_reason = None;
+
+    let mut draw_offer = state.draw_offer.lock().unwrap();
+    *draw_offer = None;
+
     let mut history = state.move_history.lock().unwrap();
     history.clear();
 
-    Ok(())
-}
+    let mut move_tree = state.move_tree.lock().unwrap();
+    *move_tree = GameTree::new();
 
-#[tauri::command]
-pub async fn undo_move(state: State<'_, GameState>) -> Result<(), String> {
-    let last_pos = {
-        let mut history = state.move_history.lock().unwrap();
-        history.pop().ok_or("No moves to undo")?
-    };
+    let mut replay = state.replay.lock().unwrap();
+    *replay = None;
 
-    let mut board = state.board.lock().unwrap();
-    board.clear_cell(last_pos.x, last_pos.y)?;
+    let mut saved_game_id = state.saved_game_id.lock().unwrap();
+    *saved_game_id = None;
 
-    let mut player = state.current_player.lock().unwrap();
-    *player = player.opponent();
+    let mut saved_moves = state.saved_moves.lock().unwrap();
+    saved_moves.clear();
 
-    let mut status = state.game_status.lock().unwrap();
-    *status = GameStatus::InProgress;
+    // 新的对局使用新的自动保存
+    let mut autosave_id = state.autosave_id.lock().unwrap();
+    *autosave_id = None;
 
-    Ok(())
-}
+    let mut autosave_moves = state.autosave_moves.lock().unwrap();
+    autosave_moves.clear();
 
-#[derive(Serialize, Deserialize)]
-pub struct GameConfig {
-    pub mode: String,
-    pub difficulty: String,
+    let mut rated_game_id = state.rated_game_id.lock().unwrap();
+    *rated_game_id = None;
+
+    let mut captures = state.captures.lock().unwrap();
+    *captures = CaptureCount::default();
+
+    let mut capture_history = state.capture_history.lock().unwrap();
+    capture_history.clear();
+
+    let mut opening = state.opening.lock().unwrap();
+    *opening = OpeningState::default();
+
+    let mut ai_player = state.ai_player.lock().unwrap();
+    *ai_player = Player::White;
+
+    // 沿用当前的计时规则
+    let mut clock = state.clock.lock().unwrap();
+    *clock = GameClock::new(clock.control);
+    clock.start(Player::Black, Instant::now());
+
+    Ok(())
 }
 
-/// 开始新游戏（支持模式选择）
 #[tauri::command]
-pub async fn new_game_with_mode(
-    state: State<'_, GameState>,
-    mode: String,
-    difficulty: Option<String>,
-) -> Result<(), String> {
-    // 解析游戏模式
-    let game_mode = match mode.as_str() {
-        "pvp" => GameMode::PvP,
-        "pve" => GameMode::PvE,
-        _ => return Err("Invalid game mode".to_string()),
-    };
+pub async fn undo_move(state: State<'_, GameState>) -> Result<(), String> {
+    // 超时、认输、协议和棋不是由落子结束的，不能通过悔棋恢复
+    if matches!(
+        *state.end_reason.lock().unwrap(),
+        Some(EndReason::Timeout) | Some(EndReason::Resignation) | Some(EndReason::Agreement)
+    ) {
+        return Err("Game has ended and cannot be undone".to_string());
+    }
 
-    // 解析 AI 难度
-    let ai_difficulty = if let Some(diff) = difficulty {
-        match diff.as_str() {
-            "easy" => Difficulty::Easy,
-            "medium" => Difficulty::Medium,
-            "hard" => Difficulty::Hard,
-            _ => Difficulty::Medium,
+    let (last_pos, moves_left) = {
+        let mut history = state.move_history.lock().unwrap();
+        // 开局阶段的棋子（含交换选择）不能悔
+        let opening_stones = state.opening.lock().unwrap().stones_placed;
+        if !history.is_empty() && history.len() <= opening_stones {
+            return Err("Cannot undo opening moves".to_string());
         }
-    } else {
-        Difficulty::Medium
+        let last_pos = history.pop().ok_or("No moves to undo")?;
+        (last_pos, history.len())
     };
+    // 悔掉的棋留在变化树中
+    state.move_tree.lock().unwrap().back();
+    *state.replay.lock().unwrap() = None;
 
-    // 重置游戏状态
+    // 撤回落子并放回本步提走的棋子
+    let captured = state.capture_history.lock().unwrap().pop().unwrap_or_default();
     {
         let mut board = state.board.lock().unwrap();
-        board.clear();
+        let mover = board.remove(last_pos.x, last_pos.y)?;
+        for stone in &captured {
+            board.set(stone.x, stone.y, mover.opponent())?;
+        }
+        let mut captures = state.captures.lock().unwrap();
+        let pairs = captures.get_mut(mover);
+        *pairs = pairs.saturating_sub((captured.len() / 2) as u32);
     }
 
-    {
+    // 六子棋悔掉回合中的第二子时不换人
+    let rule_set = *state.rule_set.lock().unwrap();
+    let setup = state.setup.lock().unwrap().clone();
+    let player_changed = {
         let mut player = state.current_player.lock().unwrap();
-        *player = Player::Black;
-    }
+        let previous = *player;
+        *player = setup.player_for_move(rule_set, moves_left);
+        *player != previous
+    };
 
-    {
+    let was_over = {
         let mut status = state.game_status.lock().unwrap();
+        let was_over = !matches!(*status, GameStatus::InProgress);
         *status = GameStatus::InProgress;
-    }
+        was_over
+    };
+    *state.end_reason.lock().unwrap() = None;
 
-    {
-        let mut history = state.move_history.lock().unwrap();
-        history.clear();
+    // 悔棋不退还用时，棋钟切换到悔棋后应走的一方
+    if player_changed || was_over {
+        if let Err(flagged) = switch_clock(&state) {
+            apply_timeout(&state, flagged);
+            autosave(&state);
+            return Err("Time is up".to_string());
+        }
     }
 
-    {
-        let mut mode = state.game_mode.lock().unwrap();
-        *mode = game_mode;
-    }
+    autosave(&state);
+    Ok(())
+}
 
-    {
-        let mut difficulty = state.ai_difficulty.lock().unwrap();
-        *difficulty = ai_difficulty;
+/// 跳到变化树中的节点，按该节点之前的着法重建棋盘和对局状态
+fn goto_node(state: &GameState, target: Option<NodeId>) -> Result<(), String> {
+    // 与悔棋相同：不是由落子结束的对局不能回退
+    if matches!(
+        *state.end_reason.lock().unwrap(),
+        Some(EndReason::Timeout) | Some(EndReason::Resignation) | Some(EndReason::Agreement)
+    ) {
+        return Err("Game has ended and cannot be navigated".to_string());
     }
+    rebuild_at(state, target)
+}
 
-    // 如果是 PvE 模式，初始化 AI 引擎
-    {
-        let mut ai_engine = state.ai_engine.lock().unwrap();
-        if game_mode == GameMode::PvE {
-            *ai_engine = Some(AIEngine::new(ai_difficulty));
-        } else {
-            *ai_engine = None;
+/// 按变化树中 `target` 之前的着法重建棋盘和对局状态
+fn rebuild_at(state: &GameState, target: Option<NodeId>) -> Result<(), String> {
+    let line = {
+        let tree = state.move_tree.lock().unwrap();
+        if let Some(id) = target {
+            tree.node(id).ok_or("Move not found")?;
         }
+        tree.line_to(target)
+    };
+    let opening = state.opening.lock().unwrap().clone();
+    if !opening.is_finished() || line.len() < opening.stones_placed {
+        return Err("Cannot navigate into the opening".to_string());
     }
 
+    let rule_set = *state.rule_set.lock().unwrap();
+    let setup = state.setup.lock().unwrap().clone();
+    let replay = GameReplay::replay(setup, rule_set, &line)?;
+
+    *state.board.lock().unwrap() = replay.board.clone();
+    *state.move_history.lock().unwrap() = line;
+    *state.captures.lock().unwrap() = replay.captures;
+    *state.capture_history.lock().unwrap() = replay.capture_history.clone();
+    *state.end_reason.lock().unwrap() = replay.end_reason;
+    *state.draw_offer.lock().unwrap() = None;
+    state.move_tree.lock().unwrap().jump(target)?;
+    *state.replay.lock().unwrap() = None;
+
+    let player_changed = {
+        let mut player = state.current_player.lock().unwrap();
+        let previous = *player;
+        *player = replay.next_player();
+        *player != previous
+    };
+    let was_over = {
+        let mut status = state.game_status.lock().unwrap();
+        let was_over = !matches!(*status, GameStatus::InProgress);
+        *status = replay.game_status;
+        was_over
+    };
+
+    // 和悔棋一样不退还用时
+    if player_changed || was_over {
+        if let Err(flagged) = switch_clock(state) {
+            apply_timeout(state, flagged);
+            return Err("Time is up".to_string());
+        }
+    }
     Ok(())
 }
 
-/// 获取 AI 落子
+/// 获取变化树
 #[tauri::command]
-pub async fn get_ai_move(
-    state: State<'_, GameState>,
-) -> Result<Position, String> {
-    let board = state.board.lock().unwrap().clone();
-    let current_player = state.current_player.lock().unwrap();
-    let ai_engine = state.ai_engine.lock().unwrap();
-
-    if let Some(engine) = ai_engine.as_ref() {
-        engine
-            .get_best_move(&board, *current_player)
-            .ok_or_else(|| "AI failed to find a move".to_string())
-    } else {
-        Err("AI engine not initialized".to_string())
-    }
+pub async fn get_move_tree(state: State<'_, GameState>) -> Result<GameTreeState, String> {
+    Ok(state.move_tree.lock().unwrap().state())
 }
 
-/// 获取当前游戏配置
+/// 沿主要后续前进一手
 #[tauri::command]
-pub async fn get_game_config(
-    state: State<'_, GameState>,
-) -> Result<GameConfig, String> {
-    let mode = *state.game_mode.lock().unwrap();
-    let difficulty = *state.ai_difficulty.lock().unwrap();
+pub async fn move_tree_next(state: State<'_, GameState>) -> Result<GameTreeState, String> {
+    let next = {
+        let tree = state.move_tree.lock().unwrap();
+        tree.children(tree.current()).first().copied()
+    };
+    let next = next.ok_or("No next move")?;
+    goto_node(&state, Some(next))?;
+    get_move_tree(state).await
+}
 
-    let config = GameConfig {
-        mode: match mode {
-            GameMode::PvP => "pvp".to_string(),
-            GameMode::PvE => "pve".to_string(),
-        },
-        difficulty: match difficulty {
-            Difficulty::Easy => "easy".to_string(),
-            Difficulty::Medium => "medium".to_string(),
-            Difficulty::Hard => "hard".to_string(),
-        },
+/// 后退一手（保留后续着法）
+#[tauri::command]
+pub async fn move_tree_previous(state: State<'_, GameState>) -> Result<GameTreeState, String> {
+    let parent = {
+        let tree = state.move_tree.lock().unwrap();
+        let current = tree.current().ok_or("No previous move")?;
+        tree.node(current).and_then(|node| node.parent)
     };
+    goto_node(&state, parent)?;
+    get_move_tree(state).await
+}
 
-    Ok(config)
+/// 跳到指定节点，`None` 为初始局面
+#[tauri::command]
+pub async fn move_tree_jump(
+    state: State<'_, GameState>,
+    node_id: Option<NodeId>,
+) -> Result<GameTreeState, String> {
+    goto_node(&state, node_id)?;
+    get_move_tree(state).await
 }
 
+/// 把变化提升为主线
 #[tauri::command]
-pub async fn get_board_state(
+pub async fn promote_variation(
     state: State<'_, GameState>,
-) -> Result<Vec<Vec<String>>, String> {
-    let board = state.board.lock().unwrap();
-    let mut result = Vec::new();
+    node_id: NodeId,
+) -> Result<GameTreeState, String> {
+    state.move_tree.lock().unwrap().promote(node_id)?;
+    get_move_tree(state).await
+}
 
-    for x in 0..15 {
-        let mut row = Vec::new();
-        for y in 0..15 {
-            let cell = board.get(x, y).unwrap();
-            row.push(match cell {
-                Cell::Empty => "empty".to_string(),
-                Cell::Black => "black".to_string(),
-                Cell::White => "white".to_string(),
-            });
-        }
-        result.push(row);
+/// 删除变化（节点及其全部后续）；当前局面在其中时先退回到变化之前
+#[tauri::command]
+pub async fn delete_variation(
+    state: State<'_, GameState>,
+    node_id: NodeId,
+) -> Result<GameTreeState, String> {
+    let (parent, contains_current) = {
+        let tree = state.move_tree.lock().unwrap();
+        let parent = tree.node(node_id).ok_or("Move not found")?.parent;
+        (parent, tree.path_to(tree.current()).contains(&node_id))
+    };
+    if contains_current {
+        goto_node(&state, parent)?;
     }
-
-    Ok(result)
+    state.move_tree.lock().unwrap().delete(node_id)?;
+    get_move_tree(state).await
 }
 
-/// 保存当前游戏
+/// 设置一手棋的文字评注，`None` 为删除
 #[tauri::command]
-pub async fn save_game(
+pub async fn set_move_comment(
     state: State<'_, GameState>,
-    game_name: String,
-) -> Result<i64, String> {
-    let game_mode = *state.game_mode.lock().unwrap();
-    let difficulty = *state.ai_difficulty.lock().unwrap();
-    let game_status = *state.game_status.lock().unwrap();
-    let move_history = state.move_history.lock().unwrap().clone();
+    node_id: NodeId,
+    comment: Option<String>,
+) -> Result<GameTreeState, String> {
+    let comment = comment.filter(|c| !c.trim().is_empty());
+    state.move_tree.lock().unwrap().notes_mut(node_id)?.comment = comment;
+    get_move_tree(state).await
+}
 
-    let status_str = match game_status {
-        GameStatus::InProgress => "in_progress",
-        GameStatus::BlackWin => "black_win",
-        GameStatus::WhiteWin => "white_win",
-        GameStatus::Draw => "draw",
-        GameStatus::Idle => "idle",
-    }.to_string();
+/// 设置一手棋的评注符号（`!`、`?`、`!!`、`??`、`!?`、`?!`），`None` 为删除
+#[tauri::command]
+pub async fn set_move_annotation(
+    state: State<'_, GameState>,
+    node_id: NodeId,
+    annotation: Option<String>,
+) -> Result<GameTreeState, String> {
+    let annotation = match annotation {
+        Some(glyph) => Some(Annotation::parse(&glyph).ok_or("Invalid annotation")?),
+        None => None,
+    };
+    state.move_tree.lock().unwrap().notes_mut(node_id)?.annotation = annotation;
+    get_move_tree(state).await
+}
 
-    let winner = match game_status {
-        GameStatus::BlackWin => Some("black".to_string()),
-        GameStatus::WhiteWin => Some("white".to_string()),
-        GameStatus::Draw => Some("draw".to_string()),
-        _ => None,
+/// 在一手棋上添加棋盘标记（替换同一格上的标记）
+#[tauri::command]
+pub async fn set_move_markup(
+    state: State<'_, GameState>,
+    node_id: NodeId,
+    markup: Markup,
+) -> Result<GameTreeState, String> {
+    state.move_tree.lock().unwrap().notes_mut(node_id)?.set_markup(markup)?;
+    get_move_tree(state).await
+}
+
+/// 删除一手棋上某一格的标记
+#[tauri::command]
+pub async fn remove_move_markup(
+    state: State<'_, GameState>,
+    node_id: NodeId,
+    x: usize,
+    y: usize,
+) -> Result<GameTreeState, String> {
+    state.move_tree.lock().unwrap().notes_mut(node_id)?.remove_markup(&Position { x, y });
+    get_move_tree(state).await
+}
+
+/// 清除一手棋上的全部标记
+#[tauri::command]
+pub async fn clear_move_markup(
+    state: State<'_, GameState>,
+    node_id: NodeId,
+) -> Result<GameTreeState, String> {
+    state.move_tree.lock().unwrap().notes_mut(node_id)?.markup.clear();
+    get_move_tree(state).await
+}
+
+/// 复盘中的一个局面
+#[derive(Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub board: Vec<Vec<String>>,
+    pub move_number: usize,
+    pub total_moves: usize,
+    /// 最后一手（用于高亮）
+    pub last_move: Option<Position>,
+    pub next_player: Player,
+    pub game_status: GameStatus,
+    pub end_reason: Option<EndReason>,
+    pub winning_line: Option<Vec<Position>>,
+    pub captures: CaptureCount,
+}
+
+/// 重放到游标所在的一手
+fn replay_frame(state: &GameState, cursor: &ReplayCursor) -> Result<ReplayFrame, String> {
+    let (line, at_live_position) = {
+        let tree = state.move_tree.lock().unwrap();
+        (tree.line_to(cursor.node()), cursor.node() == tree.current())
     };
+    let rule_set = *state.rule_set.lock().unwrap();
+    let setup = state.setup.lock().unwrap().clone();
+    let replay = GameReplay::replay(setup, rule_set, &line)?;
 
-    let saved_game = SavedGame {
-        id: None,
-        name: game_name,
-        mode: match game_mode {
-            GameMode::PvP => "pvp".to_string(),
-            GameMode::PvE => "pve".to_string(),
-        },
-        difficulty: Some(match difficulty {
-            Difficulty::Easy => "easy".to_string(),
-            Difficulty::Medium => "medium".to_string(),
-            Difficulty::Hard => "hard".to_string(),
-        }),
-        created_at: Utc::now().timestamp(),
-        updated_at: Utc::now().timestamp(),
-        status: status_str,
-        winner,
-        total_moves: move_history.len() as i32,
+    // 认输、超时、协议和棋不由落子决定，在对局当前的局面上沿用实际结果
+    let (game_status, end_reason) = if at_live_position && matches!(replay.game_status, GameStatus::InProgress) {
+        (*state.game_status.lock().unwrap(), *state.end_reason.lock().unwrap())
+    } else {
+        (replay.game_status, replay.end_reason)
     };
 
-    let db = state.database.lock().unwrap();
-    let game_id = db.save_game(&saved_game)
-        .map_err(|e| format!("Failed to save game: {}", e))?;
+    let mut board = Vec::new();
+    for x in 0..15 {
+        let mut row = Vec::new();
+        for y in 0..15 {
+            row.push(match replay.board.get(x, y)? {
+                Cell::Empty => "empty".to_string(),
+                Cell::Black => "black".to_string(),
+                Cell::White => "white".to_string(),
+            });
+        }
+        board.push(row);
+    }
 
-    // 保存所有落子记录
-    for (index, pos) in move_history.iter().enumerate() {
-        let player = if index % 2 == 0 { "black" } else { "white" };
-        let saved_move = SavedMove {
-            id: None,
-            game_id,
-            move_number: (index + 1) as i32,
-            player: player.to_string(),
-            position_x: pos.x as i32,
-            position_y: pos.y as i32,
-            timestamp: Utc::now().timestamp(),
-        };
-        db.save_move(&saved_move)
-            .map_err(|e| format!("Failed to save move: {}", e))?;
+    Ok(ReplayFrame {
+        board,
+        move_number: cursor.index(),
+        total_moves: cursor.len(),
+        last_move: line.last().cloned(),
+        next_player: replay.next_player(),
+        game_status,
+        end_reason,
+        winning_line: replay.winning_line,
+        captures: replay.captures,
+    })
+}
+
+/// 移动复盘游标（还没开始复盘时从对局当前的一手开始）
+fn step_replay(
+    state: &GameState,
+    step: impl FnOnce(&mut ReplayCursor) -> Result<(), String>,
+) -> Result<ReplayFrame, String> {
+    let cursor = {
+        let mut replay = state.replay.lock().unwrap();
+        let cursor = replay.get_or_insert_with(|| ReplayCursor::new(&state.move_tree.lock().unwrap()));
+        step(cursor)?;
+        cursor.clone()
+    };
+    replay_frame(state, &cursor)
+}
+
+/// 获取复盘中的局面
+#[tauri::command]
+pub async fn get_replay_frame(state: State<'_, GameState>) -> Result<ReplayFrame, String> {
+    step_replay(&state, |_| Ok(()))
+}
+
+/// 复盘：下一手
+#[tauri::command]
+pub async fn replay_next(state: State<'_, GameState>) -> Result<ReplayFrame, String> {
+    step_replay(&state, |cursor| cursor.step_forward())
+}
+
+/// 复盘：上一手
+#[tauri::command]
+pub async fn replay_previous(state: State<'_, GameState>) -> Result<ReplayFrame, String> {
+    step_replay(&state, |cursor| cursor.step_back())
+}
+
+/// 复盘：跳到第 N 手之后（0 为初始局面）
+#[tauri::command]
+pub async fn replay_goto(
+    state: State<'_, GameState>,
+    move_number: usize,
+) -> Result<ReplayFrame, String> {
+    step_replay(&state, |cursor| cursor.goto(move_number))
+}
+
+/// 复盘：回到初始局面
+#[tauri::command]
+pub async fn replay_to_start(state: State<'_, GameState>) -> Result<ReplayFrame, String> {
+    step_replay(&state, |cursor| cursor.goto(0))
+}
+
+/// 复盘：到最后一手
+#[tauri::command]
+pub async fn replay_to_end(state: State<'_, GameState>) -> Result<ReplayFrame, String> {
+    step_replay(&state, |cursor| cursor.goto(cursor.len()))
+}
+
+/// 结束复盘，回到对局当前的局面
+#[tauri::command]
+pub async fn exit_replay(state: State<'_, GameState>) -> Result<(), String> {
+    *state.replay.lock().unwrap() = None;
+    Ok(())
+}
+
+/// 从复盘的局面继续下棋，之后的着法成为新的分支
+#[tauri::command]
+pub async fn resume_from_replay(state: State<'_, GameState>) -> Result<(), String> {
+    let cursor = state.replay.lock().unwrap().clone().ok_or("Not replaying a game")?;
+
+    // 认输、超时等结果只属于原来的对局；继续下的分支重新计时
+    *state.end_reason.lock().unwrap() = None;
+    {
+        let mut clock = state.clock.lock().unwrap();
+        *clock = GameClock::new(clock.control);
     }
+    rebuild_at(&state, cursor.node())?;
 
-    Ok(game_id)
+    if matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress) {
+        let next = next_actor(&state);
+        state.clock.lock().unwrap().start(next, Instant::now());
+    }
+    Ok(())
 }
 
-/// 加载游戏返回结果
 #[derive(Serialize, Deserialize)]
-pub struct LoadGameResult {
-    pub board: Vec<Vec<String>>,
-    pub current_player: String,
-    pub game_status: String,
-    pub game_mode: String,
-    pub ai_difficulty: String,
-    pub move_history: Vec<Position>,
+pub struct GameConfig {
+    pub mode: String,
+    pub difficulty: String,
+    pub opening: String,
+    pub ai_player: Player,
+    pub rule_set: RuleSet,
 }
 
-/// 加载游戏
+/// 开始新游戏（支持模式选择）
 #[tauri::command]
-pub async fn load_game(
+pub async fn new_game_with_mode(
     state: State<'_, GameState>,
-    game_id: i64,
-) -> Result<LoadGameResult, String> {
-    let db = state.database.lock().unwrap();
+    mode: String,
+    difficulty: Option<String>,
+    opening: Option<String>,
+    time_control: Option<TimeControl>,
+    rule_set: Option<String>,
+    setup: Option<SetupPosition>,
+) -> Result<(), String> {
+    // 解析游戏模式
+    let game_mode = match mode.as_str() {
+        "pvp" => GameMode::PvP,
+        "pve" => GameMode::PvE,
+        _ => return Err("Invalid game mode".to_string()),
+    };
 
-    // 获取游戏记录
-    let games = db.list_games()
-        .map_err(|e| format!("Failed to load games: {}", e))?;
-    let game = games.into_iter()
-        .find(|g| g.id == Some(game_id))
-        .ok_or("Game not found".to_string())?;
+    // 未指定的选项使用保存的默认设置
+    let settings = {
+        let db = state.database.lock().unwrap();
+        AppSettings::load(&db).unwrap_or_default()
+    };
+    let difficulty = difficulty.or(Some(settings.difficulty));
+    let opening = opening.or(Some(settings.opening));
+    let time_control = time_control.or(Some(settings.time_control));
+    let rule_set = rule_set.or(Some(settings.rule_set));
 
-    // 获取落子记录
-    let moves = db.get_moves(game_id)
-        .map_err(|e| format!("Failed to load moves: {}", e))?;
+    // 解析 AI 难度
+    let ai_difficulty = if let Some(diff) = difficulty {
+        match diff.as_str() {
+            "easy" => Difficulty::Easy,
+            "medium" => Difficulty::Medium,
+            "hard" => Difficulty::Hard,
+            _ => Difficulty::Medium,
+        }
+    } else {
+        Difficulty::Medium
+    };
 
-    // 重置棋盘
-    {
-        let mut board = state.board.lock().unwrap();
-        board.clear();
+    // 解析开局规则
+    let opening_rule = match opening {
+        Some(rule) => OpeningRule::parse(&rule).ok_or("Invalid opening rule")?,
+        None => OpeningRule::Standard,
+    };
+
+    // 解析规则
+    let rule_set = match rule_set {
+        Some(rule_set) => RuleSet::parse(&rule_set).ok_or("Invalid rule set")?,
+        None => RuleSet::Freestyle,
+    };
+    if rule_set == RuleSet::Connect6 && opening_rule != OpeningRule::Standard {
+        return Err("Connect6 does not support opening rules".to_string());
     }
 
-    // 重新下所有的棋子
-    for move_data in &moves {
-        let player = match move_data.player.as_str() {
-            "black" => Player::Black,
-            "white" => Player::White,
-            _ => continue,
-        };
+    // 初始局面（让子或自定义摆子）
+    let setup = setup.unwrap_or_default();
+    if !setup.is_empty() && (opening_rule != OpeningRule::Standard || rule_set == RuleSet::Connect6) {
+        return Err("Setup positions can only be used with the standard opening".to_string());
+    }
+    let mut start_board = Board::new();
+    setup.apply(&mut start_board)?;
 
+    // 重置游戏状态
+    {
         let mut board = state.board.lock().unwrap();
-        board.set(move_data.position_x as usize, move_data.position_y as usize, player)
-            .map_err(|e| format!("Failed to replay move: {}", e))?;
+        *board = start_board;
     }
 
-    // 恢复游戏状态
-    let current_player = if moves.len() % 2 == 0 {
-        Player::Black
-    } else {
-        Player::White
-    };
     {
-        let mut cp = state.current_player.lock().unwrap();
-        *cp = current_player;
+        let mut player = state.current_player.lock().unwrap();
+        *player = setup.to_move;
     }
 
-    let game_status = match game.status.as_str() {
-        "in_progress" | "idle" => GameStatus::InProgress,
-        "black_win" => GameStatus::BlackWin,
-        "white_win" => GameStatus::WhiteWin,
-        "draw" => GameStatus::Draw,
-        _ => GameStatus::InProgress,
-    };
     {
-        let mut gs = state.game_status.lock().unwrap();
-        *gs = game_status.clone();
+        let mut status = state.game_status.lock().unwrap();
+        *status = GameStatus::InProgress;
     }
+    *state.end_reason.lock().unwrap() = None;
+    *state.draw_offer.lock().unwrap() = None;
+
+    {
+        let mut history = state.move_history.lock().unwrap();
+        history.clear();
+    }
+    *state.move_tree.lock().unwrap() = GameTree::new();
+    *state.replay.lock().unwrap() = None;
+    *state.saved_game_id.lock().unwrap() = None;
+    state.saved_moves.lock().unwrap().clear();
+    *state.autosave_id.lock().unwrap() = None;
+    state.autosave_moves.lock().unwrap().clear();
+    *state.rated_game_id.lock().unwrap() = None;
+    *state.captures.lock().unwrap() = CaptureCount::default();
+    state.capture_history.lock().unwrap().clear();
 
-    let game_mode = match game.mode.as_str() {
-        "pvp" => GameMode::PvP,
-        "pve" => GameMode::PvE,
-        _ => GameMode::PvP,
-    };
     {
         let mut mode = state.game_mode.lock().unwrap();
         *mode = game_mode;
     }
 
-    // 解析 AI 难度
-    let ai_difficulty = game.difficulty.as_deref().unwrap_or("medium");
-    let difficulty = match ai_difficulty {
-        "easy" => Difficulty::Easy,
-        "medium" => Difficulty::Medium,
-        "hard" => Difficulty::Hard,
-        _ => Difficulty::Medium,
-    };
     {
-        let mut diff = state.ai_difficulty.lock().unwrap();
-        *diff = difficulty;
+        let mut current_rule_set = state.rule_set.lock().unwrap();
+        *current_rule_set = rule_set;
+    }
+
+    {
+        let mut difficulty = state.ai_difficulty.lock().unwrap();
+        *difficulty = ai_difficulty;
+    }
+
+    {
+        let mut opening = state.opening.lock().unwrap();
+        *opening = OpeningState::new(opening_rule);
+    }
+
+    {
+        let mut ai_player = state.ai_player.lock().unwrap();
+        *ai_player = settings.ai_player;
+    }
+
+    // 设置棋钟，第一位行动者开始计时
+    {
+        let mut clock = state.clock.lock().unwrap();
+        *clock = GameClock::new(time_control.unwrap_or(TimeControl::Unlimited));
+        clock.start(setup.to_move, Instant::now());
     }
+    *state.setup.lock().unwrap() = setup;
 
     // 如果是 PvE 模式，初始化 AI 引擎
     {
         let mut ai_engine = state.ai_engine.lock().unwrap();
         if game_mode == GameMode::PvE {
-            *ai_engine = Some(AIEngine::new(difficulty));
+            let mut engine = AIEngine::new(ai_difficulty);
+            engine.set_rule_set(rule_set);
+            *ai_engine = Some(engine);
         } else {
             *ai_engine = None;
         }
     }
 
-    let move_history: Vec<Position> = moves.iter().map(|m| Position {
-        x: m.position_x as usize,
-        y: m.position_y as usize,
-    }).collect();
-    {
-        let mut history = state.move_history.lock().unwrap();
-        *history = move_history.clone();
-    }
-
-    // 获取棋盘状态
-    let board_state = {
-        let board = state.board.lock().unwrap();
-        let mut result = Vec::new();
-        for x in 0..15 {
-            let mut row = Vec::new();
-            for y in 0..15 {
-                let cell = board.get(x, y).unwrap();
-                row.push(match cell {
-                    Cell::Empty => "empty".to_string(),
-                    Cell::Black => "black".to_string(),
-                    Cell::White => "white".to_string(),
-                });
-            }
-            result.push(row);
-        }
-        result
-    };
-
-    Ok(LoadGameResult {
-        board: board_state,
-        current_player: match current_player {
-            Player::Black => "black".to_string(),
-            Player::White => "white".to_string(),
-        },
-        game_status: match game_status {
-            GameStatus::InProgress => "playing".to_string(),
-            GameStatus::BlackWin => "black_win".to_string(),
-            GameStatus::WhiteWin => "white_win".to_string(),
-            GameStatus::Draw => "draw".to_string(),
-            GameStatus::Idle => "idle".to_string(),
-        },
-        game_mode: match game_mode {
-            GameMode::PvP => "pvp".to_string(),
-            GameMode::PvE => "pve".to_string(),
-        },
-        ai_difficulty: ai_difficulty.to_string(),
-        move_history,
-    })
+    Ok(())
 }
 
-/// 获取保存的游戏列表
+/// 获取 AI 落子
 #[tauri::command]
-pub async fn list_saved_games(
+pub async fn get_ai_move(
     state: State<'_, GameState>,
-) -> Result<Vec<SavedGame>, String> {
-    let db = state.database.lock().unwrap();
-    db.list_games()
-        .map_err(|e| format!("Failed to list games: {}", e))
-}
+) -> Result<Position, String> {
+    let board = state.board.lock().unwrap().clone();
+    let current_player = state.current_player.lock().unwrap();
+    let ai_engine = state.ai_engine.lock().unwrap();
+
+    // 开局阶段按开局规则落子
+    let opening = state.opening.lock().unwrap().clone();
+    match opening.phase() {
+        Some(OpeningPhase::PlaceStone { stone, zone, .. }) => {
+            return OpeningAdvisor::balanced_stone(&board, stone, zone)
+                .ok_or_else(|| "AI failed to find a move".to_string());
+        }
+        Some(OpeningPhase::ChooseOffer { by }) => {
+            return OpeningAdvisor::choose_offer(&board, &opening.offers, by)
+                .ok_or_else(|| "AI failed to find a move".to_string());
+        }
+        Some(_) => return Err("Waiting for an opening decision".to_string()),
+        None => {}
+    }
+
+    // 根据 AI 剩余用时分配思考时间
+    let budget = state.clock.lock().unwrap().time_budget(*current_player, Instant::now());
+    let captures = *state.captures.lock().unwrap();
+
+    if let Some(engine) = ai_engine.as_ref() {
+        engine.get_best_move_within(&board, *current_player, captures, budget)
+            .ok_or_else(|| "AI failed to find a move".to_string())
+    } else {
+        Err("AI engine not initialized".to_string())
+    }
+}
+
+/// 六子棋：获取 AI 本回合剩余的全部落子（由前端依次调用 place_stone）
+#[tauri::command]
+pub async fn get_ai_turn(
+    state: State<'_, GameState>,
+) -> Result<Vec<Position>, String> {
+    if *state.rule_set.lock().unwrap() != RuleSet::Connect6 {
+        return get_ai_move(state).await.map(|position| vec![position]);
+    }
+
+    let board = state.board.lock().unwrap().clone();
+    let current_player = *state.current_player.lock().unwrap();
+    let budget = state.clock.lock().unwrap().time_budget(current_player, Instant::now());
+    let ai_engine = state.ai_engine.lock().unwrap();
+    let engine = ai_engine.as_ref().ok_or("AI engine not initialized")?;
+
+    let stones = engine.get_best_stones(&board, current_player, budget);
+    if stones.is_empty() {
+        return Err("AI failed to find a move".to_string());
+    }
+    Ok(stones)
+}
+
+/// 获取当前游戏配置
+#[tauri::command]
+pub async fn get_game_config(
+    state: State<'_, GameState>,
+) -> Result<GameConfig, String> {
+    let mode = *state.game_mode.lock().unwrap();
+    let difficulty = *state.ai_difficulty.lock().unwrap();
+    let opening = state.opening.lock().unwrap().rule;
+    let ai_player = *state.ai_player.lock().unwrap();
+    let rule_set = *state.rule_set.lock().unwrap();
+
+    let config = GameConfig {
+        mode: match mode {
+            GameMode::PvP => "pvp".to_string(),
+            GameMode::PvE => "pve".to_string(),
+        },
+        difficulty: match difficulty {
+            Difficulty::Easy => "easy".to_string(),
+            Difficulty::Medium => "medium".to_string(),
+            Difficulty::Hard => "hard".to_string(),
+        },
+        opening: opening.as_str().to_string(),
+        ai_player,
+        rule_set,
+    };
+
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn get_board_state(
+    state: State<'_, GameState>,
+) -> Result<Vec<Vec<String>>, String> {
+    let board = state.board.lock().unwrap();
+    let mut result = Vec::new();
+
+    for x in 0..15 {
+        let mut row = Vec::new();
+        for y in 0..15 {
+            let cell = board.get(x, y).unwrap();
+            row.push(match cell {
+                Cell::Empty => "empty".to_string(),
+                Cell::Black => "black".to_string(),
+                Cell::White => "white".to_string(),
+            });
+        }
+        result.push(row);
+    }
+
+    Ok(result)
+}
+
+/// 自动保存记录的名称
+const AUTOSAVE_NAME: &str = "Autosave";
+
+/// 把当前对局写入数据库，`existing_id` 存在时原地更新
+fn write_game(
+    state: &GameState,
+    game_name: String,
+    existing_id: Option<i64>,
+    mut saved_moves: HashMap<NodeId, SavedNode>,
+    autosave: bool,
+) -> Result<(i64, HashMap<NodeId, SavedNode>), String> {
+    let game_mode = *state.game_mode.lock().unwrap();
+    let difficulty = *state.ai_difficulty.lock().unwrap();
+    let game_status = *state.game_status.lock().unwrap();
+    let move_history = state.move_history.lock().unwrap().clone();
+    let move_tree = state.move_tree.lock().unwrap().clone();
+    let opening = state.opening.lock().unwrap().clone();
+    let rule_set = *state.rule_set.lock().unwrap();
+    let end_reason = *state.end_reason.lock().unwrap();
+    let captures = *state.captures.lock().unwrap();
+    let setup = state.setup.lock().unwrap().clone();
+
+    let saved_game = SavedGame {
+        id: None,
+        name: game_name,
+        mode: match game_mode {
+            GameMode::PvP => "pvp".to_string(),
+            GameMode::PvE => "pve".to_string(),
+        },
+        difficulty: Some(match difficulty {
+            Difficulty::Easy => "easy".to_string(),
+            Difficulty::Medium => "medium".to_string(),
+            Difficulty::Hard => "hard".to_string(),
+        }),
+        created_at: Utc::now().timestamp(),
+        updated_at: Utc::now().timestamp(),
+        status: status_str(game_status).to_string(),
+        winner: winner_str(game_status),
+        total_moves: move_history.len() as i32,
+        rule_set: rule_set.as_str().to_string(),
+        end_reason: end_reason.map(|r| r.as_str().to_string()),
+        black_captures: captures.black as i32,
+        white_captures: captures.white as i32,
+        first_player: setup.to_move.as_str().to_string(),
+        black_player_id: *state.black_player_id.lock().unwrap(),
+        white_player_id: *state.white_player_id.lock().unwrap(),
+        is_rated: false,
+        is_favorite: false,
+    };
+
+    let db = state.database.lock().unwrap();
+    let mut saved_game = saved_game;
+    // PvE 模式下 AI 一方记为对应难度的 AI 档案
+    if game_mode == GameMode::PvE {
+        let ai_id = saved_game.difficulty.as_deref()
+            .map(|difficulty| db.ai_player_id(difficulty))
+            .transpose()
+            .map_err(|e| format!("Failed to save game: {}", e))?
+            .flatten();
+        match *state.ai_player.lock().unwrap() {
+            Player::Black => saved_game.black_player_id = ai_id,
+            Player::White => saved_game.white_player_id = ai_id,
+        }
+    }
+
+    let tx = db.transaction()
+        .map_err(|e| format!("Failed to save game: {}", e))?;
+
+    let updated_id = match existing_id {
+        Some(game_id) => db.update_game(game_id, &saved_game)
+            .map_err(|e| format!("Failed to save game: {}", e))?
+            .then_some(game_id),
+        None => None,
+    };
+    let (game_id, indexed) = match updated_id {
+        Some(game_id) => {
+            let indexed = store_moves(&db, game_id, &setup, rule_set, &move_tree, &mut saved_moves)?;
+            db.delete_offers(game_id)
+                .map_err(|e| format!("Failed to save offer: {}", e))?;
+            (game_id, indexed)
+        }
+        None => {
+            let game_id = db.save_game(&saved_game)
+                .map_err(|e| format!("Failed to save game: {}", e))?;
+            saved_moves = store_setup_and_moves(&db, game_id, &setup, rule_set, &move_tree)?;
+            // 新的自动保存取代之前的
+            if autosave {
+                db.make_autosave(game_id)
+                    .map_err(|e| format!("Failed to save game: {}", e))?;
+            }
+            (game_id, 0)
+        }
+    };
+    index_positions(&db, game_id, &setup, rule_set, &move_tree.main_line(), indexed)?;
+    let current_move_id = move_tree.current().and_then(|id| saved_moves.get(&id)).map(|node| node.move_id);
+    db.set_current_move(game_id, current_move_id)
+        .map_err(|e| format!("Failed to save game: {}", e))?;
+
+    // 保存棋钟状态
+    let clock = state.clock.lock().unwrap().snapshot(Instant::now());
+    if clock.control != TimeControl::Unlimited {
+        let saved_clock = SavedClock {
+            game_id,
+            time_control: serde_json::to_string(&clock.control)
+                .map_err(|e| format!("Failed to save clock: {}", e))?,
+            black_remaining_ms: clock.black.remaining_ms as i64,
+            black_periods: clock.black.periods_left as i32,
+            white_remaining_ms: clock.white.remaining_ms as i64,
+            white_periods: clock.white.periods_left as i32,
+            flagged: clock.flagged.map(|p| p.as_str().to_string()),
+        };
+        db.save_clock(&saved_clock)
+            .map_err(|e| format!("Failed to save clock: {}", e))?;
+    }
+
+    // 保存开局状态，加载时从同一阶段继续
+    if opening.rule != OpeningRule::Standard {
+        let saved_opening = SavedOpening {
+            game_id,
+            rule: opening.rule.as_str().to_string(),
+            state: serde_json::to_string(&opening)
+                .map_err(|e| format!("Failed to save opening: {}", e))?,
+        };
+        db.save_opening(&saved_opening)
+            .map_err(|e| format!("Failed to save opening: {}", e))?;
+    }
+
+    // 保存五手打点（包括未被选中的候选）
+    if let Some(chosen) = &opening.chosen_offer {
+        let move_number = opening.stones_placed as i32;
+        for offer in &opening.offers {
+            let saved_offer = SavedOffer {
+                id: None,
+                game_id,
+                move_number,
+                position_x: offer.x as i32,
+                position_y: offer.y as i32,
+                chosen: offer.x == chosen.x && offer.y == chosen.y,
+            };
+            db.save_offer(&saved_offer)
+                .map_err(|e| format!("Failed to save offer: {}", e))?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to save game: {}", e))?;
+    Ok((game_id, saved_moves))
+}
+
+/// 保存当前游戏
+#[tauri::command]
+pub async fn save_game(
+    state: State<'_, GameState>,
+    game_name: String,
+    save_as_copy: Option<bool>,
+) -> Result<i64, String> {
+    // 同一盘棋再次保存时更新原来的记录，另存为副本时新建
+    let (existing_id, saved_moves) = if save_as_copy.unwrap_or(false) {
+        (None, HashMap::new())
+    } else {
+        (*state.saved_game_id.lock().unwrap(), state.saved_moves.lock().unwrap().clone())
+    };
+
+    let (game_id, saved_moves) = write_game(&state, game_name, existing_id, saved_moves, false)?;
+    *state.saved_game_id.lock().unwrap() = Some(game_id);
+    *state.saved_moves.lock().unwrap() = saved_moves;
+    Ok(game_id)
+}
+
+/// 自动保存到单独的记录（不改动手动保存的棋谱），失败原因由前端通过 take_autosave_error 取走
+fn autosave(state: &GameState) {
+    let existing_id = *state.autosave_id.lock().unwrap();
+    let saved_moves = state.autosave_moves.lock().unwrap().clone();
+    match write_game(state, AUTOSAVE_NAME.to_string(), existing_id, saved_moves, true) {
+        Ok((game_id, saved_moves)) => {
+            *state.autosave_id.lock().unwrap() = Some(game_id);
+            *state.autosave_moves.lock().unwrap() = saved_moves;
+        }
+        Err(e) => *state.autosave_error.lock().unwrap() = Some(format!("Autosave failed: {}", e)),
+    }
+}
+
+/// 对局结束：自动保存后为计分对局记录结果
+fn end_game(state: &GameState) {
+    autosave(state);
+    let Some(autosave_id) = *state.autosave_id.lock().unwrap() else {
+        return;
+    };
+    if let Err(e) = record_rated_game(state, autosave_id) {
+        *state.autosave_error.lock().unwrap() = Some(e);
+    }
+}
+
+/// 计分对局结束时留下正式棋谱并更新双方等级分
+fn record_rated_game(state: &GameState, autosave_id: i64) -> Result<(), String> {
+    if !*state.rated.lock().unwrap()
+        || state.rated_game_id.lock().unwrap().is_some()
+        || matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress)
+    {
+        return Ok(());
+    }
+
+    let name = {
+        let db = state.database.lock().unwrap();
+        let game = db.get_game(autosave_id)
+            .map_err(|e| format!("Failed to load game: {}", e))?
+            .ok_or("Game not found")?;
+        let (Some(black_id), Some(white_id)) = (game.black_player_id, game.white_player_id) else {
+            return Ok(());
+        };
+        let name_of = |player_id| -> Result<String, String> {
+            Ok(db.get_player(player_id)
+                .map_err(|e| format!("Failed to load player: {}", e))?
+                .ok_or("Player not found")?
+                .name)
+        };
+        format!("{} vs {}", name_of(black_id)?, name_of(white_id)?)
+    };
+
+    let saved_game_id = *state.saved_game_id.lock().unwrap();
+    let (game_id, saved_moves, name) = match saved_game_id {
+        Some(saved_id) => {
+            let name = state.database.lock().unwrap().get_game(saved_id)
+                .map_err(|e| format!("Failed to load game: {}", e))?
+                .map_or(name, |game| game.name);
+            let saved_moves = state.saved_moves.lock().unwrap().clone();
+            let (game_id, saved_moves) = write_game(state, name.clone(), Some(saved_id), saved_moves, false)?;
+            (game_id, saved_moves, name)
+        }
+        None => (autosave_id, state.autosave_moves.lock().unwrap().clone(), name),
+    };
+
+    {
+        let db = state.database.lock().unwrap();
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to rate game: {}", e))?;
+        db.mark_rated_game(game_id, &name)
+            .and_then(|_| db.rate_game(game_id))
+            .map_err(|e| format!("Failed to rate game: {}", e))?;
+        if game_id != autosave_id {
+            db.delete_game(autosave_id)
+                .map_err(|e| format!("Failed to rate game: {}", e))?;
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to rate game: {}", e))?;
+    }
+
+    // 之后手动保存时更新这条棋谱
+    *state.rated_game_id.lock().unwrap() = Some(game_id);
+    *state.saved_game_id.lock().unwrap() = Some(game_id);
+    *state.saved_moves.lock().unwrap() = saved_moves;
+    *state.autosave_id.lock().unwrap() = None;
+    state.autosave_moves.lock().unwrap().clear();
+    Ok(())
+}
+
+/// 取走最近一次自动保存或计分的错误（没有错误时为 None）
+#[tauri::command]
+pub async fn take_autosave_error(state: State<'_, GameState>) -> Result<Option<String>, String> {
+    Ok(state.autosave_error.lock().unwrap().take())
+}
+
+/// 启动时发现的未结束的自动保存
+#[tauri::command]
+pub async fn get_resumable_game(state: State<'_, GameState>) -> Result<Option<SavedGame>, String> {
+    let Some(game_id) = *state.resumable_game.lock().unwrap() else {
+        return Ok(None);
+    };
+    let db = state.database.lock().unwrap();
+    db.get_game(game_id)
+        .map_err(|e| format!("Failed to load game: {}", e))
+}
+
+/// 恢复上次未结束的对局（程序关闭或崩溃前的自动保存）
+#[tauri::command]
+pub async fn resume_last_game(state: State<'_, GameState>) -> Result<LoadGameResult, String> {
+    let game_id = state.resumable_game.lock().unwrap().take().ok_or("No game to resume")?;
+    let result = load_game(state.clone(), game_id).await?;
+
+    // 继续写入这条自动保存，而不是当作手动保存的棋谱
+    let saved_moves = std::mem::take(&mut *state.saved_moves.lock().unwrap());
+    *state.saved_game_id.lock().unwrap() = None;
+    *state.autosave_id.lock().unwrap() = Some(game_id);
+    *state.autosave_moves.lock().unwrap() = saved_moves;
+    Ok(result)
+}
+
+/// 加载游戏返回结果
+#[derive(Serialize, Deserialize)]
+pub struct LoadGameResult {
+    pub board: Vec<Vec<String>>,
+    pub current_player: String,
+    pub game_status: String,
+    pub game_mode: String,
+    pub ai_difficulty: String,
+    pub move_history: Vec<Position>,
+    pub offers: Vec<SavedOffer>,
+    pub captures: CaptureCount,
+    pub setup: SetupPosition,
+}
+
+/// 加载游戏
+#[tauri::command]
+pub async fn load_game(
+    state: State<'_, GameState>,
+    game_id: i64,
+) -> Result<LoadGameResult, String> {
+    let db = state.database.lock().unwrap();
+
+    // 获取游戏记录
+    let game = db.get_game(game_id)
+        .map_err(|e| format!("Failed to load game: {}", e))?
+        .ok_or("Game not found".to_string())?;
+
+    // 获取落子记录
+    let moves = db.get_moves(game_id)
+        .map_err(|e| format!("Failed to load moves: {}", e))?;
+    let offers = db.get_offers(game_id)
+        .map_err(|e| format!("Failed to load offers: {}", e))?;
+
+    // 恢复初始局面
+    let setup_stones = db.get_setup_stones(game_id)
+        .map_err(|e| format!("Failed to load setup: {}", e))?;
+    let setup = SetupPosition {
+        stones: setup_stones.iter().filter_map(|stone| Some(SetupStone {
+            position: Position {
+                x: stone.position_x as usize,
+                y: stone.position_y as usize,
+            },
+            player: Player::parse(&stone.player)?,
+        })).collect(),
+        to_move: Player::parse(&game.first_player).unwrap_or(Player::Black),
+    };
+    {
+        let mut board = state.board.lock().unwrap();
+        board.clear();
+        setup.apply(&mut board)
+            .map_err(|e| format!("Failed to restore setup: {}", e))?;
+    }
+    *state.setup.lock().unwrap() = setup.clone();
+
+    // 恢复变化树，回到保存时所在的一手
+    let annotations = db.get_annotations(game_id)
+        .map_err(|e| format!("Failed to load annotations: {}", e))?;
+    let markup = db.get_markup(game_id)
+        .map_err(|e| format!("Failed to load markup: {}", e))?;
+    let mut move_tree = exchange::move_tree(&moves)?;
+    exchange::attach_notes(&mut move_tree, &moves, &annotations, &markup)?;
+    let current_move_id = db.get_current_move(game_id)
+        .map_err(|e| format!("Failed to load game: {}", e))?;
+    let current = match current_move_id {
+        Some(move_id) => moves.iter().position(|m| m.id == Some(move_id)),
+        // 旧存档没有记录当前节点，按手数回到主线上的一手
+        None => {
+            let main_line = move_tree.main_line_nodes();
+            match game.total_moves {
+                n if n <= 0 => None,
+                n => main_line.get(n as usize - 1).or(main_line.last()).copied(),
+            }
+        }
+    };
+    move_tree.jump(current)?;
+    let move_history = move_tree.current_line();
+
+    // 重新下到当前节点（提子规则下同时重新提子）
+    let rule_set = RuleSet::parse(&game.rule_set).unwrap_or(RuleSet::Freestyle);
+    let mut captures = CaptureCount::default();
+    let mut capture_history = Vec::new();
+    for (index, position) in move_history.iter().enumerate() {
+        let player = setup.player_for_move(rule_set, index);
+        let mut board = state.board.lock().unwrap();
+        board.set(position.x, position.y, player)
+            .map_err(|e| format!("Failed to replay move: {}", e))?;
+        let captured = if rule_set == RuleSet::Pente {
+            RulesValidator::apply_captures(&mut board, position)
+        } else {
+            Vec::new()
+        };
+        *captures.get_mut(player) += (captured.len() / 2) as u32;
+        capture_history.push(captured);
+    }
+    *state.captures.lock().unwrap() = captures;
+    *state.capture_history.lock().unwrap() = capture_history;
+
+    // 恢复游戏状态
+    let current_player = setup.player_for_move(rule_set, move_history.len());
+    {
+        let mut cp = state.current_player.lock().unwrap();
+        *cp = current_player;
+    }
+
+    let game_status = parse_status(&game.status);
+    {
+        let mut gs = state.game_status.lock().unwrap();
+        *gs = game_status.clone();
+    }
+
+    let game_mode = match game.mode.as_str() {
+        "pvp" => GameMode::PvP,
+        "pve" => GameMode::PvE,
+        _ => GameMode::PvP,
+    };
+    {
+        let mut mode = state.game_mode.lock().unwrap();
+        *mode = game_mode;
+    }
+
+    {
+        let mut current_rule_set = state.rule_set.lock().unwrap();
+        *current_rule_set = rule_set;
+    }
+    *state.draw_offer.lock().unwrap() = None;
+
+    // 解析 AI 难度
+    let ai_difficulty = game.difficulty.as_deref().unwrap_or("medium");
+    let difficulty = match ai_difficulty {
+        "easy" => Difficulty::Easy,
+        "medium" => Difficulty::Medium,
+        "hard" => Difficulty::Hard,
+        _ => Difficulty::Medium,
+    };
+    {
+        let mut diff = state.ai_difficulty.lock().unwrap();
+        *diff = difficulty;
+    }
+
+    // 恢复开局状态；没有记录的旧存档视为开局已结束，只恢复五手打点记录
+    let saved_opening = db.get_opening(game_id)
+        .map_err(|e| format!("Failed to load opening: {}", e))?;
+    {
+        let mut opening = state.opening.lock().unwrap();
+        *opening = match &saved_opening {
+            Some(saved) => serde_json::from_str(&saved.state)
+                .map_err(|e| format!("Invalid opening state: {}", e))?,
+            None => OpeningState::finished(OpeningRule::Standard, 0),
+        };
+        if let (None, Some(chosen)) = (&saved_opening, offers.iter().find(|o| o.chosen)) {
+            opening.stones_placed = chosen.move_number as usize;
+            opening.chosen_offer = Some(Position {
+                x: chosen.position_x as usize,
+                y: chosen.position_y as usize,
+            });
+            opening.offers = offers.iter().map(|o| Position {
+                x: o.position_x as usize,
+                y: o.position_y as usize,
+            }).collect();
+        }
+    }
+    // 恢复双方玩家档案；执黑的是 AI 档案时 AI 执黑
+    let black_is_ai = game.black_player_id
+        .map(|player_id| db.get_player(player_id))
+        .transpose()
+        .map_err(|e| format!("Failed to load players: {}", e))?
+        .flatten()
+        .is_some_and(|player| player.kind == "ai");
+    {
+        let mut ai_player = state.ai_player.lock().unwrap();
+        *ai_player = if black_is_ai { Player::Black } else { Player::White };
+    }
+    *state.black_player_id.lock().unwrap() = game.black_player_id;
+    *state.white_player_id.lock().unwrap() = game.white_player_id;
+
+    // 恢复棋钟，未结束的对局继续为当前玩家计时
+    let saved_clock = db.get_clock(game_id)
+        .map_err(|e| format!("Failed to load clock: {}", e))?;
+    let flagged = saved_clock.as_ref().and_then(|c| c.flagged.as_deref()).and_then(Player::parse);
+    {
+        let mut clock = state.clock.lock().unwrap();
+        *clock = match &saved_clock {
+            Some(saved) => {
+                let control: TimeControl = serde_json::from_str(&saved.time_control)
+                    .map_err(|e| format!("Invalid time control: {}", e))?;
+                GameClock::from_snapshot(&ClockSnapshot {
+                    control,
+                    black: SideClock {
+                        remaining_ms: saved.black_remaining_ms as u64,
+                        periods_left: saved.black_periods as u32,
+                    },
+                    white: SideClock {
+                        remaining_ms: saved.white_remaining_ms as u64,
+                        periods_left: saved.white_periods as u32,
+                    },
+                    running: None,
+                    flagged,
+                })
+            }
+            None => GameClock::default(),
+        };
+        if matches!(game_status, GameStatus::InProgress) {
+            clock.start(next_actor(&state), Instant::now());
+        }
+    }
+
+    // 旧存档没有记录结束原因，根据结果推断
+    let end_reason = match game_status {
+        _ if game.end_reason.is_some() => game.end_reason.as_deref().and_then(EndReason::parse),
+        GameStatus::BlackWin | GameStatus::WhiteWin if flagged.is_some() => Some(EndReason::Timeout),
+        GameStatus::BlackWin | GameStatus::WhiteWin => Some(EndReason::FiveInRow),
+        GameStatus::Draw => Some(EndReason::BoardFull),
+        _ => None,
+    };
+    *state.end_reason.lock().unwrap() = end_reason;
+
+    // 如果是 PvE 模式，初始化 AI 引擎
+    {
+        let mut ai_engine = state.ai_engine.lock().unwrap();
+        if game_mode == GameMode::PvE {
+            let mut engine = AIEngine::new(difficulty);
+            engine.set_rule_set(rule_set);
+            *ai_engine = Some(engine);
+        } else {
+            *ai_engine = None;
+        }
+    }
+
+    {
+        let mut history = state.move_history.lock().unwrap();
+        *history = move_history.clone();
+    }
+    // 之后保存时更新这盘棋，节点编号即落子记录的下标
+    *state.saved_moves.lock().unwrap() = moves
+        .iter()
+        .enumerate()
+        .filter_map(|(index, m)| {
+            let saved_node = SavedNode {
+                move_id: m.id?,
+                is_main_line: m.is_main_line,
+                notes: move_tree.node(index)?.notes.clone(),
+            };
+            Some((index, saved_node))
+        })
+        .collect();
+    *state.move_tree.lock().unwrap() = move_tree;
+    *state.replay.lock().unwrap() = None;
+    *state.saved_game_id.lock().unwrap() = Some(game_id);
+    *state.autosave_id.lock().unwrap() = None;
+    state.autosave_moves.lock().unwrap().clear();
+    // 已计分的对局不再重复计分
+    *state.rated.lock().unwrap() = game.is_rated;
+    *state.rated_game_id.lock().unwrap() = game.is_rated.then_some(game_id);
+
+    // 获取棋盘状态
+    let board_state = {
+        let board = state.board.lock().unwrap();
+        let mut result = Vec::new();
+        for x in 0..15 {
+            let mut row = Vec::new();
+            for y in 0..15 {
+                let cell = board.get(x, y).unwrap();
+                row.push(match cell {
+                    Cell::Empty => "empty".to_string(),
+                    Cell::Black => "black".to_string(),
+                    Cell::White => "white".to_string(),
+                });
+            }
+            result.push(row);
+        }
+        result
+    };
+
+    Ok(LoadGameResult {
+        board: board_state,
+        current_player: match current_player {
+            Player::Black => "black".to_string(),
+            Player::White => "white".to_string(),
+        },
+        game_status: match game_status {
+            GameStatus::InProgress => "playing".to_string(),
+            GameStatus::BlackWin => "black_win".to_string(),
+            GameStatus::WhiteWin => "white_win".to_string(),
+            GameStatus::Draw => "draw".to_string(),
+            GameStatus::Idle => "idle".to_string(),
+        },
+        game_mode: match game_mode {
+            GameMode::PvP => "pvp".to_string(),
+            GameMode::PvE => "pve".to_string(),
+        },
+        ai_difficulty: ai_difficulty.to_string(),
+        move_history,
+        offers,
+        captures,
+        setup,
+    })
+}
+
+/// 获取保存的游戏列表
+#[tauri::command]
+pub async fn list_saved_games(
+    state: State<'_, GameState>,
+) -> Result<Vec<SavedGame>, String> {
+    let db = state.database.lock().unwrap();
+    db.list_games()
+        .map_err(|e| format!("Failed to list games: {}", e))
+}
+
+/// 按条件搜索保存的游戏（支持排序和分页）
+#[tauri::command]
+pub async fn search_saved_games(
+    state: State<'_, GameState>,
+    query: GameQuery,
+) -> Result<GamePage, String> {
+    let db = state.database.lock().unwrap();
+    db.search_games(&query)
+        .map_err(|e| format!("Failed to search games: {}", e))
+}
+
+/// 删除保存的游戏
+#[tauri::command]
+pub async fn delete_saved_game(
+    state: State<'_, GameState>,
+    game_id: i64,
+) -> Result<(), String> {
+    let db = state.database.lock().unwrap();
+    db.delete_game(game_id)
+        .map_err(|e| format!("Failed to delete game: {}", e))
+}
+
+fn tag_name(tag: &str) -> Result<String, String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("Tag cannot be empty".to_string());
+    }
+    Ok(tag.to_string())
+}
+
+fn collection_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// 收藏或取消收藏保存的游戏
+#[tauri::command]
+pub async fn set_game_favorite(state: State<'_, GameState>, game_id: i64, favorite: bool) -> Result<(), String> {
+    let db = state.database.lock().unwrap();
+    let updated = db.set_favorite(game_id, favorite)
+        .map_err(|e| format!("Failed to update game: {}", e))?;
+    if !updated {
+        return Err("Game not found".to_string());
+    }
+    Ok(())
+}
+
+/// 获取保存的游戏的标签
+#[tauri::command]
+pub async fn get_game_tags(state: State<'_, GameState>, game_id: i64) -> Result<Vec<String>, String> {
+    let db = state.database.lock().unwrap();
+    db.get_tags(game_id)
+        .map_err(|e| format!("Failed to load tags: {}", e))
+}
+
+/// 给保存的游戏加标签，返回该游戏的全部标签
+#[tauri::command]
+pub async fn add_game_tag(state: State<'_, GameState>, game_id: i64, tag: String) -> Result<Vec<String>, String> {
+    let tag = tag_name(&tag)?;
+    let db = state.database.lock().unwrap();
+    db.get_game(game_id)
+        .map_err(|e| format!("Failed to load game: {}", e))?
+        .ok_or("Game not found")?;
+    db.add_tag(game_id, &tag)
+        .and_then(|_| db.get_tags(game_id))
+        .map_err(|e| format!("Failed to tag game: {}", e))
+}
+
+/// 移除保存的游戏的标签，返回该游戏的全部标签
+#[tauri::command]
+pub async fn remove_game_tag(state: State<'_, GameState>, game_id: i64, tag: String) -> Result<Vec<String>, String> {
+    let db = state.database.lock().unwrap();
+    db.remove_tag(game_id, tag.trim())
+        .and_then(|_| db.get_tags(game_id))
+        .map_err(|e| format!("Failed to untag game: {}", e))
+}
+
+/// 获取全部标签
+#[tauri::command]
+pub async fn list_tags(state: State<'_, GameState>) -> Result<Vec<SavedTag>, String> {
+    let db = state.database.lock().unwrap();
+    db.list_tags()
+        .map_err(|e| format!("Failed to list tags: {}", e))
+}
+
+/// 删除标签（从所有游戏上移除）
+#[tauri::command]
+pub async fn delete_tag(state: State<'_, GameState>, tag_id: i64) -> Result<(), String> {
+    let db = state.database.lock().unwrap();
+    let deleted = db.delete_tag(tag_id)
+        .map_err(|e| format!("Failed to delete tag: {}", e))?;
+    if !deleted {
+        return Err("Tag not found".to_string());
+    }
+    Ok(())
+}
+
+/// 获取全部合集
+#[tauri::command]
+pub async fn list_collections(state: State<'_, GameState>) -> Result<Vec<SavedCollection>, String> {
+    let db = state.database.lock().unwrap();
+    db.list_collections()
+        .map_err(|e| format!("Failed to list collections: {}", e))
+}
+
+/// 新建合集
+#[tauri::command]
+pub async fn create_collection(
+    state: State<'_, GameState>,
+    name: String,
+    description: Option<String>,
+) -> Result<SavedCollection, String> {
+    let mut collection = SavedCollection {
+        id: None,
+        name: collection_name(&name)?,
+        description: description.filter(|d| !d.trim().is_empty()),
+        created_at: Utc::now().timestamp(),
+        game_count: 0,
+    };
+    let db = state.database.lock().unwrap();
+    collection.id = Some(db.save_collection(&collection)
+        .map_err(|e| format!("Failed to create collection: {}", e))?);
+    Ok(collection)
+}
+
+/// 修改合集名称和说明
+#[tauri::command]
+pub async fn update_collection(
+    state: State<'_, GameState>,
+    collection_id: i64,
+    name: String,
+    description: Option<String>,
+) -> Result<(), String> {
+    let name = collection_name(&name)?;
+    let description = description.filter(|d| !d.trim().is_empty());
+    let db = state.database.lock().unwrap();
+    let updated = db.update_collection(collection_id, &name, description.as_deref())
+        .map_err(|e| format!("Failed to update collection: {}", e))?;
+    if !updated {
+        return Err("Collection not found".to_string());
+    }
+    Ok(())
+}
+
+/// 删除合集（其中的游戏保留）
+#[tauri::command]
+pub async fn delete_collection(state: State<'_, GameState>, collection_id: i64) -> Result<(), String> {
+    let db = state.database.lock().unwrap();
+    let deleted = db.delete_collection(collection_id)
+        .map_err(|e| format!("Failed to delete collection: {}", e))?;
+    if !deleted {
+        return Err("Collection not found".to_string());
+    }
+    Ok(())
+}
+
+/// 获取保存的游戏所在的合集
+#[tauri::command]
+pub async fn get_game_collections(state: State<'_, GameState>, game_id: i64) -> Result<Vec<i64>, String> {
+    let db = state.database.lock().unwrap();
+    db.get_game_collections(game_id)
+        .map_err(|e| format!("Failed to load collections: {}", e))
+}
+
+/// 把保存的游戏加入合集
+#[tauri::command]
+pub async fn add_game_to_collection(
+    state: State<'_, GameState>,
+    collection_id: i64,
+    game_id: i64,
+) -> Result<(), String> {
+    let db = state.database.lock().unwrap();
+    db.get_game(game_id)
+        .map_err(|e| format!("Failed to load game: {}", e))?
+        .ok_or("Game not found")?;
+    db.add_to_collection(collection_id, game_id, Utc::now().timestamp())
+        .map_err(|e| format!("Failed to add game to collection: {}", e))
+}
+
+/// 把保存的游戏移出合集
+#[tauri::command]
+pub async fn remove_game_from_collection(
+    state: State<'_, GameState>,
+    collection_id: i64,
+    game_id: i64,
+) -> Result<(), String> {
+    let db = state.database.lock().unwrap();
+    db.remove_from_collection(collection_id, game_id)
+        .map_err(|e| format!("Failed to remove game from collection: {}", e))
+}
+
+/// 当前对局双方的玩家档案
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GamePlayers {
+    pub black_player_id: Option<i64>,
+    pub white_player_id: Option<i64>,
+    /// 结束后是否计入等级分
+    pub rated: bool,
+}
+
+fn player_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Player name cannot be empty".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// 获取全部玩家档案
+#[tauri::command]
+pub async fn list_players(state: State<'_, GameState>) -> Result<Vec<SavedPlayer>, String> {
+    let db = state.database.lock().unwrap();
+    db.list_players()
+        .map_err(|e| format!("Failed to list players: {}", e))
+}
+
+/// 新建人类玩家档案
+#[tauri::command]
+pub async fn create_player(state: State<'_, GameState>, name: String) -> Result<SavedPlayer, String> {
+    let rating = Rating::default();
+    let mut player = SavedPlayer {
+        id: None,
+        name: player_name(&name)?,
+        kind: "human".to_string(),
+        difficulty: None,
+        created_at: Utc::now().timestamp(),
+        rating: rating.rating,
+        rating_deviation: rating.deviation,
+        volatility: rating.volatility,
+    };
+    let db = state.database.lock().unwrap();
+    player.id = Some(db.save_player(&player)
+        .map_err(|e| format!("Failed to create player: {}", e))?);
+    Ok(player)
+}
+
+/// 重命名玩家档案
+#[tauri::command]
+pub async fn rename_player(state: State<'_, GameState>, player_id: i64, name: String) -> Result<(), String> {
+    let name = player_name(&name)?;
+    let db = state.database.lock().unwrap();
+    let renamed = db.rename_player(player_id, &name)
+        .map_err(|e| format!("Failed to rename player: {}", e))?;
+    if !renamed {
+        return Err("Player not found".to_string());
+    }
+    Ok(())
+}
+
+/// 删除人类玩家档案（AI 档案不能删除），已保存对局中的记录置空
+#[tauri::command]
+pub async fn delete_player(state: State<'_, GameState>, player_id: i64) -> Result<(), String> {
+    let deleted = {
+        let db = state.database.lock().unwrap();
+        db.delete_player(player_id)
+            .map_err(|e| format!("Failed to delete player: {}", e))?
+    };
+    if !deleted {
+        return Err("Player not found or cannot be deleted".to_string());
+    }
+
+    for current in [&state.black_player_id, &state.white_player_id] {
+        let mut current = current.lock().unwrap();
+        if *current == Some(player_id) {
+            *current = None;
+        }
+    }
+    Ok(())
+}
+
+/// 获取当前对局双方的玩家档案
+#[tauri::command]
+pub async fn get_game_players(state: State<'_, GameState>) -> Result<GamePlayers, String> {
+    Ok(GamePlayers {
+        black_player_id: *state.black_player_id.lock().unwrap(),
+        white_player_id: *state.white_player_id.lock().unwrap(),
+        rated: *state.rated.lock().unwrap(),
+    })
+}
+
+/// 设置当前对局双方的玩家档案
+#[tauri::command]
+pub async fn set_game_players(
+    state: State<'_, GameState>,
+    black_player_id: Option<i64>,
+    white_player_id: Option<i64>,
+) -> Result<GamePlayers, String> {
+    {
+        let db = state.database.lock().unwrap();
+        for player_id in [black_player_id, white_player_id].into_iter().flatten() {
+            db.get_player(player_id)
+                .map_err(|e| format!("Failed to load player: {}", e))?
+                .ok_or("Player not found")?;
+        }
+    }
+
+    *state.black_player_id.lock().unwrap() = black_player_id;
+    *state.white_player_id.lock().unwrap() = white_player_id;
+    get_game_players(state).await
+}
+
+/// 设置当前对局是否计入等级分：PvE 需要选定人类一方的玩家，PvP 需要两位不同的玩家
+#[tauri::command]
+pub async fn set_game_rated(state: State<'_, GameState>, rated: bool) -> Result<GamePlayers, String> {
+    if rated {
+        if state.rated_game_id.lock().unwrap().is_some() {
+            return Err("This game has already been rated".to_string());
+        }
+        if !matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress) {
+            return Err("Game is already over".to_string());
+        }
+        let black_player_id = *state.black_player_id.lock().unwrap();
+        let white_player_id = *state.white_player_id.lock().unwrap();
+        let ready = match *state.game_mode.lock().unwrap() {
+            GameMode::PvE => match *state.ai_player.lock().unwrap() {
+                Player::Black => white_player_id.is_some(),
+                Player::White => black_player_id.is_some(),
+            },
+            GameMode::PvP => black_player_id.is_some() && white_player_id.is_some() && black_player_id != white_player_id,
+        };
+        if !ready {
+            return Err("Rated games need a player profile on each side".to_string());
+        }
+    }
+
+    *state.rated.lock().unwrap() = rated;
+    get_game_players(state).await
+}
+
+/// 获取玩家的等级分变化记录
+#[tauri::command]
+pub async fn get_rating_history(
+    state: State<'_, GameState>,
+    player_id: i64,
+) -> Result<Vec<SavedRatingChange>, String> {
+    let db = state.database.lock().unwrap();
+    db.rating_history(player_id)
+        .map_err(|e| format!("Failed to load rating history: {}", e))
+}
+
+/// 按时间顺序重放全部计分对局，重新计算等级分，返回计分的对局数
+#[tauri::command]
+pub async fn rebuild_ratings(state: State<'_, GameState>) -> Result<usize, String> {
+    let db = state.database.lock().unwrap();
+    db.rebuild_ratings()
+        .map_err(|e| format!("Failed to rebuild ratings: {}", e))
+}
+
+/// 获取玩家统计
+#[tauri::command]
+pub async fn get_player_stats(state: State<'_, GameState>, player_id: i64) -> Result<PlayerStats, String> {
+    let db = state.database.lock().unwrap();
+    db.player_stats(player_id)
+        .map_err(|e| format!("Failed to load player stats: {}", e))?
+        .ok_or("Player not found".to_string())
+}
+
+/// 获取开局状态
+#[tauri::command]
+pub async fn get_opening_state(
+    state: State<'_, GameState>,
+) -> Result<OpeningState, String> {
+    Ok(state.opening.lock().unwrap().clone())
+}
+
+/// 开局阶段做出选择（交换执色 / 保持 / Swap2 再下两子）
+#[tauri::command]
+pub async fn make_opening_decision(
+    state: State<'_, GameState>,
+    decision: OpeningDecision,
+) -> Result<OpeningState, String> {
+    check_flag(&state)?;
+
+    let opening = {
+        let mut opening = state.opening.lock().unwrap();
+        opening.decide(decision)?;
+        opening.clone()
+    };
+
+    // PvE 模式下交换执色时，AI 也随之换色
+    if decision == OpeningDecision::SwapColor && *state.game_mode.lock().unwrap() == GameMode::PvE {
+        let mut ai_player = state.ai_player.lock().unwrap();
+        *ai_player = ai_player.opponent();
+    }
+    // 交换执色后双方玩家档案和棋钟也随之交换
+    if decision == OpeningDecision::SwapColor {
+        let mut black_player_id = state.black_player_id.lock().unwrap();
+        let mut white_player_id = state.white_player_id.lock().unwrap();
+        std::mem::swap(&mut *black_player_id, &mut *white_player_id);
+        state.clock.lock().unwrap().swap_sides();
+    }
+
+    if let Err(flagged) = switch_clock(&state) {
+        apply_timeout(&state, flagged);
+        return Err("Time is up".to_string());
+    }
+
+    Ok(opening)
+}
+
+/// 提交五手 N 打的候选落点，之后由对方通过 place_stone 选择其中一个
+#[tauri::command]
+pub async fn offer_fifth_moves(
+    state: State<'_, GameState>,
+    positions: Vec<Position>,
+) -> Result<OpeningState, String> {
+    check_flag(&state)?;
+
+    let board = state.board.lock().unwrap().clone();
+    let opening = {
+        let mut opening = state.opening.lock().unwrap();
+        opening.offer_fifth_moves(&board, &positions)?;
+        opening.clone()
+    };
+
+    if let Err(flagged) = switch_clock(&state) {
+        apply_timeout(&state, flagged);
+        return Err("Time is up".to_string());
+    }
+
+    Ok(opening)
+}
+
+/// 获取 AI 在开局阶段的行动（落子或选择），由前端再提交执行
+#[tauri::command]
+pub async fn get_ai_opening_action(
+    state: State<'_, GameState>,
+) -> Result<OpeningAction, String> {
+    let board = state.board.lock().unwrap().clone();
+    let opening = state.opening.lock().unwrap().clone();
+
+    OpeningAdvisor::choose_action(&board, &opening)
+        .ok_or_else(|| "No opening action available".to_string())
+}
+
+/// 获取棋钟状态（同时检查当前行动方是否超时）
+#[tauri::command]
+pub async fn get_clock_state(
+    state: State<'_, GameState>,
+) -> Result<ClockSnapshot, String> {
+    // 超时后游戏状态会被更新为对方胜
+    let _ = check_flag(&state);
+    Ok(state.clock.lock().unwrap().snapshot(Instant::now()))
+}
+
+/// 结束对局并返回结果
+fn finish_game(state: &GameState, game_status: GameStatus, end_reason: EndReason) -> GameOutcome {
+    *state.game_status.lock().unwrap() = game_status;
+    *state.end_reason.lock().unwrap() = Some(end_reason);
+    *state.draw_offer.lock().unwrap() = None;
+    let _ = state.clock.lock().unwrap().stop(Instant::now());
+    end_game(state);
+
+    GameOutcome::new(game_status, Some(end_reason))
+}
+
+/// 生成让子局面（`player` 为受让的一方），用于 new_game_with_mode
+#[tauri::command]
+pub async fn create_handicap_setup(
+    player: Player,
+    stones: usize,
+) -> Result<SetupPosition, String> {
+    SetupPosition::handicap(player, stones)
+}
+
+/// 获取当前对局的初始局面
+#[tauri::command]
+pub async fn get_setup_position(state: State<'_, GameState>) -> Result<SetupPosition, String> {
+    Ok(state.setup.lock().unwrap().clone())
+}
+
+/// 打开摆棋编辑器（`from_current` 为 true 时从当前棋盘开始）
+#[tauri::command]
+pub async fn start_position_editor(
+    state: State<'_, GameState>,
+    from_current: Option<bool>,
+) -> Result<EditorState, String> {
+    let rule_set = *state.rule_set.lock().unwrap();
+    let editor = if from_current.unwrap_or(false) {
+        let board = state.board.lock().unwrap().clone();
+        let current_player = *state.current_player.lock().unwrap();
+        PositionEditor::from_board(&board, current_player, rule_set)
+    } else {
+        let mut editor = PositionEditor::new();
+        editor.set_rule_set(rule_set);
+        editor
+    };
+
+    let mut current_editor = state.editor.lock().unwrap();
+    *current_editor = editor;
+    Ok(current_editor.state())
+}
+
+/// 编辑器：摆放任意一方的棋子
+#[tauri::command]
+pub async fn editor_place_stone(
+    state: State<'_, GameState>,
+    x: usize,
+    y: usize,
+    player: Player,
+) -> Result<EditorState, String> {
+    let mut editor = state.editor.lock().unwrap();
+    editor.place(&Position { x, y }, player)?;
+    Ok(editor.state())
+}
+
+/// 编辑器：移除棋子
+#[tauri::command]
+pub async fn editor_remove_stone(
+    state: State<'_, GameState>,
+    x: usize,
+    y: usize,
+) -> Result<EditorState, String> {
+    let mut editor = state.editor.lock().unwrap();
+    editor.remove(&Position { x, y })?;
+    Ok(editor.state())
+}
 
-/// 删除保存的游戏
+/// 编辑器：设置行棋方
 #[tauri::command]
-pub async fn delete_saved_game(
+pub async fn editor_set_to_move(
     state: State<'_, GameState>,
-    game_id: i64,
+    player: Player,
+) -> Result<EditorState, String> {
+    let mut editor = state.editor.lock().unwrap();
+    editor.set_to_move(player);
+    Ok(editor.state())
+}
+
+/// 编辑器：设置校验所用的规则
+#[tauri::command]
+pub async fn editor_set_rule_set(
+    state: State<'_, GameState>,
+    rule_set: String,
+) -> Result<EditorState, String> {
+    let rule_set = RuleSet::parse(&rule_set).ok_or("Invalid rule set")?;
+    let mut editor = state.editor.lock().unwrap();
+    editor.set_rule_set(rule_set);
+    Ok(editor.state())
+}
+
+/// 编辑器：清空棋盘
+#[tauri::command]
+pub async fn editor_clear(state: State<'_, GameState>) -> Result<EditorState, String> {
+    let mut editor = state.editor.lock().unwrap();
+    editor.clear();
+    Ok(editor.state())
+}
+
+/// 获取编辑器状态
+#[tauri::command]
+pub async fn get_editor_state(state: State<'_, GameState>) -> Result<EditorState, String> {
+    Ok(state.editor.lock().unwrap().state())
+}
+
+/// 从编辑器中的局面开始对局
+#[tauri::command]
+pub async fn play_from_editor(
+    state: State<'_, GameState>,
+    mode: String,
+    difficulty: Option<String>,
+    time_control: Option<TimeControl>,
+    ai_player: Option<Player>,
 ) -> Result<(), String> {
+    let (setup, rule_set) = {
+        let editor = state.editor.lock().unwrap();
+        editor.validate()?;
+        (editor.to_setup(), editor.rule_set())
+    };
+
+    let is_pve = mode == "pve";
+    new_game_with_mode(
+        state.clone(),
+        mode,
+        difficulty,
+        None,
+        time_control,
+        Some(rule_set.as_str().to_string()),
+        Some(setup),
+    )
+    .await?;
+
+    if let Some(ai_player) = ai_player.filter(|_| is_pve) {
+        *state.ai_player.lock().unwrap() = ai_player;
+    }
+    Ok(())
+}
+
+/// 导出当前局面的文本记法（用于复制分享）
+#[tauri::command]
+pub async fn export_position(state: State<'_, GameState>) -> Result<String, String> {
+    let notation = PositionNotation {
+        board: state.board.lock().unwrap().clone(),
+        rule_set: *state.rule_set.lock().unwrap(),
+        to_move: *state.current_player.lock().unwrap(),
+    };
+    Ok(notation.format())
+}
+
+/// 导入文本记法到摆棋编辑器，确认后可用 play_from_editor 开始对局
+#[tauri::command]
+pub async fn import_position(
+    state: State<'_, GameState>,
+    notation: String,
+) -> Result<EditorState, String> {
+    let notation = PositionNotation::parse(&notation)?;
+    let mut editor = state.editor.lock().unwrap();
+    *editor = PositionEditor::from_board(&notation.board, notation.to_move, notation.rule_set);
+    Ok(editor.state())
+}
+
+/// 坐标转换为 `h8` 形式的记法
+#[tauri::command]
+pub async fn format_coordinate(x: usize, y: usize) -> Result<String, String> {
+    if x >= 15 || y >= 15 {
+        return Err("Position out of bounds".to_string());
+    }
+    Ok(Position { x, y }.to_notation())
+}
+
+/// 解析 `h8` 形式的坐标记法
+#[tauri::command]
+pub async fn parse_coordinate(notation: String) -> Result<Position, String> {
+    Position::from_notation(&notation)
+}
+
+/// 读取完整的对局记录
+fn read_game_record(db: &Database, game_id: i64) -> Result<GameRecord, String> {
+    let game = db.get_game(game_id)
+        .map_err(|e| format!("Failed to load game: {}", e))?
+        .ok_or("Game not found".to_string())?;
+    let setup = db.get_setup_stones(game_id)
+        .map_err(|e| format!("Failed to load setup: {}", e))?;
+    let moves = db.get_moves(game_id)
+        .map_err(|e| format!("Failed to load moves: {}", e))?;
+    let annotations = db.get_annotations(game_id)
+        .map_err(|e| format!("Failed to load annotations: {}", e))?;
+    let markup = db.get_markup(game_id)
+        .map_err(|e| format!("Failed to load markup: {}", e))?;
+    let player_name = |player_id: Option<i64>| -> Result<Option<String>, String> {
+        let Some(player_id) = player_id else {
+            return Ok(None);
+        };
+        let player = db.get_player(player_id)
+            .map_err(|e| format!("Failed to load player: {}", e))?;
+        Ok(player.map(|player| player.name))
+    };
+    let black_player = player_name(game.black_player_id)?;
+    let white_player = player_name(game.white_player_id)?;
+    Ok(GameRecord { game, setup, moves, annotations, markup, black_player, white_player })
+}
+
+/// 按名称找到导入棋谱中玩家对应的档案，没有时新建人类玩家档案
+fn import_player(db: &Database, name: Option<&str>) -> Result<Option<i64>, String> {
+    let Some(name) = name.map(str::trim).filter(|name| !name.is_empty()) else {
+        return Ok(None);
+    };
+    let players = db.list_players()
+        .map_err(|e| format!("Failed to load players: {}", e))?;
+    if let Some(player) = players.iter().find(|player| player.name == name) {
+        return Ok(player.id);
+    }
+
+    let rating = Rating::default();
+    let player = SavedPlayer {
+        id: None,
+        name: name.to_string(),
+        kind: "human".to_string(),
+        difficulty: None,
+        created_at: Utc::now().timestamp(),
+        rating: rating.rating,
+        rating_deviation: rating.deviation,
+        volatility: rating.volatility,
+    };
+    let player_id = db.save_player(&player)
+        .map_err(|e| format!("Failed to create player: {}", e))?;
+    Ok(Some(player_id))
+}
+
+/// 按规则重放校验导入的棋谱，通过后保存到数据库
+fn store_imported_game(db: &Database, imported: ImportedGame, name: String) -> Result<i64, String> {
+    let (setup, rule_set) = match &imported.record {
+        Some(record) => (
+            record.setup_position(),
+            RuleSet::parse(&record.game.rule_set).ok_or("Invalid rule set")?,
+        ),
+        None => (SetupPosition::empty(), RuleSet::Freestyle),
+    };
+    let tree = match &imported.record {
+        Some(record) => record.tree()?,
+        None => GameTree::from_line(&imported.moves),
+    };
+    // 变化中的着法同样需要合法
+    for leaf in tree.leaves() {
+        if !tree.is_main_line(leaf) {
+            GameReplay::replay(setup.clone(), rule_set, &tree.line_to(Some(leaf)))
+                .map_err(|e| format!("Variation: {}", e))?;
+        }
+    }
+    let main_line = tree.main_line();
+    let replay = GameReplay::replay(setup.clone(), rule_set, &main_line)?;
+
+    // 认输、超时、协议和棋不由落子决定，沿用记录中的结果
+    let (game_status, end_reason) = match &imported.record {
+        Some(record) if matches!(replay.game_status, GameStatus::InProgress) => {
+            match record.game.end_reason.as_deref().and_then(EndReason::parse) {
+                Some(reason @ (EndReason::Resignation | EndReason::Timeout | EndReason::Agreement)) => {
+                    (parse_status(&record.game.status), Some(reason))
+                }
+                _ => (GameStatus::InProgress, None),
+            }
+        }
+        _ => (replay.game_status, replay.end_reason),
+    };
+
+    let now = Utc::now().timestamp();
+    let mut saved_game = SavedGame {
+        id: None,
+        name: imported
+            .record
+            .as_ref()
+            .map(|r| r.game.name.clone())
+            .filter(|record_name| !record_name.is_empty())
+            .unwrap_or(name),
+        mode: imported.record.as_ref().map(|r| r.game.mode.clone()).unwrap_or_else(|| "pvp".to_string()),
+        difficulty: imported.record.as_ref().and_then(|r| r.game.difficulty.clone()),
+        created_at: imported.record.as_ref().map(|r| r.game.created_at).unwrap_or(now),
+        updated_at: now,
+        status: status_str(game_status).to_string(),
+        winner: winner_str(game_status),
+        total_moves: main_line.len() as i32,
+        rule_set: rule_set.as_str().to_string(),
+        end_reason: end_reason.map(|r| r.as_str().to_string()),
+        black_captures: replay.captures.black as i32,
+        white_captures: replay.captures.white as i32,
+        first_player: setup.to_move.as_str().to_string(),
+        black_player_id: None,
+        white_player_id: None,
+        is_rated: false,
+        is_favorite: false,
+    };
+
+    let tx = db.transaction()
+        .map_err(|e| format!("Failed to save game: {}", e))?;
+    if let Some(record) = &imported.record {
+        saved_game.black_player_id = import_player(db, record.black_player.as_deref())?;
+        saved_game.white_player_id = import_player(db, record.white_player.as_deref())?;
+    }
+    let game_id = db.save_game(&saved_game)
+        .map_err(|e| format!("Failed to save game: {}", e))?;
+    store_setup_and_moves(db, game_id, &setup, rule_set, &tree)?;
+    index_positions(db, game_id, &setup, rule_set, &main_line, 0)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to save game: {}", e))?;
+    Ok(game_id)
+}
+
+/// 查找出现过某局面（含旋转、翻转后相同的局面）的保存的游戏。
+/// `position` 为局面记法，省略时使用当前棋盘
+#[tauri::command]
+pub async fn find_games_with_position(
+    state: State<'_, GameState>,
+    position: Option<String>,
+) -> Result<Vec<PositionMatch>, String> {
+    let notation = match position {
+        Some(position) => PositionNotation::parse(&position)?,
+        None => PositionNotation {
+            board: state.board.lock().unwrap().clone(),
+            rule_set: *state.rule_set.lock().unwrap(),
+            to_move: *state.current_player.lock().unwrap(),
+        },
+    };
+    let hash = canonical_hash(&notation.board, notation.to_move);
+
     let db = state.database.lock().unwrap();
-    db.delete_game(game_id)
-        .map_err(|e| format!("Failed to delete game: {}", e))
-}
\ No newline at end of file
+    db.find_position(hash, notation.rule_set.as_str())
+        .map_err(|e| format!("Failed to search positions: {}", e))
+}
+
+/// 开局浏览器：保存的游戏在该局面之后下过的着法及其胜率。
+/// `position` 为局面记法，省略时使用当前棋盘
+#[tauri::command]
+pub async fn get_opening_explorer(
+    state: State<'_, GameState>,
+    position: Option<String>,
+) -> Result<ExplorerPosition, String> {
+    let notation = match position {
+        Some(position) => PositionNotation::parse(&position)?,
+        None => PositionNotation {
+            board: state.board.lock().unwrap().clone(),
+            rule_set: *state.rule_set.lock().unwrap(),
+            to_move: *state.current_player.lock().unwrap(),
+        },
+    };
+
+    let db = state.database.lock().unwrap();
+    explorer::explore(&db, &notation.board, notation.to_move, notation.rule_set)
+}
+
+/// 为还没有局面索引的保存的游戏建立索引（升级前保存的棋谱），返回处理的游戏数
+#[tauri::command]
+pub async fn backfill_position_index(state: State<'_, GameState>) -> Result<usize, String> {
+    let db = state.database.lock().unwrap();
+    let game_ids = db.unindexed_games()
+        .map_err(|e| format!("Failed to load games: {}", e))?;
+    for &game_id in &game_ids {
+        let record = read_game_record(&db, game_id)?;
+        let rule_set = RuleSet::parse(&record.game.rule_set).ok_or("Invalid rule set")?;
+        let main_line = record.tree()?.main_line();
+        index_positions(&db, game_id, &record.setup_position(), rule_set, &main_line, 0)?;
+    }
+    Ok(game_ids.len())
+}
+
+/// 读取并导入一个棋谱文件
+fn import_file(db: &Database, path: &Path, format: Option<GameFormat>) -> Result<i64, String> {
+    let format = format
+        .or_else(|| GameFormat::from_path(path))
+        .ok_or("Unknown game file format")?;
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Imported game".to_string());
+
+    let imported = exchange::import_game(&text, format)?;
+    store_imported_game(db, imported, name)
+}
+
+/// 导出保存的游戏（psq / move_list / json），指定 `path` 时同时写入文件
+#[tauri::command]
+pub async fn export_game(
+    state: State<'_, GameState>,
+    game_id: i64,
+    format: String,
+    path: Option<String>,
+) -> Result<String, String> {
+    let format = GameFormat::parse(&format).ok_or("Invalid game format")?;
+    let record = {
+        let db = state.database.lock().unwrap();
+        read_game_record(&db, game_id)?
+    };
+
+    let content = exchange::export_game(&record, format)?;
+    if let Some(path) = path {
+        std::fs::write(&path, &content)
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+    Ok(content)
+}
+
+/// 从文件导入棋谱（未指定格式时按扩展名判断）
+#[tauri::command]
+pub async fn import_game(
+    state: State<'_, GameState>,
+    path: String,
+    format: Option<String>,
+) -> Result<i64, String> {
+    let format = match format {
+        Some(format) => Some(GameFormat::parse(&format).ok_or("Invalid game format")?),
+        None => None,
+    };
+    let db = state.database.lock().unwrap();
+    import_file(&db, Path::new(&path), format)
+}
+
+/// 从粘贴的文本导入棋谱
+#[tauri::command]
+pub async fn import_game_text(
+    state: State<'_, GameState>,
+    text: String,
+    format: String,
+    name: Option<String>,
+) -> Result<i64, String> {
+    let format = GameFormat::parse(&format).ok_or("Invalid game format")?;
+    let imported = exchange::import_game(&text, format)?;
+    let db = state.database.lock().unwrap();
+    store_imported_game(&db, imported, name.unwrap_or_else(|| "Imported game".to_string()))
+}
+
+/// 批量导入的结果
+#[derive(Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub imported: Vec<i64>,
+    pub failed: Vec<ImportFailure>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ImportFailure {
+    pub file: String,
+    pub error: String,
+}
+
+/// 导入目录下所有可识别格式的棋谱文件，单个文件失败不影响其他文件
+#[tauri::command]
+pub async fn import_game_directory(
+    state: State<'_, GameState>,
+    path: String,
+) -> Result<ImportSummary, String> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&path)
+        .map_err(|e| format!("Failed to read directory {}: {}", path, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| file.is_file() && GameFormat::from_path(file).is_some())
+        .collect();
+    files.sort();
+
+    let db = state.database.lock().unwrap();
+    let mut summary = ImportSummary {
+        imported: Vec::new(),
+        failed: Vec::new(),
+    };
+    for file in files {
+        match import_file(&db, &file, None) {
+            Ok(game_id) => summary.imported.push(game_id),
+            Err(error) => summary.failed.push(ImportFailure {
+                file: file.display().to_string(),
+                error,
+            }),
+        }
+    }
+    Ok(summary)
+}
+
+/// 两个路径是否指向同一个文件，`target` 还不存在时按所在目录比较
+fn is_same_file(current: &Path, target: &Path) -> bool {
+    let resolve = |path: &Path| {
+        std::fs::canonicalize(path).ok().or_else(|| {
+            let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            Some(std::fs::canonicalize(parent).ok()?.join(path.file_name()?))
+        })
+    };
+    match (resolve(current), resolve(target)) {
+        (Some(current), Some(target)) => current == target,
+        _ => current == target,
+    }
+}
+
+/// 当前数据库文件是否就是 `path`
+fn is_current_database(state: &GameState, path: &str) -> bool {
+    state.database.lock().unwrap().path().is_some_and(|current| is_same_file(current, Path::new(path)))
+}
+
+/// 导出数据库快照（SQLite 在线备份，对局进行中也保持一致）
+#[tauri::command]
+pub async fn export_database(state: State<'_, GameState>, path: String) -> Result<(), String> {
+    if is_current_database(&state, &path) {
+        return Err("Cannot export the database onto itself".to_string());
+    }
+    let db = state.database.lock().unwrap();
+    db.backup_to(Path::new(&path))
+        .map_err(|e| format!("Failed to export database: {}", e))
+}
+
+/// 用快照替换当前数据库，替换前把当前数据库备份到同目录下的 `.before-restore.bak`。
+/// 当前对局保留在内存中，之后保存时作为新记录写入
+#[tauri::command]
+pub async fn restore_database(state: State<'_, GameState>, path: String) -> Result<(), String> {
+    if is_current_database(&state, &path) {
+        return Err("Cannot restore the database from itself".to_string());
+    }
+    let snapshot = Database::open_snapshot(Path::new(&path))
+        .map_err(|e| format!("Failed to read snapshot: {}", e))?;
+
+    {
+        let mut db = state.database.lock().unwrap();
+        if let Some(current) = db.path() {
+            let mut safety = current.as_os_str().to_owned();
+            safety.push(".before-restore.bak");
+            db.backup_to(Path::new(&safety))
+                .map_err(|e| format!("Failed to back up current database: {}", e))?;
+        }
+        db.restore_from(&snapshot)
+            .map_err(|e| format!("Failed to restore database: {}", e))?;
+    }
+
+    // 原来的记录编号和玩家档案不再有效
+    *state.saved_game_id.lock().unwrap() = None;
+    state.saved_moves.lock().unwrap().clear();
+    *state.autosave_id.lock().unwrap() = None;
+    state.autosave_moves.lock().unwrap().clear();
+    *state.resumable_game.lock().unwrap() = None;
+    *state.black_player_id.lock().unwrap() = None;
+    *state.white_player_id.lock().unwrap() = None;
+    *state.rated.lock().unwrap() = false;
+    *state.rated_game_id.lock().unwrap() = None;
+    Ok(())
+}
+
+/// 合并结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeSummary {
+    pub games_added: usize,
+    /// 当前数据库中已有的相同对局
+    pub duplicates_skipped: usize,
+    pub players_added: usize,
+}
+
+/// 把另一个数据库中的玩家对应到当前数据库：AI 按难度，人类玩家按名称，没有时新建
+fn merge_player(
+    db: &Database,
+    source_players: &[SavedPlayer],
+    target_players: &mut Vec<SavedPlayer>,
+    source_id: Option<i64>,
+    added: &mut usize,
+) -> Result<Option<i64>, String> {
+    let Some(player) = source_players.iter().find(|p| p.id.is_some() && p.id == source_id) else {
+        return Ok(None);
+    };
+    let existing = target_players.iter().find(|p| {
+        p.kind == player.kind
+            && match player.kind.as_str() {
+                "ai" => p.difficulty == player.difficulty,
+                _ => p.name == player.name,
+            }
+    });
+    if let Some(existing) = existing {
+        return Ok(existing.id);
+    }
+
+    let mut created = player.clone();
+    created.id = Some(db.save_player(&created)
+        .map_err(|e| format!("Failed to create player: {}", e))?);
+    target_players.push(created.clone());
+    *added += 1;
+    Ok(created.id)
+}
+
+/// 合并另一个数据库中保存的游戏，跳过已有的相同对局
+#[tauri::command]
+pub async fn merge_database(state: State<'_, GameState>, path: String) -> Result<MergeSummary, String> {
+    if is_current_database(&state, &path) {
+        return Err("Cannot merge the database into itself".to_string());
+    }
+    let source = Database::open_snapshot(Path::new(&path))
+        .map_err(|e| format!("Failed to read database: {}", e))?;
+    let db = state.database.lock().unwrap();
+
+    // 只有创建时间和规则都相同的对局才可能重复，用到时再计算它们的指纹
+    let mut candidates: HashMap<(i64, String), Vec<i64>> = HashMap::new();
+    for game in db.list_games().map_err(|e| format!("Failed to list games: {}", e))? {
+        let Some(game_id) = game.id else { continue };
+        candidates.entry((game.created_at, game.rule_set)).or_default().push(game_id);
+    }
+    let mut fingerprints: HashMap<(i64, String), HashSet<String>> = HashMap::new();
+    let source_players = source.list_players()
+        .map_err(|e| format!("Failed to list players: {}", e))?;
+    let mut target_players = db.list_players()
+        .map_err(|e| format!("Failed to list players: {}", e))?;
+    let source_collections = source.list_collections()
+        .map_err(|e| format!("Failed to list collections: {}", e))?;
+    let mut target_collections = db.list_collections()
+        .map_err(|e| format!("Failed to list collections: {}", e))?;
+
+    let mut summary = MergeSummary {
+        games_added: 0,
+        duplicates_skipped: 0,
+        players_added: 0,
+    };
+    let mut rated_added = false;
+    let tx = db.transaction()
+        .map_err(|e| format!("Failed to merge database: {}", e))?;
+    for game in source.list_games().map_err(|e| format!("Failed to list games: {}", e))? {
+        let Some(source_id) = game.id else { continue };
+        let record = read_game_record(&source, source_id)?;
+        let key = (record.game.created_at, record.game.rule_set.clone());
+        let known = match fingerprints.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut known = HashSet::new();
+                for &game_id in candidates.get(entry.key()).into_iter().flatten() {
+                    known.insert(read_game_record(&db, game_id)?.fingerprint()?);
+                }
+                entry.insert(known)
+            }
+        };
+        if !known.insert(record.fingerprint()?) {
+            summary.duplicates_skipped += 1;
+            continue;
+        }
+
+        let mut saved_game = record.game.clone();
+        saved_game.id = None;
+        saved_game.black_player_id =
+            merge_player(&db, &source_players, &mut target_players, game.black_player_id, &mut summary.players_added)?;
+        saved_game.white_player_id =
+            merge_player(&db, &source_players, &mut target_players, game.white_player_id, &mut summary.players_added)?;
+        let game_id = db.save_game(&saved_game)
+            .map_err(|e| format!("Failed to save game: {}", e))?;
+
+        let setup = record.setup_position();
+        let rule_set = RuleSet::parse(&record.game.rule_set).ok_or("Invalid rule set")?;
+        let tree = record.tree()?;
+        let saved_moves = store_setup_and_moves(&db, game_id, &setup, rule_set, &tree)?;
+        index_positions(&db, game_id, &setup, rule_set, &tree.main_line(), 0)?;
+        let source_move_id = source.get_current_move(source_id)
+            .map_err(|e| format!("Failed to load game: {}", e))?;
+        let current_move_id = source_move_id
+            .and_then(|move_id| record.moves.iter().position(|m| m.id == Some(move_id)))
+            .and_then(|id| saved_moves.get(&id))
+            .map(|node| node.move_id);
+        db.set_current_move(game_id, current_move_id)
+            .map_err(|e| format!("Failed to save game: {}", e))?;
+
+        let offers = source.get_offers(source_id)
+            .map_err(|e| format!("Failed to load offers: {}", e))?;
+        for offer in offers {
+            db.save_offer(&SavedOffer { id: None, game_id, ..offer })
+                .map_err(|e| format!("Failed to save offer: {}", e))?;
+        }
+        if let Some(clock) = source.get_clock(source_id).map_err(|e| format!("Failed to load clock: {}", e))? {
+            db.save_clock(&SavedClock { game_id, ..clock })
+                .map_err(|e| format!("Failed to save clock: {}", e))?;
+        }
+        if let Some(opening) = source.get_opening(source_id).map_err(|e| format!("Failed to load opening: {}", e))? {
+            db.save_opening(&SavedOpening { game_id, ..opening })
+                .map_err(|e| format!("Failed to save opening: {}", e))?;
+        }
+        for tag in source.get_tags(source_id).map_err(|e| format!("Failed to load tags: {}", e))? {
+            db.add_tag(game_id, &tag)
+                .map_err(|e| format!("Failed to tag game: {}", e))?;
+        }
+        let collection_ids = source.get_game_collections(source_id)
+            .map_err(|e| format!("Failed to load collections: {}", e))?;
+        for source_collection in source_collections.iter().filter(|c| c.id.is_some_and(|id| collection_ids.contains(&id))) {
+            let collection_id = match target_collections.iter().find(|c| c.name.eq_ignore_ascii_case(&source_collection.name)) {
+                Some(existing) => existing.id,
+                None => {
+                    let mut created = source_collection.clone();
+                    created.id = Some(db.save_collection(&created)
+                        .map_err(|e| format!("Failed to create collection: {}", e))?);
+                    target_collections.push(created.clone());
+                    created.id
+                }
+            };
+            if let Some(collection_id) = collection_id {
+                db.add_to_collection(collection_id, game_id, Utc::now().timestamp())
+                    .map_err(|e| format!("Failed to add game to collection: {}", e))?;
+            }
+        }
+
+        rated_added |= saved_game.is_rated;
+        summary.games_added += 1;
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to merge database: {}", e))?;
+
+    if rated_added {
+        db.rebuild_ratings()
+            .map_err(|e| format!("Failed to rebuild ratings: {}", e))?;
+    }
+    Ok(summary)
+}
+
+/// 获取新对局的默认设置
+#[tauri::command]
+pub async fn get_settings(state: State<'_, GameState>) -> Result<AppSettings, String> {
+    let db = state.database.lock().unwrap();
+    AppSettings::load(&db)
+}
+
+/// 保存新对局的默认设置
+#[tauri::command]
+pub async fn update_settings(state: State<'_, GameState>, settings: AppSettings) -> Result<AppSettings, String> {
+    let db = state.database.lock().unwrap();
+    settings.save(&db)?;
+    Ok(settings)
+}
+
+/// 数据目录（数据库所在的目录）
+#[tauri::command]
+pub async fn get_data_dir(state: State<'_, GameState>) -> Result<Option<String>, String> {
+    let db = state.database.lock().unwrap();
+    Ok(db.path().and_then(Path::parent).map(|dir| dir.display().to_string()))
+}
+
+/// 获取双方已提走的对数（提子规则）
+#[tauri::command]
+pub async fn get_captures(state: State<'_, GameState>) -> Result<CaptureCount, String> {
+    Ok(*state.captures.lock().unwrap())
+}
+
+/// 获取当前对局结果及结束原因
+#[tauri::command]
+pub async fn get_game_outcome(state: State<'_, GameState>) -> Result<GameOutcome, String> {
+    let game_status = *state.game_status.lock().unwrap();
+    let end_reason = *state.end_reason.lock().unwrap();
+    Ok(GameOutcome::new(game_status, end_reason))
+}
+
+/// 认输（默认为当前行棋方认输）
+#[tauri::command]
+pub async fn resign(
+    state: State<'_, GameState>,
+    player: Option<Player>,
+) -> Result<GameOutcome, String> {
+    if !matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress) {
+        return Err("Game is already over".to_string());
+    }
+
+    let player = player.unwrap_or_else(|| *state.current_player.lock().unwrap());
+    let game_status = match player {
+        Player::Black => GameStatus::WhiteWin,
+        Player::White => GameStatus::BlackWin,
+    };
+    Ok(finish_game(&state, game_status, EndReason::Resignation))
+}
+
+/// 和棋提议结果
+#[derive(Serialize, Deserialize)]
+pub struct DrawOfferResult {
+    /// 对方（AI）已经作出回应时为 Some
+    pub accepted: Option<bool>,
+    pub outcome: Option<GameOutcome>,
+}
+
+/// 提议和棋；PvE 模式下向 AI 提议时，AI 根据局面评估立即回应
+#[tauri::command]
+pub async fn offer_draw(
+    state: State<'_, GameState>,
+    player: Option<Player>,
+) -> Result<DrawOfferResult, String> {
+    if !matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress) {
+        return Err("Game is already over".to_string());
+    }
+
+    let current_player = *state.current_player.lock().unwrap();
+    let player = player.unwrap_or(current_player);
+    let ai_player = *state.ai_player.lock().unwrap();
+    let is_pve = *state.game_mode.lock().unwrap() == GameMode::PvE;
+
+    if is_pve && player.opponent() == ai_player {
+        let board = state.board.lock().unwrap().clone();
+        let captures = *state.captures.lock().unwrap();
+        let accepted = state.ai_engine.lock().unwrap()
+            .as_ref()
+            .map(|engine| engine.accepts_draw(&board, ai_player, current_player, captures))
+            .unwrap_or(false);
+
+        let outcome = accepted.then(|| finish_game(&state, GameStatus::Draw, EndReason::Agreement));
+        return Ok(DrawOfferResult { accepted: Some(accepted), outcome });
+    }
+
+    *state.draw_offer.lock().unwrap() = Some(player);
+    Ok(DrawOfferResult { accepted: None, outcome: None })
+}
+
+/// 接受对方的和棋提议
+#[tauri::command]
+pub async fn accept_draw(
+    state: State<'_, GameState>,
+    player: Option<Player>,
+) -> Result<GameOutcome, String> {
+    if !matches!(*state.game_status.lock().unwrap(), GameStatus::InProgress) {
+        return Err("Game is already over".to_string());
+    }
+    check_flag(&state)?;
+
+    let offered_by = state.draw_offer.lock().unwrap()
+        .ok_or("No pending draw offer")?;
+    // 未指定时由当前行棋方接受
+    let player = player.unwrap_or_else(|| *state.current_player.lock().unwrap());
+    if player == offered_by {
+        return Err("Cannot accept your own draw offer".to_string());
+    }
+
+    Ok(finish_game(&state, GameStatus::Draw, EndReason::Agreement))
+}
+
+/// 拒绝对方的和棋提议
+#[tauri::command]
+pub async fn decline_draw(state: State<'_, GameState>) -> Result<(), String> {
+    let mut draw_offer = state.draw_offer.lock().unwrap();
+    if draw_offer.take().is_none() {
+        return Err("No pending draw offer".to_string());
+    }
+    Ok(())
+}
+
+/// AI 的行动：落子或认输
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AiAction {
+    Move { position: Position },
+    Resign,
+}
+
+/// 获取 AI 的行动，局面无望时 AI 选择认输（由前端调用 resign 执行）
+#[tauri::command]
+pub async fn get_ai_action(
+    state: State<'_, GameState>,
+) -> Result<AiAction, String> {
+    if state.opening.lock().unwrap().is_finished() {
+        let board = state.board.lock().unwrap().clone();
+        let current_player = *state.current_player.lock().unwrap();
+        let captures = *state.captures.lock().unwrap();
+        let resign = state.ai_engine.lock().unwrap()
+            .as_ref()
+            .is_some_and(|engine| engine.should_resign(&board, current_player, captures));
+        if resign {
+            return Ok(AiAction::Resign);
+        }
+    }
+
+    let position = get_ai_move(state).await?;
+    Ok(AiAction::Move { position })
+}
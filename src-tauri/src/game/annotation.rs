@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use super::types::Position;
+
+/// 着法评注符号
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Annotation {
+    Good,        // !
+    Excellent,   // !!
+    Mistake,     // ?
+    Blunder,     // ??
+    Interesting, // !?
+    Dubious,     // ?!
+}
+
+impl Annotation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Annotation::Good => "!",
+            Annotation::Excellent => "!!",
+            Annotation::Mistake => "?",
+            Annotation::Blunder => "??",
+            Annotation::Interesting => "!?",
+            Annotation::Dubious => "?!",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Annotation> {
+        match s {
+            "!" => Some(Annotation::Good),
+            "!!" => Some(Annotation::Excellent),
+            "?" => Some(Annotation::Mistake),
+            "??" => Some(Annotation::Blunder),
+            "!?" => Some(Annotation::Interesting),
+            "?!" => Some(Annotation::Dubious),
+            _ => None,
+        }
+    }
+}
+
+/// 棋盘标记类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkupKind {
+    Label,     // 文字标签
+    Triangle,
+    Circle,
+    Square,
+    Cross,
+    Highlight, // 高亮格子
+}
+
+impl MarkupKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarkupKind::Label => "label",
+            MarkupKind::Triangle => "triangle",
+            MarkupKind::Circle => "circle",
+            MarkupKind::Square => "square",
+            MarkupKind::Cross => "cross",
+            MarkupKind::Highlight => "highlight",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<MarkupKind> {
+        match s {
+            "label" => Some(MarkupKind::Label),
+            "triangle" => Some(MarkupKind::Triangle),
+            "circle" => Some(MarkupKind::Circle),
+            "square" => Some(MarkupKind::Square),
+            "cross" => Some(MarkupKind::Cross),
+            "highlight" => Some(MarkupKind::Highlight),
+            _ => None,
+        }
+    }
+}
+
+/// 棋盘上的一个标记
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Markup {
+    pub position: Position,
+    pub kind: MarkupKind,
+    /// 只有文字标签有内容
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// 一手棋的评注和标记
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MoveNotes {
+    pub comment: Option<String>,
+    pub annotation: Option<Annotation>,
+    pub markup: Vec<Markup>,
+}
+
+impl MoveNotes {
+    pub fn is_empty(&self) -> bool {
+        self.comment.is_none() && self.annotation.is_none() && self.markup.is_empty()
+    }
+
+    /// 添加标记，同一格上已有的标记会被替换
+    pub fn set_markup(&mut self, markup: Markup) -> Result<(), String> {
+        if markup.position.x >= 15 || markup.position.y >= 15 {
+            return Err("Position out of bounds".to_string());
+        }
+        if markup.kind == MarkupKind::Label && markup.label.as_deref().is_none_or(str::is_empty) {
+            return Err("Label markup needs text".to_string());
+        }
+        self.remove_markup(&markup.position);
+        self.markup.push(markup);
+        Ok(())
+    }
+
+    pub fn remove_markup(&mut self, position: &Position) {
+        self.markup
+            .retain(|m| m.position.x != position.x || m.position.y != position.y);
+    }
+}
@@ -1,5 +1,10 @@
 use super::types::{Cell, Player};
 
+/// 棋盘大小
+pub const BOARD_SIZE: usize = 15;
+/// 天元坐标
+pub const CENTER: usize = BOARD_SIZE / 2;
+
 #[derive(Clone)]
 pub struct Board {
     grid: [[Cell; 15]; 15],
@@ -42,6 +47,14 @@ impl Board {
         matches!(self.get(x, y), Ok(Cell::Empty))
     }
 
+    /// `(x, y)` 周围 `distance` 格内是否有棋子
+    pub fn has_stone_near(&self, x: usize, y: usize, distance: usize) -> bool {
+        let end_x = (x + distance).min(BOARD_SIZE - 1);
+        let end_y = (y + distance).min(BOARD_SIZE - 1);
+        (x.saturating_sub(distance)..=end_x)
+            .any(|nx| (y.saturating_sub(distance)..=end_y).any(|ny| !self.is_empty(nx, ny)))
+    }
+
     pub fn clear(&mut self) {
         self.grid = [[Cell::Empty; 15]; 15];
     }
@@ -54,6 +67,17 @@ impl Board {
         Ok(())
     }
 
+    /// 提走一枚棋子，返回被提走的棋子颜色
+    pub fn remove(&mut self, x: usize, y: usize) -> Result<Player, String> {
+        let player = match self.get(x, y)? {
+            Cell::Black => Player::Black,
+            Cell::White => Player::White,
+            Cell::Empty => return Err("No stone to remove".to_string()),
+        };
+        self.grid[x][y] = Cell::Empty;
+        Ok(player)
+    }
+
     pub fn count_pieces(&self) -> usize {
         let mut count = 0;
         for row in &self.grid {
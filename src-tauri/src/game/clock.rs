@@ -0,0 +1,377 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::types::Player;
+
+/// 计时规则
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimeControl {
+    /// 不计时
+    Unlimited,
+    /// 包干制：用完即超时
+    SuddenDeath { main_ms: u64 },
+    /// 费舍尔制：每步后加秒
+    Fischer { main_ms: u64, increment_ms: u64 },
+    /// 读秒制：基本用时用完后进入 `periods` 次读秒，每次 `period_ms`
+    ByoYomi { main_ms: u64, period_ms: u64, periods: u32 },
+    /// 每步限时
+    PerMove { move_ms: u64 },
+}
+
+/// 单方的计时状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SideClock {
+    pub remaining_ms: u64,
+    pub periods_left: u32,
+}
+
+/// 棋钟
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    pub control: TimeControl,
+    pub black: SideClock,
+    pub white: SideClock,
+    /// 已超时的一方
+    pub flagged: Option<Player>,
+    running: Option<(Player, Instant)>,
+}
+
+/// 棋钟快照（返回给前端或保存到数据库）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockSnapshot {
+    pub control: TimeControl,
+    pub black: SideClock,
+    pub white: SideClock,
+    pub running: Option<Player>,
+    pub flagged: Option<Player>,
+}
+
+impl GameClock {
+    pub fn new(control: TimeControl) -> Self {
+        let side = match control {
+            TimeControl::Unlimited => SideClock { remaining_ms: 0, periods_left: 0 },
+            TimeControl::SuddenDeath { main_ms } | TimeControl::Fischer { main_ms, .. } => {
+                SideClock { remaining_ms: main_ms, periods_left: 0 }
+            }
+            TimeControl::ByoYomi { main_ms, periods, .. } => {
+                SideClock { remaining_ms: main_ms, periods_left: periods }
+            }
+            TimeControl::PerMove { move_ms } => SideClock { remaining_ms: move_ms, periods_left: 0 },
+        };
+
+        GameClock {
+            control,
+            black: side,
+            white: side,
+            flagged: None,
+            running: None,
+        }
+    }
+
+    /// 从保存的快照恢复（棋钟处于停止状态）
+    pub fn from_snapshot(snapshot: &ClockSnapshot) -> Self {
+        GameClock {
+            control: snapshot.control,
+            black: snapshot.black,
+            white: snapshot.white,
+            flagged: snapshot.flagged,
+            running: None,
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.control == TimeControl::Unlimited
+    }
+
+    /// 开始为 `player` 计时
+    pub fn start(&mut self, player: Player, now: Instant) {
+        if self.is_unlimited() || self.flagged.is_some() {
+            return;
+        }
+        self.running = Some((player, now));
+    }
+
+    /// 停止计时并结算用时，超时返回 Err(超时方)
+    pub fn stop(&mut self, now: Instant) -> Result<(), Player> {
+        let Some((player, started)) = self.running.take() else {
+            return Ok(());
+        };
+        let elapsed = now.saturating_duration_since(started).as_millis() as u64;
+        let control = self.control;
+        let side = self.side_mut(player);
+
+        let within_time = match control {
+            TimeControl::Unlimited => true,
+            TimeControl::SuddenDeath { .. } => Self::consume_main(side, elapsed),
+            TimeControl::Fischer { increment_ms, .. } => {
+                let ok = Self::consume_main(side, elapsed);
+                if ok {
+                    side.remaining_ms += increment_ms;
+                }
+                ok
+            }
+            TimeControl::ByoYomi { period_ms, .. } => Self::consume_byo_yomi(side, elapsed, period_ms),
+            TimeControl::PerMove { move_ms } => {
+                let ok = elapsed <= move_ms;
+                side.remaining_ms = move_ms;
+                ok
+            }
+        };
+
+        if within_time {
+            Ok(())
+        } else {
+            self.flagged = Some(player);
+            Err(player)
+        }
+    }
+
+    /// 检查正在计时的一方是否已超时（不停止棋钟）
+    pub fn check_flag(&mut self, now: Instant) -> Option<Player> {
+        if let Some((player, _)) = self.running {
+            if self.remaining_time(player, now).is_zero() {
+                self.running = None;
+                self.flagged = Some(player);
+            }
+        }
+        self.flagged
+    }
+
+    /// `player` 当前这一步剩余可用时间（含读秒）
+    pub fn remaining_time(&self, player: Player, now: Instant) -> Duration {
+        let side = self.side(player);
+        let elapsed = match self.running {
+            Some((running, started)) if running == player => {
+                now.saturating_duration_since(started).as_millis() as u64
+            }
+            _ => 0,
+        };
+
+        let available = match self.control {
+            TimeControl::Unlimited => u64::MAX,
+            TimeControl::ByoYomi { period_ms, .. } => {
+                side.remaining_ms + period_ms * side.periods_left as u64
+            }
+            _ => side.remaining_ms,
+        };
+
+        Duration::from_millis(available.saturating_sub(elapsed))
+    }
+
+    /// AI 本步可用的思考时间
+    pub fn time_budget(&self, player: Player, now: Instant) -> Option<Duration> {
+        if self.is_unlimited() {
+            return None;
+        }
+
+        let side = self.side(player);
+        let remaining = self.remaining_time(player, now).as_millis() as u64;
+        // 预计还需要约 30 步
+        let budget_ms = match self.control {
+            TimeControl::Unlimited => return None,
+            TimeControl::SuddenDeath { .. } => remaining / 30,
+            TimeControl::Fischer { increment_ms, .. } => remaining / 30 + increment_ms * 4 / 5,
+            TimeControl::ByoYomi { period_ms, .. } => {
+                if side.remaining_ms > 0 {
+                    side.remaining_ms / 30 + period_ms * 4 / 5
+                } else {
+                    period_ms * 4 / 5
+                }
+            }
+            TimeControl::PerMove { .. } => remaining * 4 / 5,
+        };
+
+        Some(Duration::from_millis(budget_ms.min(remaining)))
+    }
+
+    /// 交换双方的计时（开局交换执色后时间跟随棋手）
+    pub fn swap_sides(&mut self) {
+        std::mem::swap(&mut self.black, &mut self.white);
+        if let Some((player, started)) = self.running {
+            self.running = Some((player.opponent(), started));
+        }
+        self.flagged = self.flagged.map(|player| player.opponent());
+    }
+
+    pub fn snapshot(&self, now: Instant) -> ClockSnapshot {
+        let mut black = self.black;
+        let mut white = self.white;
+        // 快照中包含正在计时一方已用掉的时间
+        if let Some((player, started)) = self.running {
+            let elapsed = now.saturating_duration_since(started).as_millis() as u64;
+            let side = match player {
+                Player::Black => &mut black,
+                Player::White => &mut white,
+            };
+            match self.control {
+                TimeControl::ByoYomi { period_ms, .. } => {
+                    Self::consume_byo_yomi(side, elapsed, period_ms);
+                }
+                _ => side.remaining_ms = side.remaining_ms.saturating_sub(elapsed),
+            }
+        }
+
+        ClockSnapshot {
+            control: self.control,
+            black,
+            white,
+            running: self.running.map(|(player, _)| player),
+            flagged: self.flagged,
+        }
+    }
+
+    fn consume_main(side: &mut SideClock, elapsed: u64) -> bool {
+        let ok = elapsed <= side.remaining_ms;
+        side.remaining_ms = side.remaining_ms.saturating_sub(elapsed);
+        ok
+    }
+
+    fn consume_byo_yomi(side: &mut SideClock, elapsed: u64, period_ms: u64) -> bool {
+        let overflow = elapsed.saturating_sub(side.remaining_ms);
+        side.remaining_ms = side.remaining_ms.saturating_sub(elapsed);
+        // 在一次读秒内落子则该次读秒不消耗
+        let periods_used = (overflow / period_ms.max(1)) as u32;
+        if periods_used >= side.periods_left && overflow > 0 {
+            side.periods_left = 0;
+            false
+        } else {
+            side.periods_left -= periods_used;
+            true
+        }
+    }
+
+    fn side(&self, player: Player) -> &SideClock {
+        match player {
+            Player::Black => &self.black,
+            Player::White => &self.white,
+        }
+    }
+
+    fn side_mut(&mut self, player: Player) -> &mut SideClock {
+        match player {
+            Player::Black => &mut self.black,
+            Player::White => &mut self.white,
+        }
+    }
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self::new(TimeControl::Unlimited)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn swapping_sides_moves_time_with_the_players() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::SuddenDeath { main_ms: 10_000 });
+        clock.start(Player::Black, start);
+        clock.stop(start + ms(4_000)).unwrap();
+        clock.start(Player::White, start + ms(4_000));
+
+        // 白方选择交换后改执黑，正在用的时间记到黑方
+        clock.swap_sides();
+        clock.stop(start + ms(5_000)).unwrap();
+        assert_eq!(clock.black.remaining_ms, 9_000);
+        assert_eq!(clock.white.remaining_ms, 6_000);
+    }
+
+    #[test]
+    fn sudden_death_flags_when_main_time_runs_out() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::SuddenDeath { main_ms: 1_000 });
+        clock.start(Player::Black, start);
+        assert_eq!(clock.check_flag(start + ms(999)), None);
+        assert_eq!(clock.check_flag(start + ms(1_000)), Some(Player::Black));
+        assert_eq!(clock.flagged, Some(Player::Black));
+
+        // 超时后不再计时
+        clock.start(Player::White, start + ms(1_000));
+        assert_eq!(clock.stop(start + ms(5_000)), Ok(()));
+        assert_eq!(clock.white.remaining_ms, 1_000);
+    }
+
+    #[test]
+    fn fischer_adds_the_increment_after_each_move() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::Fischer { main_ms: 10_000, increment_ms: 2_000 });
+        clock.start(Player::Black, start);
+        clock.stop(start + ms(3_000)).unwrap();
+        assert_eq!(clock.black.remaining_ms, 9_000);
+
+        clock.start(Player::White, start);
+        assert_eq!(clock.stop(start + ms(10_001)), Err(Player::White));
+        assert_eq!(clock.white.remaining_ms, 0);
+        assert_eq!(clock.flagged, Some(Player::White));
+    }
+
+    #[test]
+    fn byo_yomi_consumes_only_overrun_periods() {
+        let start = Instant::now();
+        let control = TimeControl::ByoYomi { main_ms: 5_000, period_ms: 1_000, periods: 3 };
+        let mut clock = GameClock::new(control);
+
+        // 用完基本用时，在第一次读秒内落子：读秒不消耗
+        clock.start(Player::Black, start);
+        clock.stop(start + ms(5_500)).unwrap();
+        assert_eq!(clock.black, SideClock { remaining_ms: 0, periods_left: 3 });
+
+        // 用掉两次读秒后在第三次内落子
+        clock.start(Player::Black, start);
+        clock.stop(start + ms(2_500)).unwrap();
+        assert_eq!(clock.black, SideClock { remaining_ms: 0, periods_left: 1 });
+        assert_eq!(clock.remaining_time(Player::Black, start), ms(1_000));
+
+        clock.start(Player::Black, start);
+        assert_eq!(clock.stop(start + ms(1_000)), Err(Player::Black));
+        assert_eq!(clock.black.periods_left, 0);
+    }
+
+    #[test]
+    fn byo_yomi_snapshot_matches_stop() {
+        let start = Instant::now();
+        let control = TimeControl::ByoYomi { main_ms: 5_000, period_ms: 1_000, periods: 3 };
+        let mut clock = GameClock::new(control);
+        clock.start(Player::White, start);
+
+        let snapshot = clock.snapshot(start + ms(7_200));
+        assert_eq!(snapshot.running, Some(Player::White));
+        assert_eq!(snapshot.white, SideClock { remaining_ms: 0, periods_left: 1 });
+        assert_eq!(snapshot.black, SideClock { remaining_ms: 5_000, periods_left: 3 });
+
+        clock.stop(start + ms(7_200)).unwrap();
+        assert_eq!(clock.white, snapshot.white);
+    }
+
+    #[test]
+    fn per_move_limit_resets_every_move() {
+        let start = Instant::now();
+        let mut clock = GameClock::new(TimeControl::PerMove { move_ms: 3_000 });
+        clock.start(Player::Black, start);
+        assert_eq!(clock.snapshot(start + ms(2_000)).black.remaining_ms, 1_000);
+        clock.stop(start + ms(2_900)).unwrap();
+        assert_eq!(clock.black.remaining_ms, 3_000);
+
+        clock.start(Player::Black, start);
+        assert_eq!(clock.stop(start + ms(3_001)), Err(Player::Black));
+    }
+
+    #[test]
+    fn unlimited_never_flags() {
+        let start = Instant::now();
+        let mut clock = GameClock::default();
+        clock.start(Player::Black, start);
+        assert_eq!(clock.check_flag(start + Duration::from_secs(86_400)), None);
+        assert_eq!(clock.time_budget(Player::Black, start), None);
+    }
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+
+use super::board::Board;
+use super::rules::RulesValidator;
+use super::setup::SetupPosition;
+use super::types::{Cell, Player, Position, RuleSet};
+
+/// 摆棋编辑器：自由摆放双方棋子，设定行棋方后从该局面开始对局
+#[derive(Clone)]
+pub struct PositionEditor {
+    board: Board,
+    to_move: Player,
+    rule_set: RuleSet,
+}
+
+/// 编辑器状态（返回给前端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EditorState {
+    pub setup: SetupPosition,
+    pub rule_set: RuleSet,
+    pub black_stones: usize,
+    pub white_stones: usize,
+    /// 按当前规则校验不通过的原因
+    pub error: Option<String>,
+}
+
+impl PositionEditor {
+    pub fn new() -> Self {
+        PositionEditor {
+            board: Board::new(),
+            to_move: Player::Black,
+            rule_set: RuleSet::Freestyle,
+        }
+    }
+
+    /// 从已有棋盘开始编辑
+    pub fn from_board(board: &Board, to_move: Player, rule_set: RuleSet) -> Self {
+        PositionEditor {
+            board: board.clone(),
+            to_move,
+            rule_set,
+        }
+    }
+
+    pub fn rule_set(&self) -> RuleSet {
+        self.rule_set
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// 在 `pos` 摆放 `player` 的棋子（已有棋子会被替换）
+    pub fn place(&mut self, pos: &Position, player: Player) -> Result<(), String> {
+        self.board.clear_cell(pos.x, pos.y)?;
+        self.board.set(pos.x, pos.y, player)
+    }
+
+    /// 移除 `pos` 上的棋子
+    pub fn remove(&mut self, pos: &Position) -> Result<(), String> {
+        if self.board.is_empty(pos.x, pos.y) {
+            return Err("No stone to remove".to_string());
+        }
+        self.board.clear_cell(pos.x, pos.y)
+    }
+
+    pub fn set_to_move(&mut self, player: Player) {
+        self.to_move = player;
+    }
+
+    pub fn set_rule_set(&mut self, rule_set: RuleSet) {
+        self.rule_set = rule_set;
+    }
+
+    pub fn clear(&mut self) {
+        self.board.clear();
+        self.to_move = Player::Black;
+    }
+
+    /// 双方棋子数
+    pub fn stone_counts(&self) -> (usize, usize) {
+        let mut black = 0;
+        let mut white = 0;
+        for x in 0..15 {
+            for y in 0..15 {
+                match self.board.get(x, y) {
+                    Ok(Cell::Black) => black += 1,
+                    Ok(Cell::White) => white += 1,
+                    _ => {}
+                }
+            }
+        }
+        (black, white)
+    }
+
+    /// 检查局面能否按当前规则从此开始对局
+    pub fn validate(&self) -> Result<(), String> {
+        let rule_set = self.rule_set;
+        if rule_set == RuleSet::Connect6 {
+            return Err("Connect6 games cannot start from an edited position".to_string());
+        }
+
+        // 黑先轮流落子：轮到黑棋时双方子数相等，轮到白棋时黑棋多一子。
+        // 提子规则下棋子会被提走，子数不受此限制。
+        let (black, white) = self.stone_counts();
+        if rule_set != RuleSet::Pente {
+            let expected_extra = match self.to_move {
+                Player::Black => 0,
+                Player::White => 1,
+            };
+            if black != white + expected_extra {
+                return Err(format!(
+                    "Impossible stone counts: {} black and {} white with {} to move",
+                    black,
+                    white,
+                    match self.to_move {
+                        Player::Black => "black",
+                        Player::White => "white",
+                    }
+                ));
+            }
+        }
+
+        if self.board.is_full() {
+            return Err("Board is full".to_string());
+        }
+
+        // 已经连五的局面无法继续
+        for x in 0..15 {
+            for y in 0..15 {
+                if self.board.is_empty(x, y) {
+                    continue;
+                }
+                if RulesValidator::check_win(&self.board, &Position { x, y }, rule_set).is_some() {
+                    return Err(format!("Position already contains a completed line at ({}, {})", x, y));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 转换为对局的初始局面
+    pub fn to_setup(&self) -> SetupPosition {
+        SetupPosition::from_board(&self.board, self.to_move)
+    }
+
+    pub fn state(&self) -> EditorState {
+        let (black_stones, white_stones) = self.stone_counts();
+        EditorState {
+            setup: self.to_setup(),
+            rule_set: self.rule_set,
+            black_stones,
+            white_stones,
+            error: self.validate().err(),
+        }
+    }
+}
+
+impl Default for PositionEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,27 @@
+pub mod annotation;
 pub mod board;
+pub mod clock;
+pub mod editor;
+pub mod notation;
+pub mod opening;
+pub mod position_hash;
+pub mod rating;
+pub mod replay;
 pub mod rules;
+pub mod setup;
+pub mod tree;
 pub mod types;
 
-pub use board::Board;
-pub use rules::RulesValidator;
+pub use annotation::{Annotation, Markup, MarkupKind, MoveNotes};
+pub use board::{Board, BOARD_SIZE, CENTER};
+pub use clock::{ClockSnapshot, GameClock, SideClock, TimeControl};
+pub use editor::{EditorState, PositionEditor};
+pub use notation::PositionNotation;
+pub use opening::{OpeningDecision, OpeningPhase, OpeningRule, OpeningState};
+pub use position_hash::{canonical_hash, canonical_symmetry, find_symmetry, indexed_positions, inverse_symmetry, transform_position, IndexedPosition};
+pub use rating::Rating;
+pub use replay::{GameReplay, ReplayCursor};
+pub use rules::{Forbidden, RulesValidator, CAPTURES_TO_WIN};
+pub use setup::{SetupPosition, SetupStone};
+pub use tree::{GameTree, GameTreeState, MoveNode, NodeId};
 pub use types::*;
\ No newline at end of file
@@ -0,0 +1,265 @@
+use super::board::{Board, BOARD_SIZE};
+use super::types::{Cell, Player, Position, RuleSet};
+
+/// 列字母（从左到右）
+const COLUMNS: &[u8] = b"abcdefghijklmno";
+
+impl Position {
+    /// 坐标记法：列字母 + 行号（行号从下往上数），如天元为 `h8`
+    pub fn to_notation(&self) -> String {
+        format!("{}{}", COLUMNS[self.y] as char, BOARD_SIZE - self.x)
+    }
+
+    /// 解析坐标记法（不区分大小写）
+    pub fn from_notation(s: &str) -> Result<Position, String> {
+        let s = s.trim().to_ascii_lowercase();
+        let mut chars = s.chars();
+        let column = chars.next().ok_or("Empty coordinate")?;
+        let y = COLUMNS
+            .iter()
+            .position(|&c| c as char == column)
+            .ok_or_else(|| format!("Invalid column in coordinate: {}", s))?;
+        let row: usize = chars
+            .as_str()
+            .parse()
+            .map_err(|_| format!("Invalid row in coordinate: {}", s))?;
+        if row == 0 || row > BOARD_SIZE {
+            return Err(format!("Row out of range in coordinate: {}", s));
+        }
+        Ok(Position { x: BOARD_SIZE - row, y })
+    }
+}
+
+impl Board {
+    /// 棋子部分的记法：从上到下每行一段，用 `/` 分隔；
+    /// `b`/`w` 为黑白棋子，数字为连续空位数
+    pub fn to_notation(&self) -> String {
+        let mut rows = Vec::with_capacity(BOARD_SIZE);
+        for x in 0..BOARD_SIZE {
+            let mut row = String::new();
+            let mut empty = 0;
+            for y in 0..BOARD_SIZE {
+                let stone = match self.get(x, y) {
+                    Ok(Cell::Black) => 'b',
+                    Ok(Cell::White) => 'w',
+                    _ => {
+                        empty += 1;
+                        continue;
+                    }
+                };
+                if empty > 0 {
+                    row.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                row.push(stone);
+            }
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+            rows.push(row);
+        }
+        rows.join("/")
+    }
+
+    /// 解析棋子部分的记法
+    pub fn from_notation(s: &str) -> Result<Board, String> {
+        let rows: Vec<&str> = s.trim().split('/').collect();
+        if rows.len() != BOARD_SIZE {
+            return Err(format!("Expected {} rows, found {}", BOARD_SIZE, rows.len()));
+        }
+
+        let mut board = Board::new();
+        for (x, row) in rows.iter().enumerate() {
+            let mut y = 0;
+            let mut digits = String::new();
+            for c in row.chars() {
+                if c.is_ascii_digit() {
+                    digits.push(c);
+                    continue;
+                }
+                if !digits.is_empty() {
+                    y = skip_empty(y, &digits, x)?;
+                    digits.clear();
+                }
+                let player = match c {
+                    'b' | 'B' => Player::Black,
+                    'w' | 'W' => Player::White,
+                    _ => return Err(format!("Invalid character '{}' in row {}", c, x + 1)),
+                };
+                if y >= BOARD_SIZE {
+                    return Err(format!("Row {} is too long", x + 1));
+                }
+                board.set(x, y, player)?;
+                y += 1;
+            }
+            if !digits.is_empty() {
+                y = skip_empty(y, &digits, x)?;
+            }
+            if y != BOARD_SIZE {
+                return Err(format!("Row {} has {} cells, expected {}", x + 1, y, BOARD_SIZE));
+            }
+        }
+        Ok(board)
+    }
+}
+
+/// 在第 `x` 行的 `y` 列之后跳过 `digits` 个空位，返回新的列号
+fn skip_empty(y: usize, digits: &str, x: usize) -> Result<usize, String> {
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|empty| y.checked_add(empty))
+        .ok_or_else(|| format!("Row {} is too long", x + 1))
+}
+
+/// 局面记法：`<大小> <规则> <棋子> <行棋方>`，
+/// 如 `15 freestyle 15/15/15/15/15/15/15/7b7/15/15/15/15/15/15/15 w`
+#[derive(Clone)]
+pub struct PositionNotation {
+    pub board: Board,
+    pub rule_set: RuleSet,
+    pub to_move: Player,
+}
+
+impl PositionNotation {
+    pub fn format(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            BOARD_SIZE,
+            self.rule_set.as_str(),
+            self.board.to_notation(),
+            match self.to_move {
+                Player::Black => "b",
+                Player::White => "w",
+            }
+        )
+    }
+
+    pub fn parse(s: &str) -> Result<PositionNotation, String> {
+        let fields: Vec<&str> = s.split_whitespace().collect();
+        let [size, rule_set, stones, to_move] = fields[..] else {
+            return Err("Position must have size, rule set, stones and side to move".to_string());
+        };
+
+        if size.parse::<usize>().ok() != Some(BOARD_SIZE) {
+            return Err(format!("Unsupported board size: {}", size));
+        }
+        let rule_set = RuleSet::parse(rule_set)
+            .ok_or_else(|| format!("Unknown rule set: {}", rule_set))?;
+        let board = Board::from_notation(stones)?;
+        let to_move = match to_move {
+            "b" | "B" => Player::Black,
+            "w" | "W" => Player::White,
+            _ => return Err(format!("Invalid side to move: {}", to_move)),
+        };
+
+        Ok(PositionNotation { board, rule_set, to_move })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 简单的伪随机数（xorshift），保证测试可重复
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    /// 按 `density`（0-100）随机摆放黑白棋子
+    fn random_board(rng: &mut Rng, density: u64) -> Board {
+        let mut board = Board::new();
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                let roll = rng.next();
+                if roll % 100 < density {
+                    let player = if roll & 0x100 == 0 { Player::Black } else { Player::White };
+                    board.set(x, y, player).unwrap();
+                }
+            }
+        }
+        board
+    }
+
+    fn assert_same_board(a: &Board, b: &Board) {
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                assert_eq!(a.get(x, y).unwrap(), b.get(x, y).unwrap(), "cell ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn coordinates_round_trip() {
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                let notation = Position { x, y }.to_notation();
+                for text in [notation.clone(), notation.to_ascii_uppercase()] {
+                    let parsed = Position::from_notation(&text).unwrap();
+                    assert_eq!((parsed.x, parsed.y), (x, y), "{}", text);
+                }
+            }
+        }
+        let center = Position::from_notation("h8").unwrap();
+        assert_eq!((center.x, center.y), (7, 7));
+    }
+
+    #[test]
+    fn invalid_coordinates_are_rejected() {
+        for text in ["", "h", "h0", "h16", "p8", "8h", "h-1", "h18446744073709551616"] {
+            assert!(Position::from_notation(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn boards_round_trip() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        for density in [0, 5, 30, 60, 100] {
+            for _ in 0..50 {
+                let board = random_board(&mut rng, density);
+                let notation = board.to_notation();
+                let parsed = Board::from_notation(&notation).unwrap();
+                assert_same_board(&board, &parsed);
+                assert_eq!(parsed.to_notation(), notation);
+            }
+        }
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+        let rule_sets = [RuleSet::Freestyle, RuleSet::Renju, RuleSet::Connect6, RuleSet::Pente];
+        for rule_set in rule_sets {
+            for to_move in [Player::Black, Player::White] {
+                for _ in 0..20 {
+                    let density = rng.next() % 101;
+                    let position = PositionNotation { board: random_board(&mut rng, density), rule_set, to_move };
+                    let text = position.format();
+                    let parsed = PositionNotation::parse(&text).unwrap();
+                    assert_eq!(parsed.rule_set, rule_set);
+                    assert_eq!(parsed.to_move, to_move);
+                    assert_same_board(&position.board, &parsed.board);
+                    assert_eq!(parsed.format(), text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_boards_are_rejected() {
+        let empty_rows = vec!["15"; BOARD_SIZE - 1].join("/");
+        for first_row in ["b18446744073709551615", "18446744073709551615b", "99999999999999999999", "16", "14", "b14w", "7x7"] {
+            let notation = format!("{}/{}", first_row, empty_rows);
+            assert!(Board::from_notation(&notation).is_err(), "{}", first_row);
+        }
+        assert!(Board::from_notation(&empty_rows).is_err());
+        assert!(PositionNotation::parse("19 freestyle 15 b").is_err());
+    }
+}
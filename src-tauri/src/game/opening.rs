@@ -0,0 +1,547 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::board::Board;
+use super::types::{Player, Position};
+
+/// 开局规则
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpeningRule {
+    Standard,   // 无开局规则，黑棋直接先行
+    Swap,       // 三手交换
+    Swap2,      // 三手交换后可选择再下两子
+    Soosorv,    // Soosõrv：前三手固定区域，三手、四手后各可交换
+    Taraguchi,  // Taraguchi：每手限定区域，每手后均可交换，第五手可改为打点 10 个
+    Renju,      // 连珠 RIF：三手交换、五手两打
+}
+
+impl OpeningRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpeningRule::Standard => "standard",
+            OpeningRule::Swap => "swap",
+            OpeningRule::Swap2 => "swap2",
+            OpeningRule::Soosorv => "soosorv",
+            OpeningRule::Taraguchi => "taraguchi",
+            OpeningRule::Renju => "renju",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<OpeningRule> {
+        match s {
+            "standard" => Some(OpeningRule::Standard),
+            "swap" => Some(OpeningRule::Swap),
+            "swap2" => Some(OpeningRule::Swap2),
+            "soosorv" => Some(OpeningRule::Soosorv),
+            "taraguchi" => Some(OpeningRule::Taraguchi),
+            "renju" => Some(OpeningRule::Renju),
+            _ => None,
+        }
+    }
+}
+
+/// 开局阶段
+///
+/// `by` 表示由当前执该颜色的一方行动（交换后执色的人会变）。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OpeningPhase {
+    /// 落下一枚 `stone` 颜色的棋子；`zone` 为距天元的最大距离
+    PlaceStone {
+        stone: Player,
+        by: Player,
+        zone: Option<usize>,
+    },
+    /// 选择是否交换执色
+    SwapChoice { by: Player },
+    /// Swap2：白方选择执黑、执白或再下两子
+    Swap2Choice { by: Player },
+    /// Soosõrv：第四手后声明第五手的打点数量（1..=max）
+    DeclareOffers { by: Player, max: usize },
+    /// Taraguchi：第五手正常落子，或改为给出 `offers` 个打点
+    FifthMoveChoice { by: Player, offers: usize },
+    /// 五手 N 打：给出 `count` 个互不对称的第五手候选
+    OfferFifth { by: Player, count: usize },
+    /// 从候选中选择一个作为第五手（通过 place_stone 落子）
+    ChooseOffer { by: Player },
+}
+
+impl OpeningPhase {
+    /// 当前阶段由执哪一色的一方行动
+    pub fn actor(&self) -> Player {
+        match *self {
+            OpeningPhase::PlaceStone { by, .. }
+            | OpeningPhase::SwapChoice { by }
+            | OpeningPhase::Swap2Choice { by }
+            | OpeningPhase::DeclareOffers { by, .. }
+            | OpeningPhase::FifthMoveChoice { by, .. }
+            | OpeningPhase::OfferFifth { by, .. }
+            | OpeningPhase::ChooseOffer { by } => by,
+        }
+    }
+}
+
+/// 开局阶段的选择
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpeningDecision {
+    KeepColor,  // 保持执色
+    SwapColor,  // 交换执色
+    PlaceTwo,   // Swap2：再下一白一黑两子，交由对方选择
+    PlaceOne,   // Taraguchi：第五手正常落子
+    OfferMany,  // Taraguchi：第五手改为打点
+    DeclareOffers { count: usize },  // Soosõrv：声明打点数量
+}
+
+/// 开局状态机
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpeningState {
+    pub rule: OpeningRule,
+    /// 交换次数为奇数时为 true，即先手方当前执白
+    pub swapped: bool,
+    /// 开局阶段已落的棋子数
+    pub stones_placed: usize,
+    /// 五手打点的候选位置
+    pub offers: Vec<Position>,
+    /// 被选中的打点
+    pub chosen_offer: Option<Position>,
+    pending: VecDeque<OpeningPhase>,
+}
+
+impl OpeningState {
+    pub fn new(rule: OpeningRule) -> Self {
+        use OpeningPhase::*;
+        use Player::{Black, White};
+
+        let place = |stone, by, zone| PlaceStone { stone, by, zone };
+        let pending: Vec<OpeningPhase> = match rule {
+            OpeningRule::Standard => vec![],
+            OpeningRule::Swap => vec![
+                place(Black, Black, None),
+                place(White, Black, None),
+                place(Black, Black, None),
+                SwapChoice { by: White },
+            ],
+            OpeningRule::Swap2 => vec![
+                place(Black, Black, None),
+                place(White, Black, None),
+                place(Black, Black, None),
+                Swap2Choice { by: White },
+            ],
+            OpeningRule::Soosorv => vec![
+                place(Black, Black, Some(0)),
+                place(White, Black, Some(1)),
+                place(Black, Black, Some(2)),
+                SwapChoice { by: White },
+                place(White, White, None),
+                DeclareOffers { by: White, max: 8 },
+                SwapChoice { by: Black },
+                OfferFifth { by: Black, count: 1 },
+                ChooseOffer { by: White },
+            ],
+            OpeningRule::Taraguchi => vec![
+                place(Black, Black, Some(0)),
+                SwapChoice { by: White },
+                place(White, White, Some(1)),
+                SwapChoice { by: Black },
+                place(Black, Black, Some(2)),
+                SwapChoice { by: White },
+                place(White, White, Some(3)),
+                SwapChoice { by: Black },
+                FifthMoveChoice { by: Black, offers: 10 },
+            ],
+            OpeningRule::Renju => vec![
+                place(Black, Black, Some(0)),
+                place(White, Black, Some(1)),
+                place(Black, Black, Some(2)),
+                SwapChoice { by: White },
+                place(White, White, None),
+                OfferFifth { by: Black, count: 2 },
+                ChooseOffer { by: White },
+            ],
+        };
+
+        OpeningState {
+            rule,
+            swapped: false,
+            stones_placed: 0,
+            offers: Vec::new(),
+            chosen_offer: None,
+            pending: pending.into(),
+        }
+    }
+
+    /// 已结束的开局（用于加载棋局）
+    pub fn finished(rule: OpeningRule, stones_placed: usize) -> Self {
+        OpeningState {
+            rule,
+            swapped: false,
+            stones_placed,
+            offers: Vec::new(),
+            chosen_offer: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// 当前等待的阶段，开局结束后为 None
+    pub fn phase(&self) -> Option<OpeningPhase> {
+        self.pending.front().copied()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// 开局阶段落子
+    pub fn place_stone(&mut self, pos: &Position, stone: Player) -> Result<(), String> {
+        match self.phase() {
+            Some(OpeningPhase::PlaceStone { stone: expected, zone, .. }) => {
+                if stone != expected {
+                    return Err("Wrong stone color for opening".to_string());
+                }
+                if let Some(zone) = zone {
+                    if !Self::in_zone(pos, zone) {
+                        return Err("Stone outside the allowed opening area".to_string());
+                    }
+                }
+                self.pending.pop_front();
+                self.stones_placed += 1;
+                Ok(())
+            }
+            Some(OpeningPhase::ChooseOffer { .. }) => {
+                if !self.offers.iter().any(|o| o.x == pos.x && o.y == pos.y) {
+                    return Err("Move is not one of the offered moves".to_string());
+                }
+                self.pending.pop_front();
+                self.stones_placed += 1;
+                self.chosen_offer = Some(pos.clone());
+                Ok(())
+            }
+            Some(OpeningPhase::OfferFifth { .. }) => Err("Waiting for fifth move offers".to_string()),
+            Some(_) => Err("Waiting for an opening decision".to_string()),
+            None => Err("Opening already finished".to_string()),
+        }
+    }
+
+    /// 开局阶段的选择
+    pub fn decide(&mut self, decision: OpeningDecision) -> Result<(), String> {
+        let phase = match self.phase() {
+            Some(OpeningPhase::PlaceStone { .. }) | Some(OpeningPhase::ChooseOffer { .. }) => {
+                return Err("Waiting for an opening stone".to_string());
+            }
+            Some(OpeningPhase::OfferFifth { .. }) => {
+                return Err("Waiting for fifth move offers".to_string());
+            }
+            Some(phase) => phase,
+            None => return Err("Opening already finished".to_string()),
+        };
+
+        match (phase, decision) {
+            (OpeningPhase::SwapChoice { .. }, OpeningDecision::KeepColor)
+            | (OpeningPhase::SwapChoice { .. }, OpeningDecision::SwapColor)
+            | (OpeningPhase::Swap2Choice { .. }, OpeningDecision::KeepColor)
+            | (OpeningPhase::Swap2Choice { .. }, OpeningDecision::SwapColor) => {
+                self.pending.pop_front();
+                if decision == OpeningDecision::SwapColor {
+                    self.swapped = !self.swapped;
+                }
+                Ok(())
+            }
+            (OpeningPhase::Swap2Choice { by }, OpeningDecision::PlaceTwo) => {
+                self.pending.pop_front();
+                let by_other = by.opponent();
+                self.pending.push_front(OpeningPhase::SwapChoice { by: by_other });
+                self.pending.push_front(OpeningPhase::PlaceStone { stone: Player::Black, by, zone: None });
+                self.pending.push_front(OpeningPhase::PlaceStone { stone: Player::White, by, zone: None });
+                Ok(())
+            }
+            (OpeningPhase::DeclareOffers { max, .. }, OpeningDecision::DeclareOffers { count }) => {
+                if count == 0 || count > max {
+                    return Err(format!("Offer count must be between 1 and {}", max));
+                }
+                self.pending.pop_front();
+                for phase in self.pending.iter_mut() {
+                    if let OpeningPhase::OfferFifth { count: pending_count, .. } = phase {
+                        *pending_count = count;
+                    }
+                }
+                Ok(())
+            }
+            (OpeningPhase::FifthMoveChoice { by, .. }, OpeningDecision::PlaceOne) => {
+                self.pending.pop_front();
+                self.pending.push_front(OpeningPhase::SwapChoice { by: by.opponent() });
+                self.pending.push_front(OpeningPhase::PlaceStone { stone: Player::Black, by, zone: Some(4) });
+                Ok(())
+            }
+            (OpeningPhase::FifthMoveChoice { by, offers }, OpeningDecision::OfferMany) => {
+                self.pending.pop_front();
+                self.pending.push_front(OpeningPhase::ChooseOffer { by: by.opponent() });
+                self.pending.push_front(OpeningPhase::OfferFifth { by, count: offers });
+                Ok(())
+            }
+            _ => Err("Decision not allowed in this opening phase".to_string()),
+        }
+    }
+
+    /// 提交第五手的候选打点
+    pub fn offer_fifth_moves(&mut self, board: &Board, offers: &[Position]) -> Result<(), String> {
+        let count = match self.phase() {
+            Some(OpeningPhase::OfferFifth { count, .. }) => count,
+            _ => return Err("Not waiting for fifth move offers".to_string()),
+        };
+
+        if offers.len() != count {
+            return Err(format!("Exactly {} offers are required", count));
+        }
+
+        for (i, offer) in offers.iter().enumerate() {
+            if !board.is_empty(offer.x, offer.y) {
+                return Err("Offered position is not empty".to_string());
+            }
+            // 候选之间不能相同或互为对称
+            if offers[..i].iter().any(|other| Self::equivalent_moves(board, offer, other)) {
+                return Err("Offered moves must not be identical or symmetric".to_string());
+            }
+        }
+
+        self.pending.pop_front();
+        self.offers = offers.to_vec();
+        Ok(())
+    }
+
+    /// 两个落点在当前局面的对称变换下是否等价
+    pub fn equivalent_moves(board: &Board, a: &Position, b: &Position) -> bool {
+        (0..8).any(|symmetry| {
+            let (x, y) = Self::transform(a.x, a.y, symmetry);
+            if (x, y) != (b.x, b.y) {
+                return false;
+            }
+            // 局面本身必须在该变换下不变
+            (0..15).all(|sx| {
+                (0..15).all(|sy| {
+                    let (tx, ty) = Self::transform(sx, sy, symmetry);
+                    board.get(sx, sy).ok() == board.get(tx, ty).ok()
+                })
+            })
+        })
+    }
+
+    /// 以天元为中心的 8 种对称变换
+    pub(crate) fn transform(x: usize, y: usize, symmetry: usize) -> (usize, usize) {
+        match symmetry {
+            0 => (x, y),
+            1 => (y, x),
+            2 => (14 - x, y),
+            3 => (x, 14 - y),
+            4 => (14 - x, 14 - y),
+            5 => (14 - y, x),
+            6 => (y, 14 - x),
+            _ => (14 - y, 14 - x),
+        }
+    }
+
+    /// 检查是否在距天元 `zone` 格以内
+    pub fn in_zone(pos: &Position, zone: usize) -> bool {
+        pos.x.abs_diff(7) <= zone && pos.y.abs_diff(7) <= zone
+    }
+}
+
+impl Default for OpeningState {
+    fn default() -> Self {
+        Self::new(OpeningRule::Standard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use OpeningPhase::*;
+    use Player::{Black, White};
+
+    fn pos(x: usize, y: usize) -> Position {
+        Position { x, y }
+    }
+
+    /// 开局落子，同时落在棋盘上
+    fn place(opening: &mut OpeningState, board: &mut Board, x: usize, y: usize, stone: Player) {
+        opening.place_stone(&pos(x, y), stone).unwrap();
+        board.set(x, y, stone).unwrap();
+    }
+
+    #[test]
+    fn standard_has_no_opening() {
+        let opening = OpeningState::new(OpeningRule::Standard);
+        assert!(opening.is_finished());
+        assert_eq!(opening.phase(), None);
+    }
+
+    #[test]
+    fn swap_lets_white_choose_colors_after_three_stones() {
+        let mut opening = OpeningState::new(OpeningRule::Swap);
+        let mut board = Board::new();
+        assert_eq!(opening.phase(), Some(PlaceStone { stone: Black, by: Black, zone: None }));
+        assert!(opening.place_stone(&pos(7, 7), White).is_err());
+        assert!(opening.decide(OpeningDecision::SwapColor).is_err());
+
+        place(&mut opening, &mut board, 7, 7, Black);
+        place(&mut opening, &mut board, 7, 8, White);
+        place(&mut opening, &mut board, 8, 8, Black);
+        assert_eq!(opening.phase(), Some(SwapChoice { by: White }));
+        assert!(opening.place_stone(&pos(9, 9), White).is_err());
+        assert!(opening.decide(OpeningDecision::PlaceTwo).is_err());
+
+        opening.decide(OpeningDecision::SwapColor).unwrap();
+        assert!(opening.is_finished());
+        assert!(opening.swapped);
+        assert_eq!(opening.stones_placed, 3);
+    }
+
+    #[test]
+    fn swap2_can_keep_or_swap_directly() {
+        for (decision, swapped) in [(OpeningDecision::KeepColor, false), (OpeningDecision::SwapColor, true)] {
+            let mut opening = OpeningState::new(OpeningRule::Swap2);
+            let mut board = Board::new();
+            place(&mut opening, &mut board, 7, 7, Black);
+            place(&mut opening, &mut board, 7, 8, White);
+            place(&mut opening, &mut board, 8, 8, Black);
+            assert_eq!(opening.phase(), Some(Swap2Choice { by: White }));
+            opening.decide(decision).unwrap();
+            assert!(opening.is_finished());
+            assert_eq!(opening.swapped, swapped);
+        }
+    }
+
+    #[test]
+    fn swap2_place_two_hands_the_choice_back() {
+        let mut opening = OpeningState::new(OpeningRule::Swap2);
+        let mut board = Board::new();
+        place(&mut opening, &mut board, 7, 7, Black);
+        place(&mut opening, &mut board, 7, 8, White);
+        place(&mut opening, &mut board, 8, 8, Black);
+        opening.decide(OpeningDecision::PlaceTwo).unwrap();
+
+        assert_eq!(opening.phase(), Some(PlaceStone { stone: White, by: White, zone: None }));
+        place(&mut opening, &mut board, 6, 6, White);
+        assert_eq!(opening.phase(), Some(PlaceStone { stone: Black, by: White, zone: None }));
+        place(&mut opening, &mut board, 9, 9, Black);
+        assert_eq!(opening.phase(), Some(SwapChoice { by: Black }));
+        opening.decide(OpeningDecision::SwapColor).unwrap();
+
+        assert!(opening.is_finished());
+        assert!(opening.swapped);
+        assert_eq!(opening.stones_placed, 5);
+    }
+
+    #[test]
+    fn soosorv_declares_the_number_of_fifth_move_offers() {
+        let mut opening = OpeningState::new(OpeningRule::Soosorv);
+        let mut board = Board::new();
+        assert!(opening.place_stone(&pos(7, 8), Black).is_err());
+        place(&mut opening, &mut board, 7, 7, Black);
+        assert!(opening.place_stone(&pos(7, 9), White).is_err());
+        place(&mut opening, &mut board, 7, 8, White);
+        place(&mut opening, &mut board, 9, 9, Black);
+        assert_eq!(opening.phase(), Some(SwapChoice { by: White }));
+        opening.decide(OpeningDecision::KeepColor).unwrap();
+        place(&mut opening, &mut board, 6, 6, White);
+
+        assert_eq!(opening.phase(), Some(DeclareOffers { by: White, max: 8 }));
+        assert!(opening.decide(OpeningDecision::DeclareOffers { count: 0 }).is_err());
+        assert!(opening.decide(OpeningDecision::DeclareOffers { count: 9 }).is_err());
+        opening.decide(OpeningDecision::DeclareOffers { count: 3 }).unwrap();
+        assert_eq!(opening.phase(), Some(SwapChoice { by: Black }));
+        opening.decide(OpeningDecision::SwapColor).unwrap();
+
+        assert_eq!(opening.phase(), Some(OfferFifth { by: Black, count: 3 }));
+        assert!(opening.offer_fifth_moves(&board, &[pos(3, 3), pos(11, 3)]).is_err());
+        let offers = [pos(3, 3), pos(11, 3), pos(10, 4)];
+        opening.offer_fifth_moves(&board, &offers).unwrap();
+
+        assert_eq!(opening.phase(), Some(ChooseOffer { by: White }));
+        assert!(opening.place_stone(&pos(5, 5), Black).is_err());
+        place(&mut opening, &mut board, 11, 3, Black);
+        assert!(opening.is_finished());
+        assert!(opening.swapped);
+        assert_eq!(opening.stones_placed, 5);
+        assert_eq!(opening.chosen_offer, Some(pos(11, 3)));
+        assert_eq!(opening.offers, offers);
+    }
+
+    #[test]
+    fn taraguchi_offers_a_swap_after_every_stone() {
+        let mut opening = OpeningState::new(OpeningRule::Taraguchi);
+        let mut board = Board::new();
+        let stones = [(7, 7, Black), (8, 8, White), (9, 7, Black), (4, 7, White)];
+        for (index, &(x, y, stone)) in stones.iter().enumerate() {
+            assert_eq!(opening.phase(), Some(PlaceStone { stone, by: stone, zone: Some(index) }));
+            place(&mut opening, &mut board, x, y, stone);
+            assert_eq!(opening.phase(), Some(SwapChoice { by: stone.opponent() }));
+            opening.decide(OpeningDecision::KeepColor).unwrap();
+        }
+        assert_eq!(opening.phase(), Some(FifthMoveChoice { by: Black, offers: 10 }));
+
+        let mut place_one = opening.clone();
+        place_one.decide(OpeningDecision::PlaceOne).unwrap();
+        assert_eq!(place_one.phase(), Some(PlaceStone { stone: Black, by: Black, zone: Some(4) }));
+        assert!(place_one.place_stone(&pos(2, 2), Black).is_err());
+        place_one.place_stone(&pos(3, 3), Black).unwrap();
+        assert_eq!(place_one.phase(), Some(SwapChoice { by: White }));
+        place_one.decide(OpeningDecision::SwapColor).unwrap();
+        assert!(place_one.is_finished());
+        assert_eq!(place_one.stones_placed, 5);
+
+        opening.decide(OpeningDecision::OfferMany).unwrap();
+        assert_eq!(opening.phase(), Some(OfferFifth { by: Black, count: 10 }));
+        let offers: Vec<Position> = (0..10).map(|x| pos(x, 0)).collect();
+        opening.offer_fifth_moves(&board, &offers).unwrap();
+        assert_eq!(opening.phase(), Some(ChooseOffer { by: White }));
+        place(&mut opening, &mut board, 4, 0, Black);
+        assert!(opening.is_finished());
+        assert!(!opening.swapped);
+    }
+
+    #[test]
+    fn renju_rejects_symmetric_fifth_move_offers() {
+        let mut opening = OpeningState::new(OpeningRule::Renju);
+        let mut board = Board::new();
+        place(&mut opening, &mut board, 7, 7, Black);
+        place(&mut opening, &mut board, 7, 8, White);
+        place(&mut opening, &mut board, 7, 9, Black);
+        assert_eq!(opening.phase(), Some(SwapChoice { by: White }));
+        opening.decide(OpeningDecision::KeepColor).unwrap();
+        place(&mut opening, &mut board, 7, 5, White);
+        assert_eq!(opening.phase(), Some(OfferFifth { by: Black, count: 2 }));
+
+        // 棋子都在中线上，中线两侧对称的落点等价
+        assert!(opening.offer_fifth_moves(&board, &[pos(6, 7), pos(8, 7)]).is_err());
+        assert!(opening.offer_fifth_moves(&board, &[pos(6, 7), pos(6, 7)]).is_err());
+        assert!(opening.offer_fifth_moves(&board, &[pos(7, 7), pos(6, 7)]).is_err());
+        opening.offer_fifth_moves(&board, &[pos(6, 7), pos(6, 8)]).unwrap();
+
+        assert_eq!(opening.phase(), Some(ChooseOffer { by: White }));
+        place(&mut opening, &mut board, 6, 8, Black);
+        assert!(opening.is_finished());
+        assert_eq!(opening.stones_placed, 5);
+    }
+
+    #[test]
+    fn unfinished_opening_survives_serialization() {
+        let mut opening = OpeningState::new(OpeningRule::Swap2);
+        let mut board = Board::new();
+        place(&mut opening, &mut board, 7, 7, Black);
+        place(&mut opening, &mut board, 7, 8, White);
+        place(&mut opening, &mut board, 8, 8, Black);
+        opening.decide(OpeningDecision::PlaceTwo).unwrap();
+
+        let json = serde_json::to_string(&opening).unwrap();
+        let mut restored: OpeningState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rule, OpeningRule::Swap2);
+        assert_eq!(restored.stones_placed, 3);
+        assert_eq!(restored.phase(), opening.phase());
+        place(&mut restored, &mut board, 6, 6, White);
+        place(&mut restored, &mut board, 9, 9, Black);
+        assert_eq!(restored.phase(), Some(SwapChoice { by: Black }));
+    }
+}
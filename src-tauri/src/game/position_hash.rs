@@ -0,0 +1,154 @@
+use super::board::{Board, BOARD_SIZE};
+use super::opening::OpeningState;
+use super::replay::GameReplay;
+use super::setup::SetupPosition;
+use super::types::{Cell, Player, Position, RuleSet};
+
+/// 规范方向：8 种对称变换中使棋盘字典序最小的一种（相同时取编号小的），以及变换后的棋盘
+fn canonical_form(board: &Board) -> (usize, [u8; BOARD_SIZE * BOARD_SIZE]) {
+    let (cells, symmetry) = (0..8)
+        .map(|symmetry| {
+            let mut cells = [0u8; BOARD_SIZE * BOARD_SIZE];
+            for x in 0..BOARD_SIZE {
+                for y in 0..BOARD_SIZE {
+                    let (tx, ty) = OpeningState::transform(x, y, symmetry);
+                    cells[tx * BOARD_SIZE + ty] = match board.get(x, y) {
+                        Ok(Cell::Black) => 1,
+                        Ok(Cell::White) => 2,
+                        _ => 0,
+                    };
+                }
+            }
+            (cells, symmetry)
+        })
+        .min()
+        .unwrap_or(([0u8; BOARD_SIZE * BOARD_SIZE], 0));
+    (symmetry, cells)
+}
+
+/// 局面的规范哈希：取 8 种对称变换中字典序最小的棋盘，
+/// 旋转或翻转后相同的局面得到相同的哈希。哈希会写入数据库，算法不能随意改动
+pub fn canonical_hash(board: &Board, to_move: Player) -> u64 {
+    hash_cells(&canonical_form(board).1, to_move)
+}
+
+/// 把局面变换到规范方向的对称变换编号，与 `canonical_hash` 使用的方向一致
+pub fn canonical_symmetry(board: &Board) -> usize {
+    canonical_form(board).0
+}
+
+/// 对称变换的逆变换编号
+pub fn inverse_symmetry(symmetry: usize) -> usize {
+    // (1, 2) 不在任何对称轴上，只有逆变换能把它变回原处
+    (0..8)
+        .find(|&inverse| {
+            let (x, y) = OpeningState::transform(1, 2, symmetry);
+            OpeningState::transform(x, y, inverse) == (1, 2)
+        })
+        .unwrap_or(0)
+}
+
+fn hash_cells(cells: &[u8], to_move: Player) -> u64 {
+    // FNV-1a，保证不同版本的程序算出相同的值
+    let side = match to_move {
+        Player::Black => 1,
+        Player::White => 2,
+    };
+    cells
+        .iter()
+        .chain(std::iter::once(&side))
+        .fold(0xcbf29ce484222325u64, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// 主线上一个局面的索引项：规范哈希，以及换算到规范方向的下一手（之后没有着法时为空）
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedPosition {
+    pub hash: u64,
+    pub next: Option<Position>,
+}
+
+/// 重放着法，返回第 `first` 手及之后每个局面的索引项（下标 0 为第 `first` 手之后的局面）。
+/// 遇到不合法的着法时只返回此前的局面
+pub fn indexed_positions(setup: SetupPosition, rule_set: RuleSet, moves: &[Position], first: usize) -> Vec<IndexedPosition> {
+    let Ok(mut replay) = GameReplay::new(setup, rule_set) else {
+        return Vec::new();
+    };
+    let mut positions = Vec::new();
+    for ply in 0..=moves.len() {
+        let canonical = (ply >= first).then(|| canonical_form(&replay.board));
+        let to_move = replay.next_player();
+        let played = moves.get(ply).filter(|pos| replay.play(pos).is_ok());
+        if let Some((symmetry, cells)) = canonical {
+            positions.push(IndexedPosition {
+                hash: hash_cells(&cells, to_move),
+                next: played.map(|pos| transform_position(pos, symmetry)),
+            });
+        }
+        if played.is_none() {
+            break;
+        }
+    }
+    positions
+}
+
+/// 把 `from` 变为 `to` 的对称变换编号（同 `OpeningState::transform`），两局面不等价时返回 `None`
+pub fn find_symmetry(from: &Board, to: &Board) -> Option<usize> {
+    (0..8).find(|&symmetry| {
+        (0..BOARD_SIZE).all(|x| {
+            (0..BOARD_SIZE).all(|y| {
+                let (tx, ty) = OpeningState::transform(x, y, symmetry);
+                from.get(x, y).ok() == to.get(tx, ty).ok()
+            })
+        })
+    })
+}
+
+/// 对落点做对称变换
+pub fn transform_position(pos: &Position, symmetry: usize) -> Position {
+    let (x, y) = OpeningState::transform(pos.x, pos.y, symmetry);
+    Position { x, y }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(moves: &[(usize, usize)]) -> Vec<Position> {
+        moves.iter().map(|&(x, y)| Position { x, y }).collect()
+    }
+
+    #[test]
+    fn index_from_a_move_matches_the_full_replay() {
+        let moves = line(&[(7, 7), (7, 8), (8, 8), (6, 6), (9, 9), (8, 7)]);
+        let full = indexed_positions(SetupPosition::default(), RuleSet::Freestyle, &moves, 0);
+        assert_eq!(full.len(), moves.len() + 1);
+        assert_eq!(full.last().unwrap().next, None);
+        for first in 0..=moves.len() + 1 {
+            let from = indexed_positions(SetupPosition::default(), RuleSet::Freestyle, &moves, first);
+            assert_eq!(from, full[first.min(full.len())..]);
+        }
+    }
+
+    #[test]
+    fn rotated_games_index_the_same_next_move() {
+        let moves = line(&[(7, 7), (8, 9), (6, 8)]);
+        let full = indexed_positions(SetupPosition::default(), RuleSet::Freestyle, &moves, 0);
+        for symmetry in 0..8 {
+            let turned: Vec<Position> = moves.iter().map(|pos| transform_position(pos, symmetry)).collect();
+            let indexed = indexed_positions(SetupPosition::default(), RuleSet::Freestyle, &turned, 0);
+            let hashes = |positions: &[IndexedPosition]| positions.iter().map(|p| p.hash).collect::<Vec<_>>();
+            assert_eq!(hashes(&indexed), hashes(&full));
+            // 两子之后局面不对称，规范方向唯一
+            assert_eq!(indexed[2], full[2]);
+
+            // 规范方向的落点换算回对局自己的方向就是实际下的那一手
+            let mut board = Board::new();
+            board.set(turned[0].x, turned[0].y, Player::Black).unwrap();
+            board.set(turned[1].x, turned[1].y, Player::White).unwrap();
+            let inverse = inverse_symmetry(canonical_symmetry(&board));
+            assert_eq!(transform_position(indexed[2].next.as_ref().unwrap(), inverse), turned[2]);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+/// Glicko-2 内部刻度与显示刻度的换算系数
+const SCALE: f64 = 173.7178;
+/// 系统常数 τ，限制波动率的变化速度
+const TAU: f64 = 0.5;
+/// 求波动率时的收敛精度
+const EPSILON: f64 = 0.000001;
+
+pub const DEFAULT_RATING: f64 = 1500.0;
+pub const DEFAULT_DEVIATION: f64 = 350.0;
+pub const DEFAULT_VOLATILITY: f64 = 0.06;
+
+/// Glicko-2 等级分（每盘棋作为一个评分周期）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub rating: f64,
+    /// 评分偏差（RD），越小越可信
+    pub deviation: f64,
+    pub volatility: f64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            rating: DEFAULT_RATING,
+            deviation: DEFAULT_DEVIATION,
+            volatility: DEFAULT_VOLATILITY,
+        }
+    }
+}
+
+impl Rating {
+    /// 与 `opponent` 下完一盘后的新等级分，`score` 为 1.0 胜、0.5 和、0.0 负
+    pub fn update(&self, opponent: &Rating, score: f64) -> Rating {
+        self.update_period(&[(*opponent, score)])
+    }
+
+    /// 一个评分周期内下完 `games`（对手和得分）后的新等级分
+    fn update_period(&self, games: &[(Rating, f64)]) -> Rating {
+        let mu = (self.rating - DEFAULT_RATING) / SCALE;
+        let phi = self.deviation / SCALE;
+
+        let mut information = 0.0;
+        let mut improvement = 0.0;
+        for (opponent, score) in games {
+            let opponent_mu = (opponent.rating - DEFAULT_RATING) / SCALE;
+            let opponent_phi = opponent.deviation / SCALE;
+            let g = 1.0 / (1.0 + 3.0 * opponent_phi.powi(2) / std::f64::consts::PI.powi(2)).sqrt();
+            let expected = 1.0 / (1.0 + (-g * (mu - opponent_mu)).exp());
+            information += g.powi(2) * expected * (1.0 - expected);
+            improvement += g * (score - expected);
+        }
+        let variance = 1.0 / information;
+        let delta = variance * improvement;
+
+        let volatility = Self::new_volatility(phi, self.volatility, variance, delta);
+        let phi_star = (phi.powi(2) + volatility.powi(2)).sqrt();
+        let new_phi = 1.0 / (1.0 / phi_star.powi(2) + 1.0 / variance).sqrt();
+        let new_mu = mu + new_phi.powi(2) * improvement;
+
+        Rating {
+            rating: new_mu * SCALE + DEFAULT_RATING,
+            deviation: (new_phi * SCALE).min(DEFAULT_DEVIATION),
+            volatility,
+        }
+    }
+
+    /// 用 Illinois 算法求新的波动率
+    fn new_volatility(phi: f64, sigma: f64, variance: f64, delta: f64) -> f64 {
+        let a = sigma.powi(2).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta.powi(2) - phi.powi(2) - variance - ex)
+                / (2.0 * (phi.powi(2) + variance + ex).powi(2))
+                - (x - a) / TAU.powi(2)
+        };
+
+        let mut lower = a;
+        let mut upper = if delta.powi(2) > phi.powi(2) + variance {
+            (delta.powi(2) - phi.powi(2) - variance).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * TAU) < 0.0 {
+                k += 1.0;
+            }
+            a - k * TAU
+        };
+
+        let mut f_lower = f(lower);
+        let mut f_upper = f(upper);
+        while (upper - lower).abs() > EPSILON {
+            let c = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+            let f_c = f(c);
+            if f_c * f_upper <= 0.0 {
+                lower = upper;
+                f_lower = f_upper;
+            } else {
+                f_lower /= 2.0;
+            }
+            upper = c;
+            f_upper = f_c;
+        }
+
+        (lower / 2.0).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(rating: f64, deviation: f64) -> Rating {
+        Rating { rating, deviation, volatility: DEFAULT_VOLATILITY }
+    }
+
+    #[test]
+    fn matches_glickmans_example() {
+        // Glickman《Example of the Glicko-2 system》中的例子
+        let player = rating(1500.0, 200.0);
+        let updated = player.update_period(&[
+            (rating(1400.0, 30.0), 1.0),
+            (rating(1550.0, 100.0), 0.0),
+            (rating(1700.0, 300.0), 0.0),
+        ]);
+        assert!((updated.rating - 1464.06).abs() < 0.01, "{}", updated.rating);
+        assert!((updated.deviation - 151.52).abs() < 0.01, "{}", updated.deviation);
+        assert!((updated.volatility - 0.05999).abs() < 0.00001, "{}", updated.volatility);
+    }
+
+    #[test]
+    fn single_game_moves_ratings_symmetrically() {
+        let (black, white) = (Rating::default(), Rating::default());
+        let winner = black.update(&white, 1.0);
+        let loser = white.update(&black, 0.0);
+        assert!(winner.rating > DEFAULT_RATING);
+        assert!((winner.rating - DEFAULT_RATING + loser.rating - DEFAULT_RATING).abs() < 1e-9);
+        assert!(winner.deviation < DEFAULT_DEVIATION);
+        assert_eq!(black.update(&white, 0.5).rating, DEFAULT_RATING);
+    }
+}
@@ -0,0 +1,159 @@
+use super::board::Board;
+use super::rules::RulesValidator;
+use super::setup::SetupPosition;
+use super::tree::{GameTree, NodeId};
+use super::types::{CaptureCount, EndReason, GameStatus, Player, Position, RuleSet};
+
+/// 按规则重放一盘棋，用于导入校验和复盘
+#[derive(Clone)]
+pub struct GameReplay {
+    pub board: Board,
+    pub rule_set: RuleSet,
+    pub setup: SetupPosition,
+    pub game_status: GameStatus,
+    pub end_reason: Option<EndReason>,
+    pub winning_line: Option<Vec<Position>>,
+    pub captures: CaptureCount,
+    /// 每一步提走的棋子
+    pub capture_history: Vec<Vec<Position>>,
+    pub moves: Vec<Position>,
+}
+
+impl GameReplay {
+    pub fn new(setup: SetupPosition, rule_set: RuleSet) -> Result<Self, String> {
+        let mut board = Board::new();
+        setup.apply(&mut board)?;
+        Ok(GameReplay {
+            board,
+            rule_set,
+            setup,
+            game_status: GameStatus::InProgress,
+            end_reason: None,
+            winning_line: None,
+            captures: CaptureCount::default(),
+            capture_history: Vec::new(),
+            moves: Vec::new(),
+        })
+    }
+
+    /// 从初始局面重放全部落子，任何一步不合法都返回错误
+    pub fn replay(setup: SetupPosition, rule_set: RuleSet, moves: &[Position]) -> Result<Self, String> {
+        let mut replay = Self::new(setup, rule_set)?;
+        for (index, pos) in moves.iter().enumerate() {
+            replay
+                .play(pos)
+                .map_err(|e| format!("Move {} ({}): {}", index + 1, pos.to_notation(), e))?;
+        }
+        Ok(replay)
+    }
+
+    /// 下一手由哪一方下
+    pub fn next_player(&self) -> Player {
+        self.setup.player_for_move(self.rule_set, self.moves.len())
+    }
+
+    /// 落下一子并更新对局状态
+    pub fn play(&mut self, pos: &Position) -> Result<(), String> {
+        if !matches!(self.game_status, GameStatus::InProgress) {
+            return Err("Game is already over".to_string());
+        }
+
+        let player = self.next_player();
+        self.board.set(pos.x, pos.y, player)?;
+        let captured = if self.rule_set == RuleSet::Pente {
+            RulesValidator::apply_captures(&mut self.board, pos)
+        } else {
+            Vec::new()
+        };
+        *self.captures.get_mut(player) += (captured.len() / 2) as u32;
+        self.capture_history.push(captured);
+        self.moves.push(pos.clone());
+
+        let win_status = match player {
+            Player::Black => GameStatus::BlackWin,
+            Player::White => GameStatus::WhiteWin,
+        };
+        if let Some(line) = RulesValidator::check_win(&self.board, pos, self.rule_set) {
+            self.game_status = win_status;
+            self.end_reason = Some(if self.rule_set == RuleSet::Connect6 {
+                EndReason::SixInRow
+            } else {
+                EndReason::FiveInRow
+            });
+            self.winning_line = Some(line);
+        } else if self.rule_set == RuleSet::Renju
+            && player == Player::Black
+            && RulesValidator::check_forbidden(&self.board, pos).is_some()
+        {
+            self.game_status = GameStatus::WhiteWin;
+            self.end_reason = Some(EndReason::ForbiddenMove);
+        } else if RulesValidator::wins_by_captures(self.captures.get(player)) {
+            self.game_status = win_status;
+            self.end_reason = Some(EndReason::Captures);
+        } else if RulesValidator::is_draw(&self.board) {
+            self.game_status = GameStatus::Draw;
+            self.end_reason = Some(EndReason::BoardFull);
+        }
+
+        Ok(())
+    }
+}
+
+/// 复盘游标：沿一条着法线前后移动，不影响正在进行的对局
+#[derive(Debug, Clone)]
+pub struct ReplayCursor {
+    /// 从第一手到这条线的最后一手
+    line: Vec<NodeId>,
+    /// 已经下了的手数
+    index: usize,
+}
+
+impl ReplayCursor {
+    /// 从变化树的当前节点开始复盘，这条线沿主要后续一直延伸到底
+    pub fn new(tree: &GameTree) -> Self {
+        let mut line = tree.path_to(tree.current());
+        let index = line.len();
+        while let Some(&next) = tree.children(line.last().copied()).first() {
+            line.push(next);
+        }
+        ReplayCursor { line, index }
+    }
+
+    /// 已经下了的手数
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// 整条线的手数
+    pub fn len(&self) -> usize {
+        self.line.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.line.is_empty()
+    }
+
+    /// 当前局面对应的节点，`None` 为初始局面
+    pub fn node(&self) -> Option<NodeId> {
+        self.index.checked_sub(1).map(|i| self.line[i])
+    }
+
+    pub fn step_forward(&mut self) -> Result<(), String> {
+        self.goto(self.index + 1)
+            .map_err(|_| "Already at the last move".to_string())
+    }
+
+    pub fn step_back(&mut self) -> Result<(), String> {
+        let index = self.index.checked_sub(1).ok_or("Already at the start")?;
+        self.goto(index)
+    }
+
+    /// 跳到第 `move_number` 手之后（0 为初始局面）
+    pub fn goto(&mut self, move_number: usize) -> Result<(), String> {
+        if move_number > self.line.len() {
+            return Err(format!("Move {} is past the end of the game ({} moves)", move_number, self.line.len()));
+        }
+        self.index = move_number;
+        Ok(())
+    }
+}
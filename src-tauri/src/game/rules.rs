@@ -1,6 +1,17 @@
-use super::types::{Cell, Position};
+use serde::{Deserialize, Serialize};
+
+use super::types::{Cell, Player, Position, RuleSet};
 use super::board::Board;
 
+/// 连珠规则下黑棋的禁手
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Forbidden {
+    Overline,     // 长连
+    DoubleFour,   // 四四
+    DoubleThree,  // 三三
+}
+
 pub struct RulesValidator;
 
 impl RulesValidator {
@@ -79,4 +90,254 @@ impl RulesValidator {
     pub fn is_draw(board: &Board) -> bool {
         board.is_full()
     }
-}
\ No newline at end of file
+
+    /// 按规则检查胜利：连珠规则下黑棋必须正好五子
+    pub fn check_win(
+        board: &Board,
+        last_pos: &Position,
+        rule_set: RuleSet,
+    ) -> Option<Vec<Position>> {
+        if rule_set == RuleSet::Renju && board.get(last_pos.x, last_pos.y).ok()? == Cell::Black {
+            return Self::check_exact_five(board, last_pos);
+        }
+        if rule_set == RuleSet::Connect6 {
+            return Self::check_line_of(board, last_pos, 6);
+        }
+        Self::check_five_in_row(board, last_pos)
+    }
+
+    /// 检查不少于 `length` 子的连线
+    pub fn check_line_of(board: &Board, last_pos: &Position, length: usize) -> Option<Vec<Position>> {
+        let player_cell = board.get(last_pos.x, last_pos.y).ok()?;
+        if player_cell == Cell::Empty {
+            return None;
+        }
+
+        DIRECTIONS
+            .iter()
+            .map(|&(dx, dy)| Self::count_direction(board, last_pos, dx, dy, player_cell))
+            .find(|line| line.len() >= length)
+    }
+
+    /// 检查正好五子连珠（不含长连）
+    pub fn check_exact_five(board: &Board, last_pos: &Position) -> Option<Vec<Position>> {
+        let player_cell = board.get(last_pos.x, last_pos.y).ok()?;
+        if player_cell == Cell::Empty {
+            return None;
+        }
+
+        DIRECTIONS
+            .iter()
+            .map(|&(dx, dy)| Self::count_direction(board, last_pos, dx, dy, player_cell))
+            .find(|line| line.len() == 5)
+    }
+
+    /// 检查黑棋在 `pos` 的落子是否为禁手（棋子已落在棋盘上）
+    ///
+    /// 成五优先于禁手。活三的判断不递归检查成四点本身是否为禁手。
+    pub fn check_forbidden(board: &Board, pos: &Position) -> Option<Forbidden> {
+        if board.get(pos.x, pos.y).ok()? != Cell::Black {
+            return None;
+        }
+        if Self::check_exact_five(board, pos).is_some() {
+            return None;
+        }
+
+        let mut fours = 0;
+        let mut open_threes = 0;
+        for (dx, dy) in DIRECTIONS {
+            if Self::count_direction(board, pos, dx, dy, Cell::Black).len() > 5 {
+                return Some(Forbidden::Overline);
+            }
+            fours += Self::count_fours(board, pos, dx, dy);
+            if Self::is_open_three(board, pos, dx, dy) {
+                open_threes += 1;
+            }
+        }
+
+        if fours >= 2 {
+            Some(Forbidden::DoubleFour)
+        } else if open_threes >= 2 {
+            Some(Forbidden::DoubleThree)
+        } else {
+            None
+        }
+    }
+
+    /// 某方向上再下一子即可正好成五的位置（以相对 `pos` 的偏移表示）
+    fn five_points(board: &Board, pos: &Position, dx: isize, dy: isize) -> Vec<isize> {
+        (-4..=4)
+            .filter(|&k| k != 0)
+            .filter(|&k| {
+                let Some(target) = Self::offset(pos, dx * k, dy * k) else {
+                    return false;
+                };
+                let mut probe = board.clone();
+                probe.set(target.x, target.y, Player::Black).is_ok()
+                    && Self::count_direction(&probe, pos, dx, dy, Cell::Black).len() == 5
+            })
+            .collect()
+    }
+
+    /// 某方向上四的数量（活四算一个，同一条线上的两个冲四算两个）
+    fn count_fours(board: &Board, pos: &Position, dx: isize, dy: isize) -> usize {
+        let points = Self::five_points(board, pos, dx, dy);
+        if points.len() == 2 && points[1] - points[0] == 5 {
+            1
+        } else {
+            points.len().min(2)
+        }
+    }
+
+    /// 某方向上是否为活三：再下一子能形成活四
+    fn is_open_three(board: &Board, pos: &Position, dx: isize, dy: isize) -> bool {
+        // 已经是四的方向不算三
+        if !Self::five_points(board, pos, dx, dy).is_empty() {
+            return false;
+        }
+
+        (-4..=4).filter(|&k| k != 0).any(|k| {
+            let Some(target) = Self::offset(pos, dx * k, dy * k) else {
+                return false;
+            };
+            let mut probe = board.clone();
+            if probe.set(target.x, target.y, Player::Black).is_err() {
+                return false;
+            }
+            let points = Self::five_points(&probe, pos, dx, dy);
+            points.len() == 2 && points[1] - points[0] == 5
+        })
+    }
+
+    /// 在 `pos` 落子后可以提走的对方棋子（夹住正好两子）
+    pub fn find_captures(board: &Board, pos: &Position) -> Vec<Position> {
+        let own = match board.get(pos.x, pos.y) {
+            Ok(Cell::Empty) | Err(_) => return Vec::new(),
+            Ok(cell) => cell,
+        };
+
+        let mut captured = Vec::new();
+        for (dx, dy) in DIRECTIONS {
+            for sign in [1, -1] {
+                let cells: Option<Vec<Position>> = (1..=3)
+                    .map(|k| Self::offset(pos, dx * sign * k, dy * sign * k))
+                    .collect();
+                let Some(cells) = cells else {
+                    continue;
+                };
+                let cell_at = |p: &Position| board.get(p.x, p.y).unwrap_or(Cell::Empty);
+                let (first, second) = (cell_at(&cells[0]), cell_at(&cells[1]));
+                if first != own && first != Cell::Empty && second == first && cell_at(&cells[2]) == own {
+                    captured.push(cells[0].clone());
+                    captured.push(cells[1].clone());
+                }
+            }
+        }
+        captured
+    }
+
+    /// 执行提子，返回被提走的棋子
+    pub fn apply_captures(board: &mut Board, pos: &Position) -> Vec<Position> {
+        let captured = Self::find_captures(board, pos);
+        for stone in &captured {
+            let _ = board.remove(stone.x, stone.y);
+        }
+        captured
+    }
+
+    /// 提子数是否达到获胜条件
+    pub fn wins_by_captures(pairs: u32) -> bool {
+        pairs >= CAPTURES_TO_WIN
+    }
+
+    fn offset(pos: &Position, dx: isize, dy: isize) -> Option<Position> {
+        let x = pos.x as isize + dx;
+        let y = pos.y as isize + dy;
+        if (0..15).contains(&x) && (0..15).contains(&y) {
+            Some(Position { x: x as usize, y: y as usize })
+        } else {
+            None
+        }
+    }
+}
+
+/// 提子规则下获胜所需的提子对数
+pub const CAPTURES_TO_WIN: u32 = 5;
+
+/// 四个方向：横、竖、斜右下、斜左下
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 摆好黑子后在 `(x, y)` 落下黑棋，返回落子点的禁手
+    fn forbidden_after(stones: &[(usize, usize)], x: usize, y: usize) -> Option<Forbidden> {
+        let mut board = Board::new();
+        for &(sx, sy) in stones {
+            board.set(sx, sy, Player::Black).unwrap();
+        }
+        board.set(x, y, Player::Black).unwrap();
+        RulesValidator::check_forbidden(&board, &Position { x, y })
+    }
+
+    #[test]
+    fn double_three_is_forbidden() {
+        let stones = [(7, 5), (7, 6), (5, 7), (6, 7)];
+        assert_eq!(forbidden_after(&stones, 7, 7), Some(Forbidden::DoubleThree));
+        // 只有一个活三时不是禁手
+        assert_eq!(forbidden_after(&stones[..3], 7, 7), None);
+    }
+
+    #[test]
+    fn double_four_across_two_lines_is_forbidden() {
+        let stones = [(7, 4), (7, 5), (7, 6), (4, 7), (5, 7), (6, 7)];
+        assert_eq!(forbidden_after(&stones, 7, 7), Some(Forbidden::DoubleFour));
+    }
+
+    #[test]
+    fn double_four_on_one_line_is_forbidden() {
+        // B.BBB.B：两端各有一个成五点
+        let stones = [(7, 3), (7, 5), (7, 7), (7, 9)];
+        assert_eq!(forbidden_after(&stones, 7, 6), Some(Forbidden::DoubleFour));
+    }
+
+    #[test]
+    fn overline_is_forbidden_and_does_not_win_for_black() {
+        let stones = [(7, 2), (7, 3), (7, 4), (7, 5), (7, 7)];
+        assert_eq!(forbidden_after(&stones, 7, 6), Some(Forbidden::Overline));
+
+        let mut board = Board::new();
+        for &(x, y) in stones.iter().chain([(7, 6)].iter()) {
+            board.set(x, y, Player::Black).unwrap();
+        }
+        let pos = Position { x: 7, y: 6 };
+        assert!(RulesValidator::check_win(&board, &pos, RuleSet::Renju).is_none());
+        assert!(RulesValidator::check_win(&board, &pos, RuleSet::Freestyle).is_some());
+    }
+
+    #[test]
+    fn five_beats_forbidden() {
+        // 成五的同时形成另一个四
+        let stones = [(7, 3), (7, 4), (7, 5), (7, 6), (4, 7), (5, 7), (6, 7)];
+        assert_eq!(forbidden_after(&stones, 7, 7), None);
+    }
+
+    #[test]
+    fn three_blocked_by_the_edge_is_not_open() {
+        // 靠边的三无法形成活四，不算活三
+        let stones = [(7, 0), (7, 1), (5, 2), (6, 2)];
+        assert_eq!(forbidden_after(&stones, 7, 2), None);
+        // 同样的棋形离开一路就是三三
+        let stones = [(7, 1), (7, 2), (5, 3), (6, 3)];
+        assert_eq!(forbidden_after(&stones, 7, 3), Some(Forbidden::DoubleThree));
+    }
+
+    #[test]
+    fn white_has_no_forbidden_moves() {
+        let mut board = Board::new();
+        for (x, y) in [(7, 5), (7, 6), (5, 7), (6, 7), (7, 7)] {
+            board.set(x, y, Player::White).unwrap();
+        }
+        assert_eq!(RulesValidator::check_forbidden(&board, &Position { x: 7, y: 7 }), None);
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+use super::board::Board;
+use super::types::{Cell, Player, Position, RuleSet};
+
+/// 让子的摆放顺序：天元、四个星位、四条边的中点
+const HANDICAP_POINTS: [(usize, usize); 9] = [
+    (7, 7),
+    (3, 3),
+    (11, 11),
+    (3, 11),
+    (11, 3),
+    (7, 3),
+    (7, 11),
+    (3, 7),
+    (11, 7),
+];
+
+/// 初始局面中的一枚棋子
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupStone {
+    pub position: Position,
+    pub player: Player,
+}
+
+/// 对局的初始局面（让子或自定义摆子），与落子记录分开保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupPosition {
+    pub stones: Vec<SetupStone>,
+    /// 摆好后先走的一方
+    pub to_move: Player,
+}
+
+impl SetupPosition {
+    /// 空棋盘，黑棋先走
+    pub fn empty() -> Self {
+        SetupPosition {
+            stones: Vec::new(),
+            to_move: Player::Black,
+        }
+    }
+
+    /// 为 `player` 一方摆放 `count` 枚让子，之后由对方先走
+    pub fn handicap(player: Player, count: usize) -> Result<Self, String> {
+        if count == 0 || count > HANDICAP_POINTS.len() {
+            return Err(format!("Handicap must be between 1 and {} stones", HANDICAP_POINTS.len()));
+        }
+
+        let stones = HANDICAP_POINTS
+            .iter()
+            .take(count)
+            .map(|&(x, y)| SetupStone {
+                position: Position { x, y },
+                player,
+            })
+            .collect();
+        Ok(SetupPosition {
+            stones,
+            to_move: player.opponent(),
+        })
+    }
+
+    /// 从棋盘当前的棋子生成初始局面
+    pub fn from_board(board: &Board, to_move: Player) -> Self {
+        let mut stones = Vec::new();
+        for x in 0..15 {
+            for y in 0..15 {
+                let player = match board.get(x, y) {
+                    Ok(Cell::Black) => Player::Black,
+                    Ok(Cell::White) => Player::White,
+                    _ => continue,
+                };
+                stones.push(SetupStone {
+                    position: Position { x, y },
+                    player,
+                });
+            }
+        }
+        SetupPosition { stones, to_move }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stones.is_empty()
+    }
+
+    /// 把初始局面摆到（已清空的）棋盘上
+    pub fn apply(&self, board: &mut Board) -> Result<(), String> {
+        for stone in &self.stones {
+            board.set(stone.position.x, stone.position.y, stone.player)?;
+        }
+        Ok(())
+    }
+
+    /// 初始局面之后第 `index` 手棋（从 0 开始）由哪一方下
+    pub fn player_for_move(&self, rule_set: RuleSet, index: usize) -> Player {
+        let player = rule_set.player_for_move(index);
+        match self.to_move {
+            Player::Black => player,
+            Player::White => player.opponent(),
+        }
+    }
+}
+
+impl Default for SetupPosition {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+
+use super::annotation::MoveNotes;
+use super::types::Position;
+
+/// 棋谱树中节点的编号
+pub type NodeId = usize;
+
+/// 棋谱树中的一手棋
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveNode {
+    pub id: NodeId,
+    pub parent: Option<NodeId>,
+    pub position: Position,
+    /// 后续着法，第一个为主线
+    pub children: Vec<NodeId>,
+    /// 评注和棋盘标记
+    #[serde(default)]
+    pub notes: MoveNotes,
+}
+
+/// 棋谱树状态（返回给前端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameTreeState {
+    pub nodes: Vec<MoveNode>,
+    /// 第一手的各个选择，第一个为主线
+    pub roots: Vec<NodeId>,
+    pub main_line: Vec<NodeId>,
+    /// 当前所在节点，`None` 为初始局面
+    pub current: Option<NodeId>,
+}
+
+/// 变化树：每手棋记录父节点，悔棋后改下的棋作为变化保留
+#[derive(Debug, Clone, Default)]
+pub struct GameTree {
+    /// 删除的节点留空，保证编号不变
+    nodes: Vec<Option<MoveNode>>,
+    roots: Vec<NodeId>,
+    current: Option<NodeId>,
+}
+
+impl GameTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 由单线棋谱构建
+    pub fn from_line(moves: &[Position]) -> Self {
+        let mut tree = Self::new();
+        for pos in moves {
+            tree.play(pos.clone());
+        }
+        tree
+    }
+
+    pub fn current(&self) -> Option<NodeId> {
+        self.current
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&MoveNode> {
+        self.nodes.get(id).and_then(Option::as_ref)
+    }
+
+    /// 节点的评注和标记
+    pub fn notes_mut(&mut self, id: NodeId) -> Result<&mut MoveNotes, String> {
+        self.nodes
+            .get_mut(id)
+            .and_then(Option::as_mut)
+            .map(|node| &mut node.notes)
+            .ok_or_else(|| "Move not found".to_string())
+    }
+
+    /// `parent` 之后的各个选择，`None` 为第一手
+    pub fn children(&self, parent: Option<NodeId>) -> &[NodeId] {
+        match parent {
+            Some(id) => self.node(id).map(|node| node.children.as_slice()).unwrap_or(&[]),
+            None => &self.roots,
+        }
+    }
+
+    fn children_mut(&mut self, parent: Option<NodeId>) -> Option<&mut Vec<NodeId>> {
+        match parent {
+            Some(id) => self.nodes.get_mut(id)?.as_mut().map(|node| &mut node.children),
+            None => Some(&mut self.roots),
+        }
+    }
+
+    /// 在 `parent` 之后添加一手（排在已有选择之后），返回新节点
+    pub fn add_child(&mut self, parent: Option<NodeId>, position: Position) -> Result<NodeId, String> {
+        let id = self.nodes.len();
+        self.children_mut(parent).ok_or("Move not found")?.push(id);
+        self.nodes.push(Some(MoveNode {
+            id,
+            parent,
+            position,
+            children: Vec::new(),
+            notes: MoveNotes::default(),
+        }));
+        Ok(id)
+    }
+
+    /// 在当前节点落子：已有相同着法时沿用该节点，否则新建节点排在已有选择之后
+    pub fn play(&mut self, position: Position) -> NodeId {
+        let existing = self
+            .children(self.current)
+            .iter()
+            .copied()
+            .find(|&id| {
+                self.node(id)
+                    .is_some_and(|node| node.position.x == position.x && node.position.y == position.y)
+            });
+        if let Some(id) = existing {
+            self.current = Some(id);
+            return id;
+        }
+
+        let id = self.nodes.len();
+        let parent = self.current;
+        if let Some(children) = self.children_mut(parent) {
+            children.push(id);
+        }
+        self.nodes.push(Some(MoveNode {
+            id,
+            parent,
+            position,
+            children: Vec::new(),
+            notes: MoveNotes::default(),
+        }));
+        self.current = Some(id);
+        id
+    }
+
+    /// 后退一手，返回是否移动
+    pub fn back(&mut self) -> bool {
+        match self.current.and_then(|id| self.node(id)) {
+            Some(node) => {
+                self.current = node.parent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 沿当前节点的主要后续前进一手
+    pub fn forward(&mut self) -> Option<NodeId> {
+        let next = self.children(self.current).first().copied()?;
+        self.current = Some(next);
+        Some(next)
+    }
+
+    /// 跳到指定节点，`None` 为初始局面
+    pub fn jump(&mut self, target: Option<NodeId>) -> Result<(), String> {
+        if let Some(id) = target {
+            self.node(id).ok_or("Move not found")?;
+        }
+        self.current = target;
+        Ok(())
+    }
+
+    /// 从初始局面到 `target` 的节点
+    pub fn path_to(&self, target: Option<NodeId>) -> Vec<NodeId> {
+        let mut path = Vec::new();
+        let mut next = target.and_then(|id| self.node(id));
+        while let Some(node) = next {
+            path.push(node.id);
+            next = node.parent.and_then(|id| self.node(id));
+        }
+        path.reverse();
+        path
+    }
+
+    /// 从初始局面到 `target` 的着法
+    pub fn line_to(&self, target: Option<NodeId>) -> Vec<Position> {
+        self.path_to(target)
+            .into_iter()
+            .filter_map(|id| self.node(id).map(|node| node.position.clone()))
+            .collect()
+    }
+
+    /// 到当前节点为止的着法
+    pub fn current_line(&self) -> Vec<Position> {
+        self.line_to(self.current)
+    }
+
+    /// 主线上的节点
+    pub fn main_line_nodes(&self) -> Vec<NodeId> {
+        let mut line = Vec::new();
+        let mut next = self.roots.first().copied();
+        while let Some(id) = next {
+            line.push(id);
+            next = self.node(id).and_then(|node| node.children.first().copied());
+        }
+        line
+    }
+
+    /// 主线上的着法
+    pub fn main_line(&self) -> Vec<Position> {
+        self.main_line_nodes()
+            .into_iter()
+            .filter_map(|id| self.node(id).map(|node| node.position.clone()))
+            .collect()
+    }
+
+    /// 节点是否在主线上（每一步都是父节点的第一个选择）
+    pub fn is_main_line(&self, id: NodeId) -> bool {
+        let mut next = self.node(id);
+        while let Some(node) = next {
+            if self.children(node.parent).first() != Some(&node.id) {
+                return false;
+            }
+            next = node.parent.and_then(|parent| self.node(parent));
+        }
+        true
+    }
+
+    /// 把节点所在的变化提升为主线
+    pub fn promote(&mut self, id: NodeId) -> Result<(), String> {
+        self.node(id).ok_or("Move not found")?;
+        for node_id in self.path_to(Some(id)) {
+            let parent = self.node(node_id).ok_or("Move not found")?.parent;
+            let children = self.children_mut(parent).ok_or("Move not found")?;
+            if let Some(index) = children.iter().position(|&child| child == node_id) {
+                let child = children.remove(index);
+                children.insert(0, child);
+            }
+        }
+        Ok(())
+    }
+
+    /// 删除节点及其全部后续；当前节点被删除时回到父节点
+    pub fn delete(&mut self, id: NodeId) -> Result<(), String> {
+        let parent = self.node(id).ok_or("Move not found")?.parent;
+        if self.path_to(self.current).contains(&id) {
+            self.current = parent;
+        }
+        if let Some(children) = self.children_mut(parent) {
+            children.retain(|&child| child != id);
+        }
+
+        let mut pending = vec![id];
+        while let Some(node_id) = pending.pop() {
+            if let Some(node) = self.nodes.get_mut(node_id).and_then(Option::take) {
+                pending.extend(node.children);
+            }
+        }
+        Ok(())
+    }
+
+    /// 按先序遍历的全部节点（父节点在前，主线在变化之前）
+    pub fn preorder(&self) -> Vec<&MoveNode> {
+        let mut order = Vec::new();
+        let mut pending: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = pending.pop() {
+            if let Some(node) = self.node(id) {
+                order.push(node);
+                pending.extend(node.children.iter().rev());
+            }
+        }
+        order
+    }
+
+    /// 没有后续着法的节点
+    pub fn leaves(&self) -> Vec<NodeId> {
+        self.preorder()
+            .into_iter()
+            .filter(|node| node.children.is_empty())
+            .map(|node| node.id)
+            .collect()
+    }
+
+    pub fn state(&self) -> GameTreeState {
+        GameTreeState {
+            nodes: self.preorder().into_iter().cloned().collect(),
+            roots: self.roots.clone(),
+            main_line: self.main_line_nodes(),
+            current: self.current,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: usize, y: usize) -> Position {
+        Position { x, y }
+    }
+
+    /// 主线 0-1-2，在第一手之后有变化 3-4
+    fn tree_with_variation() -> GameTree {
+        let mut tree = GameTree::from_line(&[pos(7, 7), pos(7, 8), pos(8, 8)]);
+        tree.jump(Some(0)).unwrap();
+        tree.play(pos(6, 6));
+        tree.play(pos(5, 5));
+        tree
+    }
+
+    #[test]
+    fn play_appends_new_moves_as_variations() {
+        let mut tree = tree_with_variation();
+        assert_eq!(tree.main_line_nodes(), vec![0, 1, 2]);
+        assert_eq!(tree.children(Some(0)), &[1, 3]);
+        assert_eq!(tree.current(), Some(4));
+        assert!(!tree.is_main_line(4));
+
+        // 已有的着法沿用原节点
+        tree.jump(None).unwrap();
+        assert_eq!(tree.play(pos(7, 7)), 0);
+        assert_eq!(tree.play(pos(6, 6)), 3);
+        assert_eq!(tree.children(None), &[0]);
+
+        // 主线末端之后落子延续主线
+        tree.jump(Some(2)).unwrap();
+        let id = tree.play(pos(9, 9));
+        assert_eq!(tree.main_line_nodes(), vec![0, 1, 2, id]);
+    }
+
+    #[test]
+    fn promote_makes_the_variation_the_main_line() {
+        let mut tree = tree_with_variation();
+        tree.promote(4).unwrap();
+        assert_eq!(tree.main_line(), vec![pos(7, 7), pos(6, 6), pos(5, 5)]);
+        assert_eq!(tree.children(Some(0)), &[3, 1]);
+        assert!(tree.is_main_line(4));
+        assert!(!tree.is_main_line(2));
+        assert!(tree.promote(99).is_err());
+    }
+
+    #[test]
+    fn delete_removes_the_subtree() {
+        let mut tree = tree_with_variation();
+        tree.delete(3).unwrap();
+        assert_eq!(tree.current(), Some(0));
+        assert!(tree.node(3).is_none());
+        assert!(tree.node(4).is_none());
+        assert_eq!(tree.children(Some(0)), &[1]);
+        assert_eq!(tree.leaves(), vec![2]);
+
+        // 删除主线上的节点后，下一个选择成为主线
+        let mut tree = tree_with_variation();
+        tree.delete(1).unwrap();
+        assert_eq!(tree.current(), Some(4));
+        assert_eq!(tree.main_line_nodes(), vec![0, 3, 4]);
+        assert!(tree.delete(1).is_err());
+    }
+
+    #[test]
+    fn main_line_follows_first_children() {
+        let tree = tree_with_variation();
+        assert_eq!(tree.main_line(), vec![pos(7, 7), pos(7, 8), pos(8, 8)]);
+        assert_eq!(tree.line_to(Some(4)), vec![pos(7, 7), pos(6, 6), pos(5, 5)]);
+        assert_eq!(tree.path_to(Some(4)), vec![0, 3, 4]);
+        assert_eq!(
+            tree.preorder().iter().map(|node| node.id).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4]
+        );
+    }
+}
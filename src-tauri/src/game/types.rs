@@ -14,6 +14,22 @@ impl Player {
             Player::White => Player::Black,
         }
     }
+
+    /// 数据库和导出文件中使用的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Player::Black => "black",
+            Player::White => "white",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Player> {
+        match s {
+            "black" => Some(Player::Black),
+            "white" => Some(Player::White),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -23,7 +39,7 @@ pub enum Cell {
     White,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
@@ -35,6 +51,8 @@ pub struct Move {
     pub player: Player,
 }
 
+/// 对局状态。超时判负不单独设状态：记为对方胜（BlackWin / WhiteWin），
+/// 结束原因为 `EndReason::Timeout`，认输、禁手等其他判负方式同理
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum GameStatus {
@@ -46,12 +64,165 @@ pub enum GameStatus {
     Draw,
 }
 
+/// 对局结束原因（与 GameStatus 中的胜负一起使用）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndReason {
+    FiveInRow,      // 连五
+    BoardFull,      // 棋盘下满
+    Timeout,        // 超时判负
+    Resignation,    // 认输
+    Agreement,      // 双方同意和棋
+    ForbiddenMove,  // 黑棋禁手判负
+    SixInRow,       // 六子棋连六
+    Captures,       // 提子数达到五对
+}
+
+impl EndReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndReason::FiveInRow => "five_in_row",
+            EndReason::BoardFull => "board_full",
+            EndReason::Timeout => "timeout",
+            EndReason::Resignation => "resignation",
+            EndReason::Agreement => "agreement",
+            EndReason::ForbiddenMove => "forbidden_move",
+            EndReason::SixInRow => "six_in_row",
+            EndReason::Captures => "captures",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<EndReason> {
+        match s {
+            "five_in_row" => Some(EndReason::FiveInRow),
+            "board_full" => Some(EndReason::BoardFull),
+            "timeout" => Some(EndReason::Timeout),
+            "resignation" => Some(EndReason::Resignation),
+            "agreement" => Some(EndReason::Agreement),
+            "forbidden_move" => Some(EndReason::ForbiddenMove),
+            "six_in_row" => Some(EndReason::SixInRow),
+            "captures" => Some(EndReason::Captures),
+            _ => None,
+        }
+    }
+}
+
+/// 对局结果：胜负与结束原因
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameOutcome {
+    pub game_status: GameStatus,
+    pub winner: Option<Player>,
+    pub end_reason: Option<EndReason>,
+}
+
+impl GameOutcome {
+    pub fn new(game_status: GameStatus, end_reason: Option<EndReason>) -> Self {
+        let winner = match game_status {
+            GameStatus::BlackWin => Some(Player::Black),
+            GameStatus::WhiteWin => Some(Player::White),
+            _ => None,
+        };
+        GameOutcome { game_status, winner, end_reason }
+    }
+}
+
+/// 规则
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleSet {
+    Freestyle,  // 自由五子棋（无禁手）
+    Renju,      // 连珠（黑棋有禁手）
+    Connect6,   // 六子棋（黑先一子，之后每方每回合两子）
+    Pente,      // 提子五子棋（夹住对方两子即提走，提五对或连五获胜）
+}
+
+impl RuleSet {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleSet::Freestyle => "freestyle",
+            RuleSet::Renju => "renju",
+            RuleSet::Connect6 => "connect6",
+            RuleSet::Pente => "pente",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<RuleSet> {
+        match s {
+            "freestyle" => Some(RuleSet::Freestyle),
+            "renju" => Some(RuleSet::Renju),
+            "connect6" => Some(RuleSet::Connect6),
+            "pente" => Some(RuleSet::Pente),
+            _ => None,
+        }
+    }
+
+    /// 获胜所需的连子数
+    pub fn win_length(&self) -> usize {
+        match self {
+            RuleSet::Connect6 => 6,
+            _ => 5,
+        }
+    }
+
+    /// 第 `index` 手棋（从 0 开始）所属的回合（从 0 开始）
+    pub fn turn_of_move(&self, index: usize) -> usize {
+        match self {
+            // 第一回合黑棋只下一子，之后每回合两子
+            RuleSet::Connect6 => index.div_ceil(2),
+            _ => index,
+        }
+    }
+
+    /// 第 `index` 手棋（从 0 开始）由哪一方下
+    pub fn player_for_move(&self, index: usize) -> Player {
+        if self.turn_of_move(index).is_multiple_of(2) {
+            Player::Black
+        } else {
+            Player::White
+        }
+    }
+
+    /// 已下 `moves` 手后，当前回合还需要下几子
+    pub fn stones_left_in_turn(&self, moves: usize) -> usize {
+        match self {
+            RuleSet::Connect6 if moves > 0 && moves % 2 == 1 => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// 提子规则下双方已提走的对数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaptureCount {
+    pub black: u32,
+    pub white: u32,
+}
+
+impl CaptureCount {
+    pub fn get(&self, player: Player) -> u32 {
+        match player {
+            Player::Black => self.black,
+            Player::White => self.white,
+        }
+    }
+
+    pub fn get_mut(&mut self, player: Player) -> &mut u32 {
+        match player {
+            Player::Black => &mut self.black,
+            Player::White => &mut self.white,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoveResult {
     pub success: bool,
     pub game_status: GameStatus,
     pub winning_line: Option<Vec<Position>>,
     pub next_player: Player,
+    pub end_reason: Option<EndReason>,
+    /// 本步提走的棋子
+    pub captured: Vec<Position>,
 }
 
 /// 游戏模式
@@ -3,23 +3,65 @@ pub mod commands;
 pub mod game;
 pub mod storage;
 
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::path::PathBuf;
-pub use game::{Board, Player, GameStatus, Position, Cell, GameMode};
-pub use ai::{AIEngine, Difficulty, PatternEvaluator, MinimaxSolver, Pattern};
-pub use storage::{Database, SavedGame, SavedMove};
+use tauri::Manager;
+pub use game::{Board, Player, GameStatus, Position, Cell, GameMode, OpeningRule, OpeningState, PositionEditor};
+pub use game::{CaptureCount, EndReason, GameClock, GameOutcome, GameTree, NodeId, Rating, ReplayCursor, RuleSet, SetupPosition, TimeControl};
+pub use ai::{AIEngine, Difficulty, PatternEvaluator, MinimaxSolver, Pattern, OpeningAdvisor};
+pub use storage::{AppSettings, Database, PlayerStats, SavedAnnotation, SavedClock, SavedGame, SavedMarkup, SavedMove, SavedNode, SavedOffer, SavedOpening, SavedPlayer, SavedRatingChange, SavedSetupStone};
 
 // GameState for managing game state across Tauri commands
 pub struct GameState {
     pub board: Mutex<Board>,
     pub current_player: Mutex<Player>,
     pub game_status: Mutex<GameStatus>,
+    pub end_reason: Mutex<Option<EndReason>>,
+    pub rule_set: Mutex<RuleSet>,
+    // 等待对方回应的和棋提议（提议方）
+    pub draw_offer: Mutex<Option<Player>>,
+    // 从初始局面到变化树当前节点的着法
     pub move_history: Mutex<Vec<Position>>,
+    // 变化树：悔棋后改下的棋作为变化保留
+    pub move_tree: Mutex<GameTree>,
+    // 复盘游标（复盘时不改变对局本身）
+    pub replay: Mutex<Option<ReplayCursor>>,
+    // 当前对局已保存的记录（再次保存时原地更新），以及树节点对应的落子记录
+    pub saved_game_id: Mutex<Option<i64>>,
+    pub saved_moves: Mutex<HashMap<NodeId, SavedNode>>,
+    // 自动保存的记录（与手动保存分开）
+    pub autosave_id: Mutex<Option<i64>>,
+    pub autosave_moves: Mutex<HashMap<NodeId, SavedNode>>,
+    // 最近一次自动保存或计分失败的原因，由前端取走后清空
+    pub autosave_error: Mutex<Option<String>>,
+    // 启动时发现的未结束的自动保存
+    pub resumable_game: Mutex<Option<i64>>,
+    // 提子规则：双方提子数，以及每一步提走的棋子（用于悔棋）
+    pub captures: Mutex<CaptureCount>,
+    pub capture_history: Mutex<Vec<Vec<Position>>>,
+    // 初始局面（让子或自定义摆子），不计入 move_history
+    pub setup: Mutex<SetupPosition>,
+    // 摆棋编辑器
+    pub editor: Mutex<PositionEditor>,
 
     // 新增字段
     pub game_mode: Mutex<GameMode>,
     pub ai_difficulty: Mutex<Difficulty>,
     pub ai_engine: Mutex<Option<AIEngine>>,
+    pub ai_player: Mutex<Player>,
+    // 执黑/执白的玩家档案（PvE 模式下 AI 一方保存时使用 AI 档案）
+    pub black_player_id: Mutex<Option<i64>>,
+    pub white_player_id: Mutex<Option<i64>>,
+    // 当前对局是否计入等级分，以及已计分的记录（避免重复计分）
+    pub rated: Mutex<bool>,
+    pub rated_game_id: Mutex<Option<i64>>,
+
+    // 开局规则状态
+    pub opening: Mutex<OpeningState>,
+
+    // 棋钟
+    pub clock: Mutex<GameClock>,
 
     // 新增：数据库连接
     pub database: Arc<Mutex<Database>>,
@@ -31,10 +73,32 @@ impl GameState {
             board: Mutex::new(Board::new()),
             current_player: Mutex::new(Player::Black),
             game_status: Mutex::new(GameStatus::InProgress),
+            end_reason: Mutex::new(None),
+            rule_set: Mutex::new(RuleSet::Freestyle),
+            draw_offer: Mutex::new(None),
             move_history: Mutex::new(Vec::new()),
+            move_tree: Mutex::new(GameTree::new()),
+            replay: Mutex::new(None),
+            saved_game_id: Mutex::new(None),
+            saved_moves: Mutex::new(HashMap::new()),
+            autosave_id: Mutex::new(None),
+            autosave_moves: Mutex::new(HashMap::new()),
+            autosave_error: Mutex::new(None),
+            resumable_game: Mutex::new(None),
+            captures: Mutex::new(CaptureCount::default()),
+            capture_history: Mutex::new(Vec::new()),
+            setup: Mutex::new(SetupPosition::empty()),
+            editor: Mutex::new(PositionEditor::new()),
             game_mode: Mutex::new(GameMode::PvP),
             ai_difficulty: Mutex::new(Difficulty::Medium),
             ai_engine: Mutex::new(None),
+            ai_player: Mutex::new(Player::White),
+            black_player_id: Mutex::new(None),
+            white_player_id: Mutex::new(None),
+            rated: Mutex::new(false),
+            rated_game_id: Mutex::new(None),
+            opening: Mutex::new(OpeningState::default()),
+            clock: Mutex::new(GameClock::default()),
             database: Arc::new(Mutex::new(database)),
         }
     }
@@ -48,33 +112,158 @@ impl Default for GameState {
     }
 }
 
+const DATABASE_FILE: &str = "games.db";
+/// 指定数据目录的环境变量，命令行参数 `--data-dir` 优先
+const DATA_DIR_ENV: &str = "GOMOKU_DATA_DIR";
+
+fn data_dir_arg() -> Option<PathBuf> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--data-dir" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(dir) = arg.strip_prefix("--data-dir=") {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    None
+}
+
+/// 数据目录：命令行参数、环境变量、应用配置目录，依次取第一个可用的
+fn resolve_data_dir(config_dir: Option<PathBuf>) -> std::io::Result<PathBuf> {
+    let overridden = data_dir_arg().or_else(|| std::env::var_os(DATA_DIR_ENV).map(PathBuf::from));
+    let legacy_dir = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".gomoku"));
+    let dir = match (overridden, config_dir) {
+        (Some(dir), _) => dir,
+        (None, Some(dir)) => {
+            let legacy_db = legacy_dir.map(|legacy| legacy.join(DATABASE_FILE));
+            if let Some(legacy_db) = legacy_db.filter(|db| db.is_file() && !dir.join(DATABASE_FILE).exists()) {
+                std::fs::create_dir_all(&dir)?;
+                std::fs::copy(&legacy_db, dir.join(DATABASE_FILE))?;
+            }
+            dir
+        }
+        (None, None) => legacy_dir.unwrap_or_else(|| PathBuf::from(".gomoku")),
+    };
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
 // Run function for Tauri app
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // 初始化数据库
-    let app_data_dir = std::env::var("HOME")
-        .map(|home| PathBuf::from(home).join(".gomoku"))
-        .unwrap_or_else(|_| PathBuf::from(".gomoku"));
-
-    std::fs::create_dir_all(&app_data_dir).ok();
-    let db_path = app_data_dir.join("games.db");
-    let database = Database::new(db_path).expect("Failed to initialize database");
-
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
-        .manage(GameState::new(database))
+        .setup(|app| {
+            // 初始化数据库
+            let app_data_dir = resolve_data_dir(app.path().app_config_dir().ok())?;
+            let database = Database::new(app_data_dir.join(DATABASE_FILE))?;
+
+            // 上次关闭或崩溃时未结束的对局
+            let resumable_game = database.unfinished_autosave()?.and_then(|game| game.id);
+            let game_state = GameState::new(database);
+            *game_state.resumable_game.lock().unwrap() = resumable_game;
+            app.manage(game_state);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::place_stone,
             commands::new_game,
             commands::new_game_with_mode,
             commands::get_ai_move,
+            commands::get_ai_turn,
             commands::get_game_config,
             commands::undo_move,
+            commands::get_move_tree,
+            commands::move_tree_next,
+            commands::move_tree_previous,
+            commands::move_tree_jump,
+            commands::promote_variation,
+            commands::delete_variation,
+            commands::set_move_comment,
+            commands::set_move_annotation,
+            commands::set_move_markup,
+            commands::remove_move_markup,
+            commands::clear_move_markup,
+            commands::get_replay_frame,
+            commands::replay_next,
+            commands::replay_previous,
+            commands::replay_goto,
+            commands::replay_to_start,
+            commands::replay_to_end,
+            commands::exit_replay,
+            commands::resume_from_replay,
             commands::get_board_state,
             commands::save_game,
+            commands::get_resumable_game,
+            commands::take_autosave_error,
+            commands::resume_last_game,
             commands::load_game,
             commands::list_saved_games,
+            commands::search_saved_games,
             commands::delete_saved_game,
+            commands::set_game_favorite,
+            commands::get_game_tags,
+            commands::add_game_tag,
+            commands::remove_game_tag,
+            commands::list_tags,
+            commands::delete_tag,
+            commands::list_collections,
+            commands::create_collection,
+            commands::update_collection,
+            commands::delete_collection,
+            commands::get_game_collections,
+            commands::add_game_to_collection,
+            commands::remove_game_from_collection,
+            commands::find_games_with_position,
+            commands::get_opening_explorer,
+            commands::backfill_position_index,
+            commands::list_players,
+            commands::create_player,
+            commands::rename_player,
+            commands::delete_player,
+            commands::get_game_players,
+            commands::set_game_players,
+            commands::get_player_stats,
+            commands::set_game_rated,
+            commands::get_rating_history,
+            commands::rebuild_ratings,
+            commands::get_opening_state,
+            commands::make_opening_decision,
+            commands::offer_fifth_moves,
+            commands::get_ai_opening_action,
+            commands::get_clock_state,
+            commands::create_handicap_setup,
+            commands::get_setup_position,
+            commands::start_position_editor,
+            commands::editor_place_stone,
+            commands::editor_remove_stone,
+            commands::editor_set_to_move,
+            commands::editor_set_rule_set,
+            commands::editor_clear,
+            commands::get_editor_state,
+            commands::play_from_editor,
+            commands::export_position,
+            commands::import_position,
+            commands::format_coordinate,
+            commands::parse_coordinate,
+            commands::export_game,
+            commands::import_game,
+            commands::import_game_text,
+            commands::import_game_directory,
+            commands::export_database,
+            commands::restore_database,
+            commands::merge_database,
+            commands::get_settings,
+            commands::update_settings,
+            commands::get_data_dir,
+            commands::get_captures,
+            commands::get_game_outcome,
+            commands::resign,
+            commands::offer_draw,
+            commands::accept_draw,
+            commands::decline_draw,
+            commands::get_ai_action,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
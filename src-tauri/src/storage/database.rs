@@ -1,78 +1,104 @@
-use rusqlite::{Connection, Result};
-use std::path::PathBuf;
-use super::models::{SavedGame, SavedMove};
+use rusqlite::backup::Backup;
+use rusqlite::{params, params_from_iter, Connection, DatabaseName, OpenFlags, OptionalExtension, Result, Row, Transaction};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use super::migrations;
+use super::query::{GamePage, GameQuery};
+use super::models::{DifficultyRecord, OpponentRecord, PlayerStats, RecordCounts, Streak};
+use super::models::{SavedAnnotation, SavedClock, SavedGame, SavedMarkup, SavedMove, SavedOffer, SavedOpening, SavedSetupStone};
+use super::models::{PositionContinuation, PositionMatch, SavedCollection, SavedPlayer, SavedRatingChange, SavedTag};
+use crate::game::{IndexedPosition, Rating};
+
+/// games 表查询的列（顺序与 row_to_game 对应）
+const GAME_COLUMNS: &str = "id, name, mode, difficulty, created_at, updated_at, status, winner, total_moves, \
+    rule_set, end_reason, black_captures, white_captures, first_player, black_player_id, white_player_id, is_rated, \
+    is_favorite";
+
+/// players 表查询的列（顺序与 row_to_player 对应）
+const PLAYER_COLUMNS: &str = "id, name, kind, difficulty, created_at, rating, rating_deviation, volatility";
+
+/// 某玩家参与的已结束对局，以该玩家的视角给出执色、对手和胜负（?1 为玩家 id）
+const PLAYER_GAMES: &str = "player_games AS (
+    SELECT id, created_at, total_moves, mode, difficulty, color,
+           CASE WHEN color = 'black' THEN white_player_id ELSE black_player_id END AS opponent_id,
+           CASE WHEN status = 'draw' THEN 'draw'
+                WHEN (status = 'black_win') = (color = 'black') THEN 'win'
+                ELSE 'loss' END AS outcome
+    FROM (
+        SELECT *, CASE WHEN black_player_id = ?1 THEN 'black' ELSE 'white' END AS color
+        FROM games
+        WHERE is_autosave = 0 AND status != 'in_progress'
+          AND (black_player_id = ?1 OR white_player_id = ?1)
+    )
+)";
+
+/// 按 player_games 统计胜负和的列（顺序与 row_to_counts 对应）
+const COUNT_COLUMNS: &str = "COUNT(*),
+    COALESCE(SUM(outcome = 'win'), 0),
+    COALESCE(SUM(outcome = 'loss'), 0),
+    COALESCE(SUM(outcome = 'draw'), 0)";
 
 pub struct Database {
     conn: Connection,
+    /// 数据库文件，内存数据库为空
+    path: Option<PathBuf>,
 }
 
 impl Database {
-    /// 初始化数据库
+    /// 初始化数据库，旧版本的数据库先备份再升级
     pub fn new(db_path: PathBuf) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
-        let db = Database { conn };
-        db.create_tables()?;
-        Ok(db)
+        let mut conn = Connection::open(&db_path)?;
+        migrations::migrate(&mut conn, Some(&db_path))?;
+        Ok(Database { conn, path: Some(db_path) })
     }
 
     /// 创建内存数据库（用于测试和默认实例）
     pub fn new_in_memory() -> Result<Self> {
-        let conn = Connection::open_in_memory()?;
-        let db = Database { conn };
-        db.create_tables()?;
-        Ok(db)
+        let mut conn = Connection::open_in_memory()?;
+        migrations::migrate(&mut conn, None)?;
+        Ok(Database { conn, path: None })
     }
 
-    /// 创建表结构
-    fn create_tables(&self) -> Result<()> {
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS games (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                mode TEXT NOT NULL,
-                difficulty TEXT,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL,
-                status TEXT NOT NULL,
-                winner TEXT,
-                total_moves INTEGER DEFAULT 0
-            )",
-            [],
-        )?;
+    /// 把快照文件读入内存数据库并升级到当前结构版本，快照文件本身不变
+    pub fn open_snapshot(snapshot_path: &Path) -> Result<Self> {
+        let source = Connection::open_with_flags(snapshot_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut conn = Connection::open_in_memory()?;
+        Backup::new(&source, &mut conn)?.run_to_completion(256, Duration::ZERO, None)?;
+        migrations::migrate(&mut conn, None)?;
+        Ok(Database { conn, path: None })
+    }
 
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS moves (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                game_id INTEGER NOT NULL,
-                move_number INTEGER NOT NULL,
-                player TEXT NOT NULL,
-                position_x INTEGER NOT NULL,
-                position_y INTEGER NOT NULL,
-                timestamp INTEGER NOT NULL,
-                FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_games_status ON games(status)",
-            [],
-        )?;
+    /// 用 SQLite 在线备份导出一致的快照（目标文件已存在时被覆盖）
+    pub fn backup_to(&self, snapshot_path: &Path) -> Result<()> {
+        self.conn.backup(DatabaseName::Main, snapshot_path, None)
+    }
 
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_moves_game_id ON moves(game_id)",
-            [],
-        )?;
+    /// 用另一个数据库（通常来自 `open_snapshot`）的内容替换当前数据库
+    pub fn restore_from(&mut self, snapshot: &Database) -> Result<()> {
+        Backup::new(&snapshot.conn, &mut self.conn)?.run_to_completion(256, Duration::ZERO, None)
+    }
 
-        Ok(())
+    /// 数据库结构版本
+    pub fn schema_version(&self) -> Result<i32> {
+        migrations::schema_version(&self.conn)
+    }
+
+    /// 开始事务，提交前丢弃（drop）即回滚
+    pub fn transaction(&self) -> Result<Transaction<'_>> {
+        self.conn.unchecked_transaction()
     }
 
     /// 保存游戏
     pub fn save_game(&self, game: &SavedGame) -> Result<i64> {
         self.conn.execute(
-            "INSERT INTO games (name, mode, difficulty, created_at, updated_at, status, winner, total_moves)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            (
+            "INSERT INTO games (name, mode, difficulty, created_at, updated_at, status, winner, total_moves, rule_set, end_reason, black_captures, white_captures, first_player,
+                                black_player_id, white_player_id, is_rated, is_favorite)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
                 &game.name,
                 &game.mode,
                 &game.difficulty,
@@ -81,57 +107,685 @@ impl Database {
                 &game.status,
                 &game.winner,
                 game.total_moves,
-            ),
+                &game.rule_set,
+                &game.end_reason,
+                game.black_captures,
+                game.white_captures,
+                &game.first_player,
+                game.black_player_id,
+                game.white_player_id,
+                game.is_rated,
+                game.is_favorite,
+            ],
         )?;
         Ok(self.conn.last_insert_rowid())
     }
 
+    /// 更新已保存的游戏（保留创建时间、是否计分和收藏标记），返回是否存在该游戏
+    pub fn update_game(&self, game_id: i64, game: &SavedGame) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE games SET name = ?1, mode = ?2, difficulty = ?3, updated_at = ?4, status = ?5, winner = ?6,
+                              total_moves = ?7, rule_set = ?8, end_reason = ?9, black_captures = ?10,
+                              white_captures = ?11, first_player = ?12, black_player_id = ?13, white_player_id = ?14
+                 WHERE id = ?15",
+            (
+                &game.name,
+                &game.mode,
+                &game.difficulty,
+                game.updated_at,
+                &game.status,
+                &game.winner,
+                game.total_moves,
+                &game.rule_set,
+                &game.end_reason,
+                game.black_captures,
+                game.white_captures,
+                &game.first_player,
+                game.black_player_id,
+                game.white_player_id,
+                game_id,
+            ),
+        )?;
+        Ok(updated > 0)
+    }
+
     /// 保存落子记录
     pub fn save_move(&self, move_data: &SavedMove) -> Result<i64> {
         self.conn.execute(
-            "INSERT INTO moves (game_id, move_number, player, position_x, position_y, timestamp)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            "INSERT INTO moves (game_id, move_number, turn_number, player, position_x, position_y, timestamp,
+                                parent_move_id, is_main_line)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             (
                 move_data.game_id,
                 move_data.move_number,
+                move_data.turn_number,
                 &move_data.player,
                 move_data.position_x,
                 move_data.position_y,
                 move_data.timestamp,
+                move_data.parent_move_id,
+                move_data.is_main_line,
             ),
         )?;
         Ok(self.conn.last_insert_rowid())
     }
 
-    /// 获取所有游戏列表
-    pub fn list_games(&self) -> Result<Vec<SavedGame>> {
+    /// 保存五手打点记录
+    pub fn save_offer(&self, offer: &SavedOffer) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO move_offers (game_id, move_number, position_x, position_y, chosen)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                offer.game_id,
+                offer.move_number,
+                offer.position_x,
+                offer.position_y,
+                offer.chosen,
+            ),
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 获取游戏的五手打点记录
+    pub fn get_offers(&self, game_id: i64) -> Result<Vec<SavedOffer>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, name, mode, difficulty, created_at, updated_at, status, winner, total_moves
-                 FROM games ORDER BY updated_at DESC"
+            "SELECT id, game_id, move_number, position_x, position_y, chosen
+                 FROM move_offers WHERE game_id = ?1 ORDER BY id ASC"
         )?;
 
-        let games = stmt.query_map([], |row| {
-            Ok(SavedGame {
+        let offers = stmt.query_map([game_id], |row| {
+            Ok(SavedOffer {
                 id: Some(row.get(0)?),
-                name: row.get(1)?,
-                mode: row.get(2)?,
-                difficulty: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-                status: row.get(6)?,
-                winner: row.get(7)?,
-                total_moves: row.get(8)?,
+                game_id: row.get(1)?,
+                move_number: row.get(2)?,
+                position_x: row.get(3)?,
+                position_y: row.get(4)?,
+                chosen: row.get(5)?,
+            })
+        })?;
+
+        offers.collect()
+    }
+
+    /// 保存初始局面中的一枚棋子
+    pub fn save_setup_stone(&self, stone: &SavedSetupStone) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO setup_stones (game_id, player, position_x, position_y)
+                 VALUES (?1, ?2, ?3, ?4)",
+            (
+                stone.game_id,
+                &stone.player,
+                stone.position_x,
+                stone.position_y,
+            ),
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 获取游戏的初始局面
+    pub fn get_setup_stones(&self, game_id: i64) -> Result<Vec<SavedSetupStone>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, game_id, player, position_x, position_y
+                 FROM setup_stones WHERE game_id = ?1 ORDER BY id ASC"
+        )?;
+
+        let stones = stmt.query_map([game_id], |row| {
+            Ok(SavedSetupStone {
+                id: Some(row.get(0)?),
+                game_id: row.get(1)?,
+                player: row.get(2)?,
+                position_x: row.get(3)?,
+                position_y: row.get(4)?,
             })
         })?;
 
+        stones.collect()
+    }
+
+    /// 保存一手棋的评注
+    pub fn save_annotation(&self, annotation: &SavedAnnotation) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO move_annotations (move_id, comment, glyph) VALUES (?1, ?2, ?3)",
+            (annotation.move_id, &annotation.comment, &annotation.glyph),
+        )?;
+        Ok(())
+    }
+
+    /// 获取游戏中所有着法的评注
+    pub fn get_annotations(&self, game_id: i64) -> Result<Vec<SavedAnnotation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.move_id, a.comment, a.glyph
+                 FROM move_annotations a JOIN moves m ON m.id = a.move_id
+                 WHERE m.game_id = ?1 ORDER BY a.move_id ASC"
+        )?;
+
+        let annotations = stmt.query_map([game_id], |row| {
+            Ok(SavedAnnotation {
+                move_id: row.get(0)?,
+                comment: row.get(1)?,
+                glyph: row.get(2)?,
+            })
+        })?;
+
+        annotations.collect()
+    }
+
+    /// 保存棋盘标记
+    pub fn save_markup(&self, markup: &SavedMarkup) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO move_markup (move_id, position_x, position_y, kind, label)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                markup.move_id,
+                markup.position_x,
+                markup.position_y,
+                &markup.kind,
+                &markup.label,
+            ),
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 获取游戏中所有着法的棋盘标记
+    pub fn get_markup(&self, game_id: i64) -> Result<Vec<SavedMarkup>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT k.id, k.move_id, k.position_x, k.position_y, k.kind, k.label
+                 FROM move_markup k JOIN moves m ON m.id = k.move_id
+                 WHERE m.game_id = ?1 ORDER BY k.id ASC"
+        )?;
+
+        let markup = stmt.query_map([game_id], |row| {
+            Ok(SavedMarkup {
+                id: Some(row.get(0)?),
+                move_id: row.get(1)?,
+                position_x: row.get(2)?,
+                position_y: row.get(3)?,
+                kind: row.get(4)?,
+                label: row.get(5)?,
+            })
+        })?;
+
+        markup.collect()
+    }
+
+    /// 保存棋钟状态
+    pub fn save_clock(&self, clock: &SavedClock) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO game_clocks
+                 (game_id, time_control, black_remaining_ms, black_periods, white_remaining_ms, white_periods, flagged)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            (
+                clock.game_id,
+                &clock.time_control,
+                clock.black_remaining_ms,
+                clock.black_periods,
+                clock.white_remaining_ms,
+                clock.white_periods,
+                &clock.flagged,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// 获取游戏的棋钟状态（不计时的游戏没有记录）
+    pub fn get_clock(&self, game_id: i64) -> Result<Option<SavedClock>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT game_id, time_control, black_remaining_ms, black_periods, white_remaining_ms, white_periods, flagged
+                 FROM game_clocks WHERE game_id = ?1"
+        )?;
+
+        let mut clocks = stmt.query_map([game_id], |row| {
+            Ok(SavedClock {
+                game_id: row.get(0)?,
+                time_control: row.get(1)?,
+                black_remaining_ms: row.get(2)?,
+                black_periods: row.get(3)?,
+                white_remaining_ms: row.get(4)?,
+                white_periods: row.get(5)?,
+                flagged: row.get(6)?,
+            })
+        })?;
+
+        clocks.next().transpose()
+    }
+
+    /// 保存开局状态
+    pub fn save_opening(&self, opening: &SavedOpening) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO game_openings (game_id, rule, state) VALUES (?1, ?2, ?3)",
+            (opening.game_id, &opening.rule, &opening.state),
+        )?;
+        Ok(())
+    }
+
+    /// 获取游戏的开局状态
+    pub fn get_opening(&self, game_id: i64) -> Result<Option<SavedOpening>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT game_id, rule, state FROM game_openings WHERE game_id = ?1"
+        )?;
+
+        let mut openings = stmt.query_map([game_id], |row| {
+            Ok(SavedOpening {
+                game_id: row.get(0)?,
+                rule: row.get(1)?,
+                state: row.get(2)?,
+            })
+        })?;
+
+        openings.next().transpose()
+    }
+
+    /// 获取所有保存的游戏列表
+    pub fn list_games(&self) -> Result<Vec<SavedGame>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM games WHERE is_autosave = 0 ORDER BY updated_at DESC",
+            GAME_COLUMNS
+        ))?;
+
+        let games = stmt.query_map([], Self::row_to_game)?;
+
         games.collect()
     }
 
-    /// 获取游戏的所有落子记录
+    /// 按条件筛选、排序并分页查询游戏
+    pub fn search_games(&self, query: &GameQuery) -> Result<GamePage> {
+        let (conditions, params) = query.where_clause();
+
+        let total = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM games WHERE {}", conditions),
+            params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM games WHERE {} {}",
+            GAME_COLUMNS,
+            conditions,
+            query.order_clause()
+        ))?;
+        let games = stmt
+            .query_map(params_from_iter(params.iter()), Self::row_to_game)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(GamePage { games, total })
+    }
+
+    /// 把游戏标记为自动保存，并删除之前的自动保存
+    pub fn make_autosave(&self, game_id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM games WHERE is_autosave = 1 AND id != ?1", [game_id])?;
+        self.conn.execute("UPDATE games SET is_autosave = 1 WHERE id = ?1", [game_id])?;
+        Ok(())
+    }
+
+    /// 最近一次未结束的自动保存
+    pub fn unfinished_autosave(&self) -> Result<Option<SavedGame>> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM games WHERE is_autosave = 1 AND status = 'in_progress'
+                         ORDER BY updated_at DESC LIMIT 1",
+                    GAME_COLUMNS
+                ),
+                [],
+                Self::row_to_game,
+            )
+            .optional()
+    }
+
+    /// 按 id 获取游戏
+    pub fn get_game(&self, game_id: i64) -> Result<Option<SavedGame>> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM games WHERE id = ?1", GAME_COLUMNS),
+                [game_id],
+                Self::row_to_game,
+            )
+            .optional()
+    }
+
+    fn row_to_game(row: &Row) -> Result<SavedGame> {
+        Ok(SavedGame {
+            id: Some(row.get(0)?),
+            name: row.get(1)?,
+            mode: row.get(2)?,
+            difficulty: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            status: row.get(6)?,
+            winner: row.get(7)?,
+            total_moves: row.get(8)?,
+            rule_set: row.get(9)?,
+            end_reason: row.get(10)?,
+            black_captures: row.get(11)?,
+            white_captures: row.get(12)?,
+            first_player: row.get(13)?,
+            black_player_id: row.get(14)?,
+            white_player_id: row.get(15)?,
+            is_rated: row.get(16)?,
+            is_favorite: row.get(17)?,
+        })
+    }
+
+    /// 新建玩家档案
+    pub fn save_player(&self, player: &SavedPlayer) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO players (name, kind, difficulty, created_at) VALUES (?1, ?2, ?3, ?4)",
+            (&player.name, &player.kind, &player.difficulty, player.created_at),
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 全部玩家档案，人类玩家在前
+    pub fn list_players(&self) -> Result<Vec<SavedPlayer>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM players ORDER BY kind = 'ai', name COLLATE NOCASE, id",
+            PLAYER_COLUMNS
+        ))?;
+        let players = stmt.query_map([], Self::row_to_player)?;
+        players.collect()
+    }
+
+    pub fn get_player(&self, player_id: i64) -> Result<Option<SavedPlayer>> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM players WHERE id = ?1", PLAYER_COLUMNS),
+                [player_id],
+                Self::row_to_player,
+            )
+            .optional()
+    }
+
+    /// 某难度 AI 的档案
+    pub fn ai_player_id(&self, difficulty: &str) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT id FROM players WHERE kind = 'ai' AND difficulty = ?1",
+                [difficulty],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn rename_player(&self, player_id: i64, name: &str) -> Result<bool> {
+        let updated = self.conn.execute("UPDATE players SET name = ?1 WHERE id = ?2", (name, player_id))?;
+        Ok(updated > 0)
+    }
+
+    /// 删除人类玩家档案，对局中的引用置空
+    pub fn delete_player(&self, player_id: i64) -> Result<bool> {
+        let deleted = self.conn.execute("DELETE FROM players WHERE id = ?1 AND kind = 'human'", [player_id])?;
+        Ok(deleted > 0)
+    }
+
+    fn row_to_player(row: &Row) -> Result<SavedPlayer> {
+        Ok(SavedPlayer {
+            id: Some(row.get(0)?),
+            name: row.get(1)?,
+            kind: row.get(2)?,
+            difficulty: row.get(3)?,
+            created_at: row.get(4)?,
+            rating: row.get(5)?,
+            rating_deviation: row.get(6)?,
+            volatility: row.get(7)?,
+        })
+    }
+
+    fn player_rating(&self, player_id: i64) -> Result<Rating> {
+        self.conn.query_row(
+            "SELECT rating, rating_deviation, volatility FROM players WHERE id = ?1",
+            [player_id],
+            |row| {
+                Ok(Rating {
+                    rating: row.get(0)?,
+                    deviation: row.get(1)?,
+                    volatility: row.get(2)?,
+                })
+            },
+        )
+    }
+
+    fn apply_rating(&self, player_id: i64, game_id: i64, before: &Rating, after: &Rating, time: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE players SET rating = ?1, rating_deviation = ?2, volatility = ?3 WHERE id = ?4",
+            (after.rating, after.deviation, after.volatility, player_id),
+        )?;
+        self.conn.execute(
+            "INSERT INTO rating_history (player_id, game_id, rating, rating_deviation, volatility, rating_change, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            (
+                player_id,
+                game_id,
+                after.rating,
+                after.deviation,
+                after.volatility,
+                after.rating - before.rating,
+                time,
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// 把对局记为计分对局并保留在棋谱列表中
+    pub fn mark_rated_game(&self, game_id: i64, name: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE games SET is_autosave = 0, is_rated = 1, name = ?1 WHERE id = ?2",
+            (name, game_id),
+        )?;
+        Ok(())
+    }
+
+    /// 按计分对局的结果更新双方等级分，返回是否计分。
+    /// 未结束、缺少一方玩家或已经计过分的对局不计分
+    pub fn rate_game(&self, game_id: i64) -> Result<bool> {
+        let Some(game) = self.get_game(game_id)? else {
+            return Ok(false);
+        };
+        let (Some(black_id), Some(white_id)) = (game.black_player_id, game.white_player_id) else {
+            return Ok(false);
+        };
+        let black_score = match game.status.as_str() {
+            "black_win" => 1.0,
+            "white_win" => 0.0,
+            "draw" => 0.5,
+            _ => return Ok(false),
+        };
+        if !game.is_rated || black_id == white_id {
+            return Ok(false);
+        }
+        let rated: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM rating_history WHERE game_id = ?1)",
+            [game_id],
+            |row| row.get(0),
+        )?;
+        if rated {
+            return Ok(false);
+        }
+
+        let black = self.player_rating(black_id)?;
+        let white = self.player_rating(white_id)?;
+        // 与 rebuild_ratings 的排序使用同一个时间
+        self.apply_rating(black_id, game_id, &black, &black.update(&white, black_score), game.created_at)?;
+        self.apply_rating(white_id, game_id, &white, &white.update(&black, 1.0 - black_score), game.created_at)?;
+        Ok(true)
+    }
+
+    /// 清空等级分，按时间顺序重新计算全部计分对局，返回计分的对局数
+    pub fn rebuild_ratings(&self) -> Result<usize> {
+        let tx = self.transaction()?;
+        let initial = Rating::default();
+        self.conn.execute("DELETE FROM rating_history", [])?;
+        self.conn.execute(
+            "UPDATE players SET rating = ?1, rating_deviation = ?2, volatility = ?3",
+            (initial.rating, initial.deviation, initial.volatility),
+        )?;
+
+        let game_ids = {
+            let mut stmt = self.conn.prepare(
+                "SELECT id FROM games WHERE is_rated = 1 AND status != 'in_progress' ORDER BY created_at, id",
+            )?;
+            let ids = stmt.query_map([], |row| row.get::<_, i64>(0))?;
+            ids.collect::<Result<Vec<_>>>()?
+        };
+        let mut rated = 0;
+        for game_id in game_ids {
+            if self.rate_game(game_id)? {
+                rated += 1;
+            }
+        }
+
+        tx.commit()?;
+        Ok(rated)
+    }
+
+    /// 玩家的等级分变化记录，按时间顺序
+    pub fn rating_history(&self, player_id: i64) -> Result<Vec<SavedRatingChange>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, player_id, game_id, rating, rating_deviation, volatility, rating_change, created_at
+                 FROM rating_history WHERE player_id = ?1 ORDER BY id",
+        )?;
+        let history = stmt.query_map([player_id], |row| {
+            Ok(SavedRatingChange {
+                id: Some(row.get(0)?),
+                player_id: row.get(1)?,
+                game_id: row.get(2)?,
+                rating: row.get(3)?,
+                rating_deviation: row.get(4)?,
+                volatility: row.get(5)?,
+                rating_change: row.get(6)?,
+                created_at: row.get(7)?,
+            })
+        })?;
+        history.collect()
+    }
+
+    fn row_to_counts(row: &Row, start: usize) -> Result<RecordCounts> {
+        Ok(RecordCounts {
+            games: row.get(start)?,
+            wins: row.get(start + 1)?,
+            losses: row.get(start + 2)?,
+            draws: row.get(start + 3)?,
+        })
+    }
+
+    /// 玩家统计：按执色、对手和 AI 难度的胜负和，平均手数以及连胜连败
+    pub fn player_stats(&self, player_id: i64) -> Result<Option<PlayerStats>> {
+        let Some(player) = self.get_player(player_id)? else {
+            return Ok(None);
+        };
+
+        let (overall, average_moves) = self.conn.query_row(
+            &format!(
+                "WITH {} SELECT {}, COALESCE(AVG(total_moves), 0) FROM player_games",
+                PLAYER_GAMES, COUNT_COLUMNS
+            ),
+            [player_id],
+            |row| Ok((Self::row_to_counts(row, 0)?, row.get(4)?)),
+        )?;
+
+        let mut as_black = RecordCounts::default();
+        let mut as_white = RecordCounts::default();
+        {
+            let mut stmt = self.conn.prepare(&format!(
+                "WITH {} SELECT color, {} FROM player_games GROUP BY color",
+                PLAYER_GAMES, COUNT_COLUMNS
+            ))?;
+            let mut rows = stmt.query([player_id])?;
+            while let Some(row) = rows.next()? {
+                let color: String = row.get(0)?;
+                let counts = Self::row_to_counts(row, 1)?;
+                if color == "black" {
+                    as_black = counts;
+                } else {
+                    as_white = counts;
+                }
+            }
+        }
+
+        let by_opponent = {
+            let mut stmt = self.conn.prepare(&format!(
+                "WITH {} SELECT opponent_id, players.name, {} FROM player_games
+                     LEFT JOIN players ON players.id = opponent_id
+                     GROUP BY opponent_id ORDER BY COUNT(*) DESC, opponent_id",
+                PLAYER_GAMES, COUNT_COLUMNS
+            ))?;
+            let records = stmt.query_map([player_id], |row| {
+                Ok(OpponentRecord {
+                    opponent_id: row.get(0)?,
+                    opponent_name: row.get(1)?,
+                    record: Self::row_to_counts(row, 2)?,
+                })
+            })?;
+            records.collect::<Result<Vec<_>>>()?
+        };
+
+        let by_difficulty = {
+            let mut stmt = self.conn.prepare(&format!(
+                "WITH {} SELECT difficulty, {} FROM player_games
+                     WHERE mode = 'pve' AND difficulty IS NOT NULL
+                     GROUP BY difficulty ORDER BY difficulty",
+                PLAYER_GAMES, COUNT_COLUMNS
+            ))?;
+            let records = stmt.query_map([player_id], |row| {
+                Ok(DifficultyRecord {
+                    difficulty: row.get(0)?,
+                    record: Self::row_to_counts(row, 1)?,
+                })
+            })?;
+            records.collect::<Result<Vec<_>>>()?
+        };
+
+        // 连续相同结果划为一段：总序号减去同结果内的序号在同一段内不变
+        let streaks = format!(
+            "WITH {},
+             ordered AS (
+                 SELECT outcome, ROW_NUMBER() OVER (ORDER BY created_at, id) AS seq FROM player_games
+             ),
+             runs AS (
+                 SELECT outcome, COUNT(*) AS length, MAX(seq) AS last_seq FROM (
+                     SELECT outcome, seq, seq - ROW_NUMBER() OVER (PARTITION BY outcome ORDER BY seq) AS run
+                     FROM ordered
+                 ) GROUP BY outcome, run
+             )",
+            PLAYER_GAMES
+        );
+        let (longest_win_streak, longest_loss_streak) = self.conn.query_row(
+            &format!(
+                "{} SELECT COALESCE(MAX(CASE WHEN outcome = 'win' THEN length END), 0),
+                           COALESCE(MAX(CASE WHEN outcome = 'loss' THEN length END), 0)
+                    FROM runs",
+                streaks
+            ),
+            [player_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let current_streak = self.conn
+            .query_row(
+                &format!("{} SELECT outcome, length FROM runs ORDER BY last_seq DESC LIMIT 1", streaks),
+                [player_id],
+                |row| Ok(Streak { outcome: row.get(0)?, length: row.get(1)? }),
+            )
+            .optional()?;
+
+        Ok(Some(PlayerStats {
+            player,
+            overall,
+            as_black,
+            as_white,
+            by_opponent,
+            by_difficulty,
+            average_moves,
+            current_streak,
+            longest_win_streak,
+            longest_loss_streak,
+        }))
+    }
+
+    /// 获取游戏的所有落子记录（包括变化），按保存顺序排列，上一手总在后续之前
     pub fn get_moves(&self, game_id: i64) -> Result<Vec<SavedMove>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, game_id, move_number, player, position_x, position_y, timestamp
-                 FROM moves WHERE game_id = ?1 ORDER BY move_number ASC"
+            "SELECT id, game_id, move_number, turn_number, player, position_x, position_y, timestamp,
+                    parent_move_id, is_main_line
+                 FROM moves WHERE game_id = ?1 ORDER BY id ASC"
         )?;
 
         let moves = stmt.query_map([game_id], |row| {
@@ -139,19 +793,376 @@ impl Database {
                 id: Some(row.get(0)?),
                 game_id: row.get(1)?,
                 move_number: row.get(2)?,
-                player: row.get(3)?,
-                position_x: row.get(4)?,
-                position_y: row.get(5)?,
-                timestamp: row.get(6)?,
+                turn_number: row.get(3)?,
+                player: row.get(4)?,
+                position_x: row.get(5)?,
+                position_y: row.get(6)?,
+                timestamp: row.get(7)?,
+                parent_move_id: row.get(8)?,
+                is_main_line: row.get(9)?,
             })
         })?;
 
         moves.collect()
     }
 
+    /// 更新落子是否在主线上
+    pub fn set_move_main_line(&self, move_id: i64, is_main_line: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE moves SET is_main_line = ?1 WHERE id = ?2",
+            (is_main_line, move_id),
+        )?;
+        Ok(())
+    }
+
+    /// 记录游戏当前所在的落子，`None` 为初始局面
+    pub fn set_current_move(&self, game_id: i64, move_id: Option<i64>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE games SET current_move_id = ?1 WHERE id = ?2",
+            (move_id, game_id),
+        )?;
+        Ok(())
+    }
+
+    /// 获取游戏当前所在的落子
+    pub fn get_current_move(&self, game_id: i64) -> Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT current_move_id FROM games WHERE id = ?1",
+                [game_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+    }
+
+    /// 删除落子记录（连同评注和标记）
+    pub fn delete_move(&self, move_id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM moves WHERE id = ?1", [move_id])?;
+        Ok(())
+    }
+
+    /// 删除一手棋的评注和标记
+    pub fn delete_notes(&self, move_id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM move_annotations WHERE move_id = ?1", [move_id])?;
+        self.conn.execute("DELETE FROM move_markup WHERE move_id = ?1", [move_id])?;
+        Ok(())
+    }
+
+    /// 删除游戏的五手打点记录
+    pub fn delete_offers(&self, game_id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM move_offers WHERE game_id = ?1", [game_id])?;
+        Ok(())
+    }
+
     /// 删除游戏
     pub fn delete_game(&self, game_id: i64) -> Result<()> {
         self.conn.execute("DELETE FROM games WHERE id = ?1", [game_id])?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// 从第 `first` 手起重写游戏主线的局面索引（`positions[0]` 为第 `first` 手之后的局面）
+    pub fn save_position_index(&self, game_id: i64, first: usize, positions: &[IndexedPosition]) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM position_index WHERE game_id = ?1 AND move_number >= ?2",
+            (game_id, first as i64),
+        )?;
+        let mut stmt = self.conn.prepare(
+            "INSERT INTO position_index (game_id, move_number, position_hash, next_x, next_y)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (offset, position) in positions.iter().enumerate() {
+            // SQLite 整数有符号，按位保存
+            stmt.execute((
+                game_id,
+                (first + offset) as i64,
+                position.hash as i64,
+                position.next.as_ref().map(|next| next.x as i64),
+                position.next.as_ref().map(|next| next.y as i64),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// 还没有局面索引的游戏
+    pub fn unindexed_games(&self) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM games WHERE NOT EXISTS (SELECT 1 FROM position_index WHERE game_id = games.id)
+                 ORDER BY id",
+        )?;
+        let ids = stmt.query_map([], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    /// 出现过该局面的游戏，同一盘只取第一次出现
+    pub fn find_position(&self, hash: u64, rule_set: &str) -> Result<Vec<PositionMatch>> {
+        let columns = GAME_COLUMNS
+            .split(',')
+            .map(|column| format!("games.{}", column.trim()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, MIN(position_index.move_number) AS first_move FROM position_index
+                 JOIN games ON games.id = position_index.game_id
+                 WHERE position_index.position_hash = ?1 AND games.rule_set = ?2 AND games.is_autosave = 0
+                 GROUP BY games.id ORDER BY games.updated_at DESC",
+            columns
+        ))?;
+        let matches = stmt.query_map((hash as i64, rule_set), |row| {
+            Ok(PositionMatch {
+                game: Self::row_to_game(row)?,
+                move_number: row.get("first_move")?,
+            })
+        })?;
+        matches.collect()
+    }
+
+    /// 保存的游戏在该局面之后的下一手，按下一手分组统计结果和对局时的等级分。
+    /// 同一盘只取第一次出现
+    pub fn position_continuations(&self, hash: u64, rule_set: &str) -> Result<Vec<PositionContinuation>> {
+        let mut stmt = self.conn.prepare(
+            "WITH occurrences AS (
+                 SELECT game_id, MIN(move_number) AS move_number FROM position_index
+                 WHERE position_hash = ?1 GROUP BY game_id
+             )
+             SELECT position_index.next_x, position_index.next_y, COUNT(*),
+                    COALESCE(SUM(games.status = 'black_win'), 0),
+                    COALESCE(SUM(games.status = 'white_win'), 0),
+                    COALESCE(SUM(games.status = 'draw'), 0),
+                    COALESCE(SUM(black.rating - black.rating_change), 0), COUNT(black.rating),
+                    COALESCE(SUM(white.rating - white.rating_change), 0), COUNT(white.rating)
+                 FROM occurrences
+                 JOIN position_index ON position_index.game_id = occurrences.game_id
+                     AND position_index.move_number = occurrences.move_number
+                 JOIN games ON games.id = occurrences.game_id
+                 LEFT JOIN rating_history AS black
+                     ON black.game_id = games.id AND black.player_id = games.black_player_id
+                 LEFT JOIN rating_history AS white
+                     ON white.game_id = games.id AND white.player_id = games.white_player_id
+                 WHERE games.rule_set = ?2 AND games.is_autosave = 0
+                 GROUP BY position_index.next_x, position_index.next_y",
+        )?;
+        let continuations = stmt.query_map((hash as i64, rule_set), |row| {
+            Ok(PositionContinuation {
+                next_x: row.get(0)?,
+                next_y: row.get(1)?,
+                games: row.get(2)?,
+                black_wins: row.get(3)?,
+                white_wins: row.get(4)?,
+                draws: row.get(5)?,
+                black_rating_total: row.get(6)?,
+                black_rated_games: row.get(7)?,
+                white_rating_total: row.get(8)?,
+                white_rated_games: row.get(9)?,
+            })
+        })?;
+        continuations.collect()
+    }
+
+    /// 全部设置（值为 JSON 文本）
+    pub fn get_settings(&self) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare("SELECT key, value FROM settings ORDER BY key")?;
+        let settings = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        settings.collect()
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            (key, value),
+        )?;
+        Ok(())
+    }
+
+    /// 设置收藏标记，返回是否存在该游戏
+    pub fn set_favorite(&self, game_id: i64, favorite: bool) -> Result<bool> {
+        let updated = self.conn.execute("UPDATE games SET is_favorite = ?1 WHERE id = ?2", (favorite, game_id))?;
+        Ok(updated > 0)
+    }
+
+    /// 给游戏加标签，标签不存在时新建
+    pub fn add_tag(&self, game_id: i64, tag: &str) -> Result<()> {
+        self.conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+        self.conn.execute(
+            "INSERT OR IGNORE INTO game_tags (game_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+            (game_id, tag),
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_tag(&self, game_id: i64, tag: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM game_tags WHERE game_id = ?1 AND tag_id IN (SELECT id FROM tags WHERE name = ?2)",
+            (game_id, tag),
+        )?;
+        Ok(())
+    }
+
+    /// 游戏的全部标签
+    pub fn get_tags(&self, game_id: i64) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tags.name FROM game_tags JOIN tags ON tags.id = game_tags.tag_id
+                 WHERE game_tags.game_id = ?1 ORDER BY tags.name",
+        )?;
+        let tags = stmt.query_map([game_id], |row| row.get(0))?;
+        tags.collect()
+    }
+
+    /// 全部标签及使用次数
+    pub fn list_tags(&self) -> Result<Vec<SavedTag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tags.id, tags.name, COUNT(games.id) FROM tags
+                 LEFT JOIN game_tags ON game_tags.tag_id = tags.id
+                 LEFT JOIN games ON games.id = game_tags.game_id AND games.is_autosave = 0
+                 GROUP BY tags.id ORDER BY tags.name",
+        )?;
+        let tags = stmt.query_map([], |row| {
+            Ok(SavedTag {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                game_count: row.get(2)?,
+            })
+        })?;
+        tags.collect()
+    }
+
+    /// 删除标签（从所有游戏上移除），返回是否存在
+    pub fn delete_tag(&self, tag_id: i64) -> Result<bool> {
+        let deleted = self.conn.execute("DELETE FROM tags WHERE id = ?1", [tag_id])?;
+        Ok(deleted > 0)
+    }
+
+    /// 新建合集
+    pub fn save_collection(&self, collection: &SavedCollection) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO collections (name, description, created_at) VALUES (?1, ?2, ?3)",
+            (&collection.name, &collection.description, collection.created_at),
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// 修改合集名称和说明，返回是否存在
+    pub fn update_collection(&self, collection_id: i64, name: &str, description: Option<&str>) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE collections SET name = ?1, description = ?2 WHERE id = ?3",
+            (name, description, collection_id),
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// 删除合集（其中的游戏保留），返回是否存在
+    pub fn delete_collection(&self, collection_id: i64) -> Result<bool> {
+        let deleted = self.conn.execute("DELETE FROM collections WHERE id = ?1", [collection_id])?;
+        Ok(deleted > 0)
+    }
+
+    /// 全部合集及其中的对局数
+    pub fn list_collections(&self) -> Result<Vec<SavedCollection>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT collections.id, collections.name, collections.description, collections.created_at,
+                    COUNT(collection_games.game_id)
+                 FROM collections LEFT JOIN collection_games ON collection_games.collection_id = collections.id
+                 GROUP BY collections.id ORDER BY collections.name",
+        )?;
+        let collections = stmt.query_map([], |row| {
+            Ok(SavedCollection {
+                id: Some(row.get(0)?),
+                name: row.get(1)?,
+                description: row.get(2)?,
+                created_at: row.get(3)?,
+                game_count: row.get(4)?,
+            })
+        })?;
+        collections.collect()
+    }
+
+    /// 游戏所在的合集
+    pub fn get_game_collections(&self, game_id: i64) -> Result<Vec<i64>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT collection_id FROM collection_games WHERE game_id = ?1 ORDER BY collection_id",
+        )?;
+        let ids = stmt.query_map([game_id], |row| row.get(0))?;
+        ids.collect()
+    }
+
+    pub fn add_to_collection(&self, collection_id: i64, game_id: i64, added_at: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO collection_games (collection_id, game_id, added_at) VALUES (?1, ?2, ?3)",
+            (collection_id, game_id, added_at),
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_from_collection(&self, collection_id: i64, game_id: i64) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM collection_games WHERE collection_id = ?1 AND game_id = ?2",
+            (collection_id, game_id),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::{game, player};
+
+    #[test]
+    fn stats_count_wins_against_an_unknown_opponent() {
+        let db = Database::new_in_memory().unwrap();
+        let alice = player(&db, "Alice");
+        db.save_game(&game("white_win", None, Some(alice))).unwrap();
+        db.save_game(&game("white_win", Some(alice), None)).unwrap();
+        db.save_game(&game("black_win", Some(alice), None)).unwrap();
+        db.save_game(&game("draw", None, Some(alice))).unwrap();
+
+        let stats = db.player_stats(alice).unwrap().unwrap();
+        assert_eq!((stats.overall.games, stats.overall.wins, stats.overall.losses, stats.overall.draws), (4, 2, 1, 1));
+        assert_eq!((stats.as_white.wins, stats.as_white.draws), (1, 1));
+        assert_eq!((stats.as_black.wins, stats.as_black.losses), (1, 1));
+    }
+
+    #[test]
+    fn find_position_reports_the_first_occurrence() {
+        let db = Database::new_in_memory().unwrap();
+        let game_id = db.save_game(&game("black_win", None, None)).unwrap();
+        let positions: Vec<IndexedPosition> = [1, 2, 3, 2]
+            .iter()
+            .map(|&hash| IndexedPosition { hash, next: None })
+            .collect();
+        db.save_position_index(game_id, 0, &positions).unwrap();
+
+        let matches = db.find_position(2, "freestyle").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].game.id, Some(game_id));
+        assert_eq!(matches[0].move_number, 1);
+        assert!(db.find_position(2, "renju").unwrap().is_empty());
+    }
+
+    #[test]
+    fn current_move_is_cleared_with_its_move() {
+        let db = Database::new_in_memory().unwrap();
+        let game_id = db.save_game(&game("in_progress", None, None)).unwrap();
+        assert_eq!(db.get_current_move(game_id).unwrap(), None);
+
+        let move_id = db.save_move(&SavedMove {
+            id: None,
+            game_id,
+            move_number: 1,
+            turn_number: 1,
+            player: "black".to_string(),
+            position_x: 7,
+            position_y: 7,
+            timestamp: 0,
+            parent_move_id: None,
+            is_main_line: true,
+        })
+        .unwrap();
+        db.set_current_move(game_id, Some(move_id)).unwrap();
+        assert_eq!(db.get_current_move(game_id).unwrap(), Some(move_id));
+
+        db.delete_move(move_id).unwrap();
+        assert_eq!(db.get_current_move(game_id).unwrap(), None);
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Annotation, GameTree, Markup, MarkupKind, MoveNotes, Player, Position, SetupPosition, SetupStone};
+use super::models::{SavedAnnotation, SavedGame, SavedMarkup, SavedMove, SavedSetupStone};
+use super::sgf::SgfGame;
+
+/// 棋谱交换格式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameFormat {
+    Psq,       // Gomocup / Piskvork 棋谱
+    MoveList,  // 纯文本着法列表，如 `h8 i9 ...`
+    Json,      // 完整的对局记录
+    Sgf,       // SGF 棋谱（GM[4]），带注释和变化
+}
+
+impl GameFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameFormat::Psq => "psq",
+            GameFormat::MoveList => "move_list",
+            GameFormat::Json => "json",
+            GameFormat::Sgf => "sgf",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<GameFormat> {
+        match s {
+            "psq" => Some(GameFormat::Psq),
+            "move_list" | "txt" => Some(GameFormat::MoveList),
+            "json" => Some(GameFormat::Json),
+            "sgf" => Some(GameFormat::Sgf),
+            _ => None,
+        }
+    }
+
+    /// 根据文件扩展名判断格式
+    pub fn from_path(path: &Path) -> Option<GameFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::parse(&extension)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GameFormat::Psq => "psq",
+            GameFormat::MoveList => "txt",
+            GameFormat::Json => "json",
+            GameFormat::Sgf => "sgf",
+        }
+    }
+}
+
+/// 完整的对局记录（JSON 格式的内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub game: SavedGame,
+    #[serde(default)]
+    pub setup: Vec<SavedSetupStone>,
+    pub moves: Vec<SavedMove>,
+    /// 双方玩家的名称（SGF 的 PB/PW）
+    #[serde(default)]
+    pub black_player: Option<String>,
+    #[serde(default)]
+    pub white_player: Option<String>,
+    #[serde(default)]
+    pub annotations: Vec<SavedAnnotation>,
+    #[serde(default)]
+    pub markup: Vec<SavedMarkup>,
+}
+
+impl GameRecord {
+    /// 初始局面
+    pub fn setup_position(&self) -> SetupPosition {
+        SetupPosition {
+            stones: self
+                .setup
+                .iter()
+                .filter_map(|stone| {
+                    Some(SetupStone {
+                        position: Position {
+                            x: stone.position_x as usize,
+                            y: stone.position_y as usize,
+                        },
+                        player: Player::parse(&stone.player)?,
+                    })
+                })
+                .collect(),
+            to_move: Player::parse(&self.game.first_player).unwrap_or(Player::Black),
+        }
+    }
+
+    /// 带评注和标记的变化树，节点编号与落子记录的下标一致
+    pub fn tree(&self) -> Result<GameTree, String> {
+        let mut tree = move_tree(&self.moves)?;
+        attach_notes(&mut tree, &self.moves, &self.annotations, &self.markup)?;
+        Ok(tree)
+    }
+
+    /// 主线上按手数排序的落子位置
+    pub fn positions(&self) -> Vec<Position> {
+        let mut moves: Vec<&SavedMove> = self.moves.iter().filter(|m| m.is_main_line).collect();
+        moves.sort_by_key(|m| m.move_number);
+        moves
+            .iter()
+            .map(|m| Position {
+                x: m.position_x as usize,
+                y: m.position_y as usize,
+            })
+            .collect()
+    }
+
+    /// 判断是否为同一盘棋的特征：规则、先手、开始时间、结果、初始局面和全部着法（含变化）
+    pub fn fingerprint(&self) -> Result<String, String> {
+        let tree = self.tree()?;
+        let nodes = tree.preorder();
+        let order: HashMap<usize, usize> = nodes.iter().enumerate().map(|(index, node)| (node.id, index)).collect();
+        let moves: Vec<String> = nodes
+            .iter()
+            .map(|node| {
+                let parent = node.parent.and_then(|id| order.get(&id)).map_or(-1, |&index| index as i64);
+                format!("{}:{},{}", parent, node.position.x, node.position.y)
+            })
+            .collect();
+        let mut setup: Vec<String> = self
+            .setup
+            .iter()
+            .map(|stone| format!("{}:{},{}", stone.player, stone.position_x, stone.position_y))
+            .collect();
+        setup.sort();
+
+        Ok(format!(
+            "{}|{}|{}|{}|{}|{}",
+            self.game.rule_set,
+            self.game.first_player,
+            self.game.created_at,
+            self.game.status,
+            setup.join(";"),
+            moves.join(";")
+        ))
+    }
+}
+
+/// 由落子记录构建变化树，节点编号与记录的下标一致。
+/// 记录需按保存顺序排列（上一手在前）
+pub fn move_tree(moves: &[SavedMove]) -> Result<GameTree, String> {
+    let mut tree = GameTree::new();
+    let mut nodes = HashMap::new();
+
+    for (index, m) in moves.iter().enumerate() {
+        let parent = match m.parent_move_id {
+            Some(parent_id) => Some(
+                *nodes
+                    .get(&parent_id)
+                    .ok_or_else(|| format!("Move {} refers to a missing previous move", index + 1))?,
+            ),
+            None => None,
+        };
+        let position = Position {
+            x: m.position_x as usize,
+            y: m.position_y as usize,
+        };
+        let node = tree.add_child(parent, position)?;
+        if let Some(id) = m.id {
+            nodes.insert(id, node);
+        }
+    }
+
+    // 追加保存的变化可能排在主线之前，按记录的标记恢复主线
+    for (index, m) in moves.iter().enumerate() {
+        if m.is_main_line {
+            tree.promote(index)?;
+        }
+    }
+    Ok(tree)
+}
+
+/// 旧版本导出的 JSON 没有上一手，按手数把着法连成一条主线
+fn link_legacy_moves(moves: &mut [SavedMove]) {
+    if moves.iter().any(|m| m.parent_move_id.is_some()) {
+        return;
+    }
+    moves.sort_by_key(|m| m.move_number);
+    let mut next_id = moves.iter().filter_map(|m| m.id).max().unwrap_or(0);
+    let mut previous = None;
+    for m in moves.iter_mut() {
+        let id = *m.id.get_or_insert_with(|| {
+            next_id += 1;
+            next_id
+        });
+        m.parent_move_id = previous;
+        m.is_main_line = true;
+        previous = Some(id);
+    }
+}
+
+/// 把评注和标记挂到 `move_tree` 构建的变化树上
+pub fn attach_notes(
+    tree: &mut GameTree,
+    moves: &[SavedMove],
+    annotations: &[SavedAnnotation],
+    markup: &[SavedMarkup],
+) -> Result<(), String> {
+    let nodes: HashMap<i64, usize> = moves
+        .iter()
+        .enumerate()
+        .filter_map(|(index, m)| Some((m.id?, index)))
+        .collect();
+
+    for annotation in annotations {
+        let Some(&node) = nodes.get(&annotation.move_id) else {
+            continue;
+        };
+        let notes = tree.notes_mut(node)?;
+        notes.comment = annotation.comment.clone();
+        notes.annotation = annotation.glyph.as_deref().and_then(Annotation::parse);
+    }
+    for saved in markup {
+        let Some(&node) = nodes.get(&saved.move_id) else {
+            continue;
+        };
+        let Some(kind) = MarkupKind::parse(&saved.kind) else {
+            continue;
+        };
+        tree.notes_mut(node)?.set_markup(Markup {
+            position: Position {
+                x: saved.position_x as usize,
+                y: saved.position_y as usize,
+            },
+            kind,
+            label: saved.label.clone(),
+        })?;
+    }
+    Ok(())
+}
+
+/// 一手棋的评注和标记转换为保存用的记录
+pub fn saved_notes(move_id: i64, notes: &MoveNotes) -> (Option<SavedAnnotation>, Vec<SavedMarkup>) {
+    let annotation = (notes.comment.is_some() || notes.annotation.is_some()).then(|| SavedAnnotation {
+        move_id,
+        comment: notes.comment.clone(),
+        glyph: notes.annotation.map(|a| a.as_str().to_string()),
+    });
+    let markup = notes
+        .markup
+        .iter()
+        .map(|m| SavedMarkup {
+            id: None,
+            move_id,
+            position_x: m.position.x as i32,
+            position_y: m.position.y as i32,
+            kind: m.kind.as_str().to_string(),
+            label: m.label.clone(),
+        })
+        .collect();
+    (annotation, markup)
+}
+
+/// 从棋谱文件中读出的内容
+pub struct ImportedGame {
+    /// 只有 JSON 和 SGF 格式带有对局信息
+    pub record: Option<GameRecord>,
+    pub moves: Vec<Position>,
+}
+
+/// 导出对局记录
+pub fn export_game(record: &GameRecord, format: GameFormat) -> Result<String, String> {
+    if !record.setup.is_empty() && !matches!(format, GameFormat::Json | GameFormat::Sgf) {
+        return Err(format!("{} format cannot store a setup position", format.as_str()));
+    }
+
+    match format {
+        GameFormat::Psq => Ok(export_psq(record)),
+        GameFormat::MoveList => Ok(record
+            .positions()
+            .iter()
+            .map(|pos| pos.to_notation())
+            .collect::<Vec<_>>()
+            .join(" ")),
+        GameFormat::Json => serde_json::to_string_pretty(record).map_err(|e| e.to_string()),
+        GameFormat::Sgf => Ok(SgfGame::from_record(record)?.write()),
+    }
+}
+
+/// 解析棋谱文本
+pub fn import_game(text: &str, format: GameFormat) -> Result<ImportedGame, String> {
+    match format {
+        GameFormat::Psq => Ok(ImportedGame {
+            record: None,
+            moves: parse_psq(text)?,
+        }),
+        GameFormat::MoveList => Ok(ImportedGame {
+            record: None,
+            moves: parse_move_list(text)?,
+        }),
+        GameFormat::Json => {
+            let mut record: GameRecord = serde_json::from_str(text)
+                .map_err(|e| format!("Invalid JSON game record: {}", e))?;
+            link_legacy_moves(&mut record.moves);
+            let moves = record.positions();
+            Ok(ImportedGame {
+                record: Some(record),
+                moves,
+            })
+        }
+        GameFormat::Sgf => {
+            let sgf = SgfGame::parse(text)?;
+            Ok(ImportedGame {
+                record: Some(sgf.to_record()),
+                moves: sgf.main_line(),
+            })
+        }
+    }
+}
+
+/// psq 格式：首行为 `Piskvorky 宽x高, ...`，之后每行 `列,行,用时`（从 1 开始），以 `-1` 结束
+fn export_psq(record: &GameRecord) -> String {
+    let mut lines = vec!["Piskvorky 15x15, 11:11, 0".to_string()];
+    for pos in record.positions() {
+        lines.push(format!("{},{},0", pos.y + 1, pos.x + 1));
+    }
+    lines.push("-1".to_string());
+    lines.join("\n")
+}
+
+fn parse_psq(text: &str) -> Result<Vec<Position>, String> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    let header = lines.next().ok_or("Empty psq file")?;
+    let size = header
+        .split_whitespace()
+        .nth(1)
+        .and_then(|size| size.trim_end_matches(',').split('x').next())
+        .and_then(|width| width.parse::<usize>().ok())
+        .ok_or_else(|| format!("Invalid psq header: {}", header))?;
+    if size != 15 {
+        return Err(format!("Unsupported board size: {}", size));
+    }
+
+    let mut moves = Vec::new();
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        // 着法之后是引擎名等附加信息
+        if fields.len() < 2 || fields[0] == "-1" {
+            break;
+        }
+        let (Ok(column), Ok(row)) = (fields[0].parse::<usize>(), fields[1].parse::<usize>()) else {
+            break;
+        };
+        if column == 0 || row == 0 || column > 15 || row > 15 {
+            return Err(format!("Move out of bounds: {}", line));
+        }
+        moves.push(Position { x: row - 1, y: column - 1 });
+    }
+    Ok(moves)
+}
+
+/// 着法列表：以空白或逗号分隔，允许 `1.` 这样的序号
+fn parse_move_list(text: &str) -> Result<Vec<Position>, String> {
+    text.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter(|token| !token.is_empty() && !token.ends_with('.'))
+        .map(Position::from_notation)
+        .collect()
+}
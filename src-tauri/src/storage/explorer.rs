@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{canonical_symmetry, inverse_symmetry, transform_position, Board, OpeningState, Player, Position, RuleSet};
+use crate::game::canonical_hash;
+use super::database::Database;
+
+/// 开局浏览器中的一个后续着法
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplorerMove {
+    /// 换算到当前局面方向的落点
+    pub position: Position,
+    pub notation: String,
+    pub games: i64,
+    pub black_wins: i64,
+    pub white_wins: i64,
+    pub draws: i64,
+    /// 已结束对局中黑胜、和棋、白胜所占的百分比
+    pub black_win_rate: f64,
+    pub draw_rate: f64,
+    pub white_win_rate: f64,
+    /// 双方在对局时的平均等级分（未计分的对局不计）
+    pub average_black_rating: Option<f64>,
+    pub average_white_rating: Option<f64>,
+}
+
+/// 当前局面在棋谱库中的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplorerPosition {
+    /// 出现过该局面的对局数（包括在此结束的对局）
+    pub games: i64,
+    /// 按对局数从多到少排列
+    pub moves: Vec<ExplorerMove>,
+}
+
+#[derive(Default)]
+struct RatingSum {
+    total: f64,
+    count: i64,
+}
+
+impl RatingSum {
+    fn add(&mut self, total: f64, count: i64) {
+        self.total += total;
+        self.count += count;
+    }
+
+    fn average(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.total / self.count as f64)
+    }
+}
+
+fn rate(count: i64, finished: i64) -> f64 {
+    if finished == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / finished as f64
+    }
+}
+
+/// 统计保存的对局在该局面之后的下一手。
+/// 对局中的局面可能是当前局面旋转或翻转后的样子，索引中的落点是规范方向，换算到当前局面的方向，
+/// 在当前局面下对称等价的落点合并统计
+pub fn explore(db: &Database, board: &Board, to_move: Player, rule_set: RuleSet) -> Result<ExplorerPosition, String> {
+    let continuations = db.position_continuations(canonical_hash(board, to_move), rule_set.as_str())
+        .map_err(|e| format!("Failed to search positions: {}", e))?;
+    let inverse = inverse_symmetry(canonical_symmetry(board));
+
+    let mut games = 0;
+    let mut moves: Vec<(ExplorerMove, RatingSum, RatingSum)> = Vec::new();
+    for continuation in continuations {
+        games += continuation.games;
+        let (Some(x), Some(y)) = (continuation.next_x, continuation.next_y) else {
+            continue;
+        };
+        let position = transform_position(&Position { x: x as usize, y: y as usize }, inverse);
+        let index = match moves
+            .iter()
+            .position(|(entry, _, _)| OpeningState::equivalent_moves(board, &entry.position, &position))
+        {
+            Some(index) => index,
+            None => {
+                moves.push((
+                    ExplorerMove {
+                        notation: position.to_notation(),
+                        position,
+                        games: 0,
+                        black_wins: 0,
+                        white_wins: 0,
+                        draws: 0,
+                        black_win_rate: 0.0,
+                        draw_rate: 0.0,
+                        white_win_rate: 0.0,
+                        average_black_rating: None,
+                        average_white_rating: None,
+                    },
+                    RatingSum::default(),
+                    RatingSum::default(),
+                ));
+                moves.len() - 1
+            }
+        };
+
+        let (entry, black_rating, white_rating) = &mut moves[index];
+        entry.games += continuation.games;
+        entry.black_wins += continuation.black_wins;
+        entry.white_wins += continuation.white_wins;
+        entry.draws += continuation.draws;
+        black_rating.add(continuation.black_rating_total, continuation.black_rated_games);
+        white_rating.add(continuation.white_rating_total, continuation.white_rated_games);
+    }
+
+    let mut moves: Vec<ExplorerMove> = moves
+        .into_iter()
+        .map(|(mut entry, black_rating, white_rating)| {
+            let finished = entry.black_wins + entry.white_wins + entry.draws;
+            entry.black_win_rate = rate(entry.black_wins, finished);
+            entry.draw_rate = rate(entry.draws, finished);
+            entry.white_win_rate = rate(entry.white_wins, finished);
+            entry.average_black_rating = black_rating.average();
+            entry.average_white_rating = white_rating.average();
+            entry
+        })
+        .collect();
+    moves.sort_by(|a, b| b.games.cmp(&a.games).then_with(|| a.notation.cmp(&b.notation)));
+
+    Ok(ExplorerPosition { games, moves })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{indexed_positions, Rating, SetupPosition};
+    use crate::storage::test_support::{game, player};
+    use crate::storage::SavedGame;
+
+    fn save(db: &Database, status: &str, players: (Option<i64>, Option<i64>), moves: &[Position]) -> i64 {
+        let game_id = db
+            .save_game(&SavedGame {
+                total_moves: moves.len() as i32,
+                ..game(status, players.0, players.1)
+            })
+            .unwrap();
+        let positions = indexed_positions(SetupPosition::default(), RuleSet::Freestyle, moves, 0);
+        db.save_position_index(game_id, 0, &positions).unwrap();
+        game_id
+    }
+
+    #[test]
+    fn explores_rotated_games_with_ratings_at_game_time() {
+        let db = Database::new_in_memory().unwrap();
+        let alice = player(&db, "Alice");
+        let bob = player(&db, "Bob");
+        let line: Vec<Position> = [(7, 7), (8, 9), (6, 8)].iter().map(|&(x, y)| Position { x, y }).collect();
+        let turned: Vec<Position> = line.iter().map(|pos| transform_position(pos, 5)).collect();
+
+        let rated = save(&db, "black_win", (Some(alice), Some(bob)), &line);
+        db.mark_rated_game(rated, "Alice vs Bob").unwrap();
+        assert!(db.rate_game(rated).unwrap());
+        save(&db, "white_win", (None, None), &turned);
+        save(&db, "draw", (None, None), &line[..2]);
+
+        let mut board = Board::new();
+        board.set(7, 7, Player::Black).unwrap();
+        board.set(8, 9, Player::White).unwrap();
+        let explored = explore(&db, &board, Player::Black, RuleSet::Freestyle).unwrap();
+
+        assert_eq!(explored.games, 3);
+        assert_eq!(explored.moves.len(), 1);
+        let next = &explored.moves[0];
+        assert_eq!(next.position, line[2]);
+        assert_eq!((next.games, next.black_wins, next.white_wins, next.draws), (2, 1, 1, 0));
+        // 计分后的等级分已经变化，统计使用对局时的等级分
+        let initial = Rating::default().rating;
+        assert_ne!(db.get_player(alice).unwrap().unwrap().rating, initial);
+        assert_eq!(next.average_black_rating, Some(initial));
+        assert_eq!(next.average_white_rating, Some(initial));
+    }
+}
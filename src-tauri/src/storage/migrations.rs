@@ -0,0 +1,752 @@
+use std::path::Path;
+
+use chrono::Utc;
+use rusqlite::{ffi, Connection, Error, Result};
+
+/// 一次数据库结构升级
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// 按版本排列的全部升级，版本号即 `PRAGMA user_version`
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema",
+        apply: baseline,
+    },
+    Migration {
+        version: 2,
+        description: "autosave flag on games",
+        apply: autosave_flag,
+    },
+    Migration {
+        version: 3,
+        description: "indexes for game search",
+        apply: search_indexes,
+    },
+    Migration {
+        version: 4,
+        description: "player profiles",
+        apply: players,
+    },
+    Migration {
+        version: 5,
+        description: "player ratings",
+        apply: ratings,
+    },
+    Migration {
+        version: 6,
+        description: "tags, favorites and collections",
+        apply: organization,
+    },
+    Migration {
+        version: 7,
+        description: "position index",
+        apply: position_index,
+    },
+    Migration {
+        version: 8,
+        description: "next move in position index",
+        apply: position_index_next_move,
+    },
+    Migration {
+        version: 9,
+        description: "settings store",
+        apply: settings,
+    },
+    Migration {
+        version: 10,
+        description: "opening state",
+        apply: opening_state,
+    },
+    Migration {
+        version: 11,
+        description: "current move",
+        apply: current_move,
+    },
+];
+
+/// 当前程序使用的数据库结构版本
+pub const SCHEMA_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+pub fn schema_version(conn: &Connection) -> Result<i32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// 把数据库升级到当前版本
+pub fn migrate(conn: &mut Connection, db_path: Option<&Path>) -> Result<()> {
+    let current = schema_version(conn)?;
+    if current > SCHEMA_VERSION {
+        return Err(Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_ERROR),
+            Some(format!(
+                "Database schema version {} is newer than supported version {}",
+                current, SCHEMA_VERSION
+            )),
+        ));
+    }
+
+    if current < SCHEMA_VERSION {
+        if let Some(path) = db_path {
+            backup_before_migration(conn, path, current)?;
+        }
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        eprintln!("Migrated database to version {}: {}", migration.version, migration.description);
+    }
+
+    // 删除对局时级联删除落子等记录（不依赖 SQLite 的编译选项）
+    conn.pragma_update(None, "foreign_keys", true)?;
+    Ok(())
+}
+
+/// 升级前把数据库复制为 `<文件名>.v<旧版本>.bak`（新建的空数据库不需要备份）
+fn backup_before_migration(conn: &Connection, path: &Path, version: i32) -> Result<()> {
+    let has_tables: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_tables {
+        return Ok(());
+    }
+
+    let mut backup_name = path.file_name().unwrap_or_default().to_os_string();
+    backup_name.push(format!(".v{}.bak", version));
+    let backup_path = path.with_file_name(backup_name);
+    if backup_path.exists() {
+        // 同一版本的备份只保留第一次的
+        return Ok(());
+    }
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy()])?;
+    Ok(())
+}
+
+/// 版本 1：引入版本号之前的全部表结构。
+/// 未标记版本的数据库可能处于其间的任一状态，所以这里的每一步都可以重复执行
+fn baseline(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS games (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            mode TEXT NOT NULL,
+            difficulty TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            winner TEXT,
+            total_moves INTEGER DEFAULT 0,
+            rule_set TEXT NOT NULL DEFAULT 'freestyle',
+            end_reason TEXT,
+            black_captures INTEGER NOT NULL DEFAULT 0,
+            white_captures INTEGER NOT NULL DEFAULT 0,
+            first_player TEXT NOT NULL DEFAULT 'black'
+        )",
+        [],
+    )?;
+
+    // 旧版本数据库没有这些列
+    ensure_column(conn, "games", "rule_set", "TEXT NOT NULL DEFAULT 'freestyle'")?;
+    ensure_column(conn, "games", "end_reason", "TEXT")?;
+    ensure_column(conn, "games", "black_captures", "INTEGER NOT NULL DEFAULT 0")?;
+    ensure_column(conn, "games", "white_captures", "INTEGER NOT NULL DEFAULT 0")?;
+    ensure_column(conn, "games", "first_player", "TEXT NOT NULL DEFAULT 'black'")?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS moves (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            game_id INTEGER NOT NULL,
+            move_number INTEGER NOT NULL,
+            turn_number INTEGER,
+            player TEXT NOT NULL,
+            position_x INTEGER NOT NULL,
+            position_y INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 旧版本每回合只有一子，回合序号与手数相同
+    ensure_column(conn, "moves", "turn_number", "INTEGER")?;
+    // 变化树：旧版本只有一条主线
+    ensure_column(conn, "moves", "parent_move_id", "INTEGER")?;
+    ensure_column(conn, "moves", "is_main_line", "INTEGER NOT NULL DEFAULT 1")?;
+    conn.execute(
+        "UPDATE moves SET turn_number = move_number WHERE turn_number IS NULL",
+        [],
+    )?;
+    // 旧版本的对局整盘都没有上一手，按保存顺序把每一手接到同一盘棋的前一条记录上
+    conn.execute(
+        "UPDATE moves SET parent_move_id = (
+            SELECT MAX(prev.id) FROM moves prev
+            WHERE prev.game_id = moves.game_id AND prev.id < moves.id
+        )
+        WHERE game_id IN (
+            SELECT game_id FROM moves GROUP BY game_id HAVING COUNT(parent_move_id) = 0
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS move_offers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            game_id INTEGER NOT NULL,
+            move_number INTEGER NOT NULL,
+            position_x INTEGER NOT NULL,
+            position_y INTEGER NOT NULL,
+            chosen INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS setup_stones (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            game_id INTEGER NOT NULL,
+            player TEXT NOT NULL,
+            position_x INTEGER NOT NULL,
+            position_y INTEGER NOT NULL,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // 评注和棋盘标记挂在具体的一手棋上
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS move_annotations (
+            move_id INTEGER PRIMARY KEY,
+            comment TEXT,
+            glyph TEXT,
+            FOREIGN KEY (move_id) REFERENCES moves(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS move_markup (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            move_id INTEGER NOT NULL,
+            position_x INTEGER NOT NULL,
+            position_y INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            label TEXT,
+            FOREIGN KEY (move_id) REFERENCES moves(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS game_clocks (
+            game_id INTEGER PRIMARY KEY,
+            time_control TEXT NOT NULL,
+            black_remaining_ms INTEGER NOT NULL,
+            black_periods INTEGER NOT NULL DEFAULT 0,
+            white_remaining_ms INTEGER NOT NULL,
+            white_periods INTEGER NOT NULL DEFAULT 0,
+            flagged TEXT,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_games_status ON games(status)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_moves_game_id ON moves(game_id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_move_offers_game_id ON move_offers(game_id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_setup_stones_game_id ON setup_stones(game_id)",
+        [],
+    )?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_move_markup_move_id ON move_markup(move_id)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// 为旧数据库补充缺少的列
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))?
+        .exists([column])?;
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// 版本 2：自动保存的对局单独标记，不出现在存档列表中
+fn autosave_flag(conn: &Connection) -> Result<()> {
+    conn.execute("ALTER TABLE games ADD COLUMN is_autosave INTEGER NOT NULL DEFAULT 0", [])?;
+    Ok(())
+}
+
+fn search_indexes(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_games_list ON games(is_autosave, updated_at);
+         CREATE INDEX IF NOT EXISTS idx_games_created_at ON games(created_at);",
+    )
+}
+
+fn players(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE players (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            difficulty TEXT,
+            created_at INTEGER NOT NULL
+        );
+        CREATE UNIQUE INDEX idx_players_ai_difficulty ON players(difficulty) WHERE kind = 'ai';
+        ALTER TABLE games ADD COLUMN black_player_id INTEGER REFERENCES players(id) ON DELETE SET NULL;
+        ALTER TABLE games ADD COLUMN white_player_id INTEGER REFERENCES players(id) ON DELETE SET NULL;
+        CREATE INDEX idx_games_black_player ON games(black_player_id);
+        CREATE INDEX idx_games_white_player ON games(white_player_id);",
+    )?;
+
+    // 每个难度一个 AI 档案
+    let now = Utc::now().timestamp();
+    for (difficulty, name) in [("easy", "AI (Easy)"), ("medium", "AI (Medium)"), ("hard", "AI (Hard)")] {
+        conn.execute(
+            "INSERT INTO players (name, kind, difficulty, created_at) VALUES (?1, 'ai', ?2, ?3)",
+            (name, difficulty, now),
+        )?;
+    }
+    Ok(())
+}
+
+fn ratings(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE players ADD COLUMN rating REAL NOT NULL DEFAULT 1500;
+        ALTER TABLE players ADD COLUMN rating_deviation REAL NOT NULL DEFAULT 350;
+        ALTER TABLE players ADD COLUMN volatility REAL NOT NULL DEFAULT 0.06;
+        ALTER TABLE games ADD COLUMN is_rated INTEGER NOT NULL DEFAULT 0;
+        CREATE TABLE rating_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            player_id INTEGER NOT NULL,
+            game_id INTEGER NOT NULL,
+            rating REAL NOT NULL,
+            rating_deviation REAL NOT NULL,
+            volatility REAL NOT NULL,
+            rating_change REAL NOT NULL,
+            created_at INTEGER NOT NULL,
+            UNIQUE (player_id, game_id),
+            FOREIGN KEY (player_id) REFERENCES players(id) ON DELETE CASCADE,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_rating_history_game_id ON rating_history(game_id);",
+    )
+}
+
+fn organization(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE games ADD COLUMN is_favorite INTEGER NOT NULL DEFAULT 0;
+        CREATE TABLE tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE
+        );
+        CREATE TABLE game_tags (
+            game_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (game_id, tag_id),
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_game_tags_tag_id ON game_tags(tag_id);
+        CREATE TABLE collections (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            description TEXT,
+            created_at INTEGER NOT NULL
+        );
+        CREATE TABLE collection_games (
+            collection_id INTEGER NOT NULL,
+            game_id INTEGER NOT NULL,
+            added_at INTEGER NOT NULL,
+            PRIMARY KEY (collection_id, game_id),
+            FOREIGN KEY (collection_id) REFERENCES collections(id) ON DELETE CASCADE,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_collection_games_game_id ON collection_games(game_id);",
+    )
+}
+
+fn position_index(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE position_index (
+            game_id INTEGER NOT NULL,
+            move_number INTEGER NOT NULL,
+            position_hash INTEGER NOT NULL,
+            PRIMARY KEY (game_id, move_number),
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_position_index_hash ON position_index(position_hash);",
+    )
+}
+
+fn position_index_next_move(conn: &Connection) -> Result<()> {
+    // 旧的索引没有下一手，清空后由 backfill_position_index 重建
+    conn.execute_batch(
+        "DELETE FROM position_index;
+        ALTER TABLE position_index ADD COLUMN next_x INTEGER;
+        ALTER TABLE position_index ADD COLUMN next_y INTEGER;",
+    )
+}
+
+fn settings(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 版本 10：保存开局规则和开局进行到的阶段，加载时继续开局
+fn opening_state(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE game_openings (
+            game_id INTEGER PRIMARY KEY,
+            rule TEXT NOT NULL,
+            state TEXT NOT NULL,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// 版本 11：记录保存时所在的节点，加载时回到该节点
+fn current_move(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "ALTER TABLE games ADD COLUMN current_move_id INTEGER REFERENCES moves(id) ON DELETE SET NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::storage::{exchange, Database};
+
+    /// 最初版本的表结构（只有对局和落子）
+    const ORIGINAL_SCHEMA: &str = "
+        CREATE TABLE games (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            mode TEXT NOT NULL,
+            difficulty TEXT,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            winner TEXT,
+            total_moves INTEGER DEFAULT 0
+        );
+        CREATE TABLE moves (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            game_id INTEGER NOT NULL,
+            move_number INTEGER NOT NULL,
+            player TEXT NOT NULL,
+            position_x INTEGER NOT NULL,
+            position_y INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_games_status ON games(status);
+        CREATE INDEX idx_moves_game_id ON moves(game_id);";
+
+    /// 开始用 ensure_column 补列之前的表结构：多了五手两打和棋钟，games / moves 仍是最初的列
+    const PRE_ENSURE_COLUMN_SCHEMA: &str = "
+        CREATE TABLE move_offers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            game_id INTEGER NOT NULL,
+            move_number INTEGER NOT NULL,
+            position_x INTEGER NOT NULL,
+            position_y INTEGER NOT NULL,
+            chosen INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        );
+        CREATE TABLE game_clocks (
+            game_id INTEGER PRIMARY KEY,
+            time_control TEXT NOT NULL,
+            black_remaining_ms INTEGER NOT NULL,
+            black_periods INTEGER NOT NULL DEFAULT 0,
+            white_remaining_ms INTEGER NOT NULL,
+            white_periods INTEGER NOT NULL DEFAULT 0,
+            flagged TEXT,
+            FOREIGN KEY (game_id) REFERENCES games(id) ON DELETE CASCADE
+        );
+        CREATE INDEX idx_move_offers_game_id ON move_offers(game_id);";
+
+    /// 各个版本都有的列中的数据
+    const ORIGINAL_ROWS: &str = "
+        INSERT INTO games (id, name, mode, difficulty, created_at, updated_at, status, winner, total_moves)
+            VALUES (1, 'Old game', 'pve', 'hard', 100, 200, 'black_win', 'black', 5),
+                   (2, 'Unfinished', 'pvp', NULL, 300, 400, 'in_progress', NULL, 2);
+        INSERT INTO moves (game_id, move_number, player, position_x, position_y, timestamp)
+            VALUES (1, 1, 'black', 7, 7, 100), (1, 2, 'white', 7, 8, 101), (1, 3, 'black', 8, 8, 102),
+                   (1, 4, 'white', 6, 6, 103), (1, 5, 'black', 9, 9, 104),
+                   (2, 1, 'black', 7, 7, 300), (2, 2, 'white', 8, 7, 301);";
+
+    /// 测试用的数据库文件，结束时连同备份一起删除
+    struct TempDb {
+        path: PathBuf,
+    }
+
+    impl TempDb {
+        fn new(name: &str) -> TempDb {
+            let dir = std::env::temp_dir()
+                .join(format!("gomoku-migrations-{}-{}", std::process::id(), name));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDb { path: dir.join("games.db") }
+        }
+
+        fn open(&self) -> Connection {
+            Connection::open(&self.path).unwrap()
+        }
+
+        fn backup_path(&self, version: i32) -> PathBuf {
+            self.path.with_file_name(format!("games.db.v{}.bak", version))
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            if let Some(dir) = self.path.parent() {
+                let _ = std::fs::remove_dir_all(dir);
+            }
+        }
+    }
+
+    /// 依次执行到 `version` 为止的升级，模拟当时的程序留下的数据库
+    fn migrate_to(conn: &mut Connection, version: i32) {
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+            let tx = conn.transaction().unwrap();
+            (migration.apply)(&tx).unwrap();
+            tx.pragma_update(None, "user_version", migration.version).unwrap();
+            tx.commit().unwrap();
+        }
+    }
+
+    /// 在 `version` 版本的数据库中写入该版本新增的表和列的数据
+    fn insert_version_rows(conn: &Connection, version: i32) {
+        if version >= 2 {
+            conn.execute_batch(
+                "INSERT INTO games (id, name, mode, created_at, updated_at, status, is_autosave)
+                     VALUES (3, 'Autosave', 'pvp', 500, 600, 'in_progress', 1);",
+            )
+            .unwrap();
+        }
+        if version >= 4 {
+            conn.execute_batch(
+                "INSERT INTO players (id, name, kind, created_at) VALUES (100, 'Alice', 'human', 100);
+                 UPDATE games SET black_player_id = 100,
+                     white_player_id = (SELECT id FROM players WHERE kind = 'ai' AND difficulty = 'hard')
+                     WHERE id = 1;",
+            )
+            .unwrap();
+        }
+        if version >= 5 {
+            conn.execute_batch(
+                "UPDATE players SET rating = 1562.5 WHERE id = 100;
+                 UPDATE games SET is_rated = 1 WHERE id = 1;
+                 INSERT INTO rating_history (player_id, game_id, rating, rating_deviation, volatility, rating_change, created_at)
+                     VALUES (100, 1, 1562.5, 290, 0.06, 62.5, 200);",
+            )
+            .unwrap();
+        }
+        if version >= 6 {
+            conn.execute_batch(
+                "UPDATE games SET is_favorite = 1 WHERE id = 2;
+                 INSERT INTO tags (id, name) VALUES (1, 'Opening');
+                 INSERT INTO game_tags (game_id, tag_id) VALUES (1, 1);
+                 INSERT INTO collections (id, name, created_at) VALUES (1, 'Study', 100);
+                 INSERT INTO collection_games (collection_id, game_id, added_at) VALUES (1, 2, 100);",
+            )
+            .unwrap();
+        }
+        if version >= 7 {
+            conn.execute_batch(
+                "INSERT INTO position_index (game_id, move_number, position_hash) VALUES (1, 1, 42), (1, 2, 43);",
+            )
+            .unwrap();
+        }
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    /// 升级 `build` 生成的 `version` 版本数据库，检查版本号、备份和原有数据
+    fn check_upgrade(name: &str, version: i32, build: impl FnOnce(&mut Connection)) {
+        let db = TempDb::new(name);
+        {
+            let mut conn = db.open();
+            build(&mut conn);
+            assert_eq!(schema_version(&conn).unwrap(), version);
+        }
+
+        let mut conn = db.open();
+        migrate(&mut conn, Some(&db.path)).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+
+        // 升级前的数据库原样备份
+        let backup = Connection::open(db.backup_path(version)).unwrap();
+        assert_eq!(schema_version(&backup).unwrap(), version);
+        assert_eq!(count(&backup, "games"), count(&conn, "games"));
+        assert_eq!(count(&backup, "moves"), count(&conn, "moves"));
+
+        let games: Vec<(i64, String, String, Option<String>)> = conn
+            .prepare("SELECT id, name, status, winner FROM games WHERE id <= 2 ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(
+            games,
+            vec![
+                (1, "Old game".to_string(), "black_win".to_string(), Some("black".to_string())),
+                (2, "Unfinished".to_string(), "in_progress".to_string(), None),
+            ]
+        );
+
+        // 旧数据库中的对局可以按当前结构读出
+        drop(conn);
+        let database = Database::new(db.path.clone()).unwrap();
+        let listed = database.list_games().unwrap();
+        assert_eq!(listed.iter().filter(|game| game.id <= Some(2)).count(), 2);
+        let moves = database.get_moves(1).unwrap();
+        let positions: Vec<(i32, i32, i32, i32)> = moves
+            .iter()
+            .map(|m| (m.move_number, m.turn_number, m.position_x, m.position_y))
+            .collect();
+        assert_eq!(positions, vec![(1, 1, 7, 7), (2, 2, 7, 8), (3, 3, 8, 8), (4, 4, 6, 6), (5, 5, 9, 9)]);
+        assert!(moves.iter().all(|m| m.is_main_line));
+        // 旧记录按顺序连成一条主线，原地保存时不会被当成变化
+        let parents: Vec<Option<i64>> = moves.iter().map(|m| m.parent_move_id).collect();
+        let previous: Vec<Option<i64>> = std::iter::once(None)
+            .chain(moves.iter().map(|m| m.id))
+            .take(moves.len())
+            .collect();
+        assert_eq!(parents, previous);
+        assert_eq!(exchange::move_tree(&moves).unwrap().main_line().len(), 5);
+        assert_eq!(database.get_moves(2).unwrap().len(), 2);
+
+        if version >= 2 {
+            let autosave = database.unfinished_autosave().unwrap().unwrap();
+            assert_eq!(autosave.id, Some(3));
+        }
+        if version >= 4 {
+            let game = database.get_game(1).unwrap().unwrap();
+            assert_eq!(game.black_player_id, Some(100));
+            assert_eq!(game.white_player_id, database.ai_player_id("hard").unwrap());
+        }
+        if version >= 5 {
+            let history = database.rating_history(100).unwrap();
+            assert_eq!(history.len(), 1);
+            assert_eq!(database.get_player(100).unwrap().unwrap().rating, 1562.5);
+        }
+        if version >= 6 {
+            assert!(database.get_game(2).unwrap().unwrap().is_favorite);
+            assert_eq!(database.get_tags(1).unwrap(), vec!["Opening".to_string()]);
+            assert_eq!(database.list_collections().unwrap()[0].game_count, 1);
+        }
+        if version >= 8 {
+            assert_eq!(database.find_position(42, "freestyle").unwrap().len(), 1);
+        }
+        if version == 7 {
+            // 局面索引在版本 8 加入下一手时清空，等待重建
+            assert!(database.position_continuations(42, "freestyle").unwrap().is_empty());
+            assert!(database.unindexed_games().unwrap().contains(&1));
+        }
+    }
+
+    #[test]
+    fn upgrades_original_schema() {
+        check_upgrade("original", 0, |conn| {
+            conn.execute_batch(ORIGINAL_SCHEMA).unwrap();
+            conn.execute_batch(ORIGINAL_ROWS).unwrap();
+        });
+    }
+
+    #[test]
+    fn upgrades_pre_ensure_column_schema() {
+        check_upgrade("pre-ensure-column", 0, |conn| {
+            conn.execute_batch(ORIGINAL_SCHEMA).unwrap();
+            conn.execute_batch(PRE_ENSURE_COLUMN_SCHEMA).unwrap();
+            conn.execute_batch(ORIGINAL_ROWS).unwrap();
+            conn.execute_batch(
+                "INSERT INTO move_offers (game_id, move_number, position_x, position_y, chosen)
+                     VALUES (1, 5, 9, 9, 1), (1, 5, 5, 5, 0);
+                 INSERT INTO game_clocks (game_id, time_control, black_remaining_ms, white_remaining_ms)
+                     VALUES (2, '{\"SuddenDeath\":{\"main_ms\":600000}}', 500000, 550000);",
+            )
+            .unwrap();
+        });
+    }
+
+    #[test]
+    fn upgrades_every_schema_version() {
+        for version in 1..SCHEMA_VERSION {
+            check_upgrade(&format!("v{}", version), version, |conn| {
+                conn.execute_batch(ORIGINAL_SCHEMA).unwrap();
+                conn.execute_batch(ORIGINAL_ROWS).unwrap();
+                migrate_to(conn, version);
+                insert_version_rows(conn, version);
+            });
+        }
+    }
+
+    #[test]
+    fn current_database_is_left_alone() {
+        let db = TempDb::new("current");
+        Database::new(db.path.clone()).unwrap();
+        let mut conn = db.open();
+        migrate(&mut conn, Some(&db.path)).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        // 新建的空数据库不备份，已是最新版本时也不备份
+        assert!(!db.backup_path(0).exists());
+        assert!(!db.backup_path(SCHEMA_VERSION).exists());
+    }
+
+    #[test]
+    fn rejects_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1).unwrap();
+        assert!(migrate(&mut conn, None).is_err());
+    }
+}
@@ -1,5 +1,18 @@
 pub mod database;
+pub mod exchange;
+pub mod explorer;
+pub mod migrations;
 pub mod models;
+pub mod query;
+pub mod settings;
+pub mod sgf;
+#[cfg(test)]
+mod test_support;
 
 pub use database::Database;
-pub use models::*;
\ No newline at end of file
+pub use exchange::{GameFormat, GameRecord, ImportedGame};
+pub use explorer::{ExplorerMove, ExplorerPosition};
+pub use models::*;
+pub use query::{GamePage, GameQuery, GameSort};
+pub use settings::AppSettings;
+pub use sgf::{SgfGame, SgfGameInfo, SgfMove, SgfNode};
\ No newline at end of file
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::game::MoveNotes;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SavedGame {
     pub id: Option<i64>,
@@ -11,6 +13,133 @@ pub struct SavedGame {
     pub status: String,      // "in_progress", "black_win", "white_win", "draw"
     pub winner: Option<String>,
     pub total_moves: i32,
+    pub rule_set: String,    // "freestyle" or "renju"
+    pub end_reason: Option<String>,
+    pub black_captures: i32, // 提子规则下双方提走的对数
+    pub white_captures: i32,
+    pub first_player: String, // 初始局面后先走的一方
+    #[serde(default)]
+    pub black_player_id: Option<i64>, // 执黑/执白的玩家档案
+    #[serde(default)]
+    pub white_player_id: Option<i64>,
+    #[serde(default)]
+    pub is_rated: bool,               // 计入等级分的对局
+    #[serde(default)]
+    pub is_favorite: bool,
+}
+
+/// 出现过某局面的对局，`move_number` 为第一次出现时已下的手数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionMatch {
+    pub game: SavedGame,
+    pub move_number: i32,
+}
+
+/// 保存的对局在某局面之后的一种下一手（规范方向，`None` 为对局在此结束）及其统计。
+/// 等级分为双方在该盘计分前的等级分之和及计入的对局数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionContinuation {
+    pub next_x: Option<i32>,
+    pub next_y: Option<i32>,
+    pub games: i64,
+    pub black_wins: i64,
+    pub white_wins: i64,
+    pub draws: i64,
+    pub black_rating_total: f64,
+    pub black_rated_games: i64,
+    pub white_rating_total: f64,
+    pub white_rated_games: i64,
+}
+
+/// 标签及使用它的对局数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTag {
+    pub id: i64,
+    pub name: String,
+    pub game_count: i64,
+}
+
+/// 棋谱合集及其中的对局数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedCollection {
+    pub id: Option<i64>,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: i64,
+    #[serde(default)]
+    pub game_count: i64,
+}
+
+/// 玩家档案（人类玩家或各难度的 AI）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPlayer {
+    pub id: Option<i64>,
+    pub name: String,
+    pub kind: String,               // "human" or "ai"
+    pub difficulty: Option<String>, // AI 档案的难度
+    pub created_at: i64,
+    pub rating: f64,                // Glicko-2 等级分
+    pub rating_deviation: f64,
+    pub volatility: f64,
+}
+
+/// 一盘计分对局后玩家等级分的变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRatingChange {
+    pub id: Option<i64>,
+    pub player_id: i64,
+    pub game_id: i64,
+    pub rating: f64,          // 这盘之后的等级分
+    pub rating_deviation: f64,
+    pub volatility: f64,
+    pub rating_change: f64,
+    pub created_at: i64,
+}
+
+/// 胜负和统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordCounts {
+    pub games: i64,
+    pub wins: i64,
+    pub losses: i64,
+    pub draws: i64,
+}
+
+/// 对某一对手的战绩
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpponentRecord {
+    pub opponent_id: Option<i64>, // 未记录对手时为空
+    pub opponent_name: Option<String>,
+    pub record: RecordCounts,
+}
+
+/// 对某一 AI 难度的战绩
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifficultyRecord {
+    pub difficulty: String,
+    pub record: RecordCounts,
+}
+
+/// 连胜或连败
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Streak {
+    pub outcome: String, // "win"、"loss"、"draw"
+    pub length: i64,
+}
+
+/// 玩家统计（只计已结束的对局）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub player: SavedPlayer,
+    pub overall: RecordCounts,
+    pub as_black: RecordCounts,
+    pub as_white: RecordCounts,
+    pub by_opponent: Vec<OpponentRecord>,
+    pub by_difficulty: Vec<DifficultyRecord>,
+    pub average_moves: f64,
+    pub current_streak: Option<Streak>,
+    pub longest_win_streak: i64,
+    pub longest_loss_streak: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,8 +147,86 @@ pub struct SavedMove {
     pub id: Option<i64>,
     pub game_id: i64,
     pub move_number: i32,
+    pub turn_number: i32,    // 回合序号（六子棋一回合两子）
     pub player: String,      // "black" or "white"
     pub position_x: i32,
     pub position_y: i32,
     pub timestamp: i64,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub parent_move_id: Option<i64>, // 上一手的记录，第一手为空
+    #[serde(default = "default_main_line")]
+    pub is_main_line: bool,          // 是否在主线上（否则为变化）
+}
+
+fn default_main_line() -> bool {
+    true
+}
+
+/// 已写入数据库的树节点：对应的落子记录，以及写入时的主线标记和评注，
+/// 再次保存时只更新有变化的部分
+#[derive(Debug, Clone)]
+pub struct SavedNode {
+    pub move_id: i64,
+    pub is_main_line: bool,
+    pub notes: MoveNotes,
+}
+
+/// 一手棋的文字评注和评注符号
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedAnnotation {
+    pub move_id: i64,
+    pub comment: Option<String>,
+    pub glyph: Option<String>, // "!"、"?"、"!!"、"??"、"!?"、"?!"
+}
+
+/// 一手棋上的棋盘标记
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedMarkup {
+    pub id: Option<i64>,
+    pub move_id: i64,
+    pub position_x: i32,
+    pub position_y: i32,
+    pub kind: String,          // "label"、"triangle"、"circle"、"square"、"cross"、"highlight"
+    pub label: Option<String>,
+}
+
+/// 初始局面中的棋子（让子或自定义摆子）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSetupStone {
+    pub id: Option<i64>,
+    pub game_id: i64,
+    pub player: String,      // "black" or "white"
+    pub position_x: i32,
+    pub position_y: i32,
+}
+
+/// 五手打点记录（被选中与未被选中的候选）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedOffer {
+    pub id: Option<i64>,
+    pub game_id: i64,
+    pub move_number: i32,
+    pub position_x: i32,
+    pub position_y: i32,
+    pub chosen: bool,
+}
+
+/// 棋钟记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedClock {
+    pub game_id: i64,
+    pub time_control: String, // TimeControl 的 JSON
+    pub black_remaining_ms: i64,
+    pub black_periods: i32,
+    pub white_remaining_ms: i64,
+    pub white_periods: i32,
+    pub flagged: Option<String>, // 超时方 "black" / "white"
+}
+
+/// 开局记录（没有开局规则的游戏没有记录）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedOpening {
+    pub game_id: i64,
+    pub rule: String,
+    pub state: String, // OpeningState 的 JSON
+}
@@ -0,0 +1,127 @@
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+use super::models::SavedGame;
+
+/// 存档列表的排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameSort {
+    #[default]
+    UpdatedAt,
+    CreatedAt,
+    Name,
+    TotalMoves,
+}
+
+impl GameSort {
+    fn column(&self) -> &'static str {
+        match self {
+            GameSort::UpdatedAt => "updated_at",
+            GameSort::CreatedAt => "created_at",
+            GameSort::Name => "name COLLATE NOCASE",
+            GameSort::TotalMoves => "total_moves",
+        }
+    }
+}
+
+/// 存档查询条件，未设置的条件不参与筛选
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameQuery {
+    pub mode: Option<String>,
+    pub difficulty: Option<String>,
+    /// 对局结果："in_progress"、"black_win"、"white_win"、"draw"
+    pub result: Option<String>,
+    pub rule_set: Option<String>,
+    /// 名称包含的文字（不区分大小写）
+    pub name: Option<String>,
+    /// 创建时间范围（秒级时间戳，含两端）
+    pub created_from: Option<i64>,
+    pub created_to: Option<i64>,
+    /// 带有该标签（不区分大小写）
+    pub tag: Option<String>,
+    pub favorite: Option<bool>,
+    pub collection_id: Option<i64>,
+    pub sort: GameSort,
+    /// 默认按时间从新到旧
+    pub ascending: bool,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// 一页查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GamePage {
+    pub games: Vec<SavedGame>,
+    /// 符合条件的总数（不受分页影响）
+    pub total: i64,
+}
+
+impl GameQuery {
+    /// 生成 WHERE 子句和对应的参数
+    pub(crate) fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut conditions = vec!["is_autosave = 0".to_string()];
+        let mut params = Vec::new();
+
+        let equals = [
+            ("mode", &self.mode),
+            ("difficulty", &self.difficulty),
+            ("status", &self.result),
+            ("rule_set", &self.rule_set),
+        ];
+        for (column, value) in equals {
+            if let Some(value) = value {
+                params.push(Value::Text(value.clone()));
+                conditions.push(format!("{} = ?{}", column, params.len()));
+            }
+        }
+
+        if let Some(name) = self.name.as_deref().filter(|name| !name.is_empty()) {
+            params.push(Value::Text(name.to_string()));
+            conditions.push(format!("instr(lower(name), lower(?{})) > 0", params.len()));
+        }
+        if let Some(from) = self.created_from {
+            params.push(Value::Integer(from));
+            conditions.push(format!("created_at >= ?{}", params.len()));
+        }
+        if let Some(to) = self.created_to {
+            params.push(Value::Integer(to));
+            conditions.push(format!("created_at <= ?{}", params.len()));
+        }
+
+        if let Some(tag) = &self.tag {
+            params.push(Value::Text(tag.trim().to_string()));
+            conditions.push(format!(
+                "id IN (SELECT game_tags.game_id FROM game_tags JOIN tags ON tags.id = game_tags.tag_id
+                        WHERE tags.name = ?{})",
+                params.len()
+            ));
+        }
+        if let Some(favorite) = self.favorite {
+            params.push(Value::Integer(favorite as i64));
+            conditions.push(format!("is_favorite = ?{}", params.len()));
+        }
+        if let Some(collection_id) = self.collection_id {
+            params.push(Value::Integer(collection_id));
+            conditions.push(format!(
+                "id IN (SELECT game_id FROM collection_games WHERE collection_id = ?{})",
+                params.len()
+            ));
+        }
+
+        (conditions.join(" AND "), params)
+    }
+
+    /// ORDER BY 和分页子句
+    pub(crate) fn order_clause(&self) -> String {
+        let direction = if self.ascending { "ASC" } else { "DESC" };
+        let mut clause = format!("ORDER BY {} {}, id {}", self.sort.column(), direction, direction);
+        if self.limit.is_some() || self.offset.is_some() {
+            // SQLite 中 LIMIT -1 表示不限
+            let limit = self.limit.map_or(-1, i64::from);
+            clause.push_str(&format!(" LIMIT {} OFFSET {}", limit, self.offset.unwrap_or(0)));
+        }
+        clause
+    }
+}
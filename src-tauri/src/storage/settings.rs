@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::game::{OpeningRule, Player, RuleSet, TimeControl};
+use super::database::Database;
+
+/// 新对局的默认设置，按字段保存在 settings 表中（值为 JSON）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub mode: String,       // "pvp" or "pve"
+    pub difficulty: String, // "easy"、"medium"、"hard"
+    pub rule_set: String,
+    pub opening: String,
+    /// 目前只支持 15 路棋盘
+    pub board_size: usize,
+    /// PvE 模式下 AI 的执色
+    pub ai_player: Player,
+    pub time_control: TimeControl,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            mode: "pve".to_string(),
+            difficulty: "medium".to_string(),
+            rule_set: RuleSet::Freestyle.as_str().to_string(),
+            opening: OpeningRule::Standard.as_str().to_string(),
+            board_size: 15,
+            ai_player: Player::White,
+            time_control: TimeControl::Unlimited,
+        }
+    }
+}
+
+impl AppSettings {
+    /// 读取设置，没有保存过的字段使用默认值
+    pub fn load(db: &Database) -> Result<Self, String> {
+        let values: Map<String, Value> = db
+            .get_settings()
+            .map_err(|e| format!("Failed to load settings: {}", e))?
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, serde_json::from_str(&value).ok()?)))
+            .collect();
+        serde_json::from_value(Value::Object(values)).map_err(|e| format!("Invalid settings: {}", e))
+    }
+
+    pub fn save(&self, db: &Database) -> Result<(), String> {
+        self.validate()?;
+        let Value::Object(values) = serde_json::to_value(self).map_err(|e| e.to_string())? else {
+            return Err("Invalid settings".to_string());
+        };
+
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to save settings: {}", e))?;
+        for (key, value) in values {
+            db.set_setting(&key, &value.to_string())
+                .map_err(|e| format!("Failed to save settings: {}", e))?;
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to save settings: {}", e))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !matches!(self.mode.as_str(), "pvp" | "pve") {
+            return Err("Invalid game mode".to_string());
+        }
+        if !matches!(self.difficulty.as_str(), "easy" | "medium" | "hard") {
+            return Err("Invalid difficulty".to_string());
+        }
+        let rule_set = RuleSet::parse(&self.rule_set).ok_or("Invalid rule set")?;
+        let opening = OpeningRule::parse(&self.opening).ok_or("Invalid opening rule")?;
+        if rule_set == RuleSet::Connect6 && opening != OpeningRule::Standard {
+            return Err("Connect6 does not support opening rules".to_string());
+        }
+        if self.board_size != 15 {
+            return Err(format!("Unsupported board size: {}", self.board_size));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,758 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+
+use crate::game::{Annotation, EndReason, GameTree, Markup, MarkupKind, MoveNotes, NodeId};
+use crate::game::{Player, Position, RuleSet, SetupPosition, SetupStone};
+use super::exchange::{saved_notes, GameRecord};
+use super::models::{SavedGame, SavedMove, SavedSetupStone};
+
+/// SGF 中五子棋的游戏编号
+const GAME_GOMOKU: &str = "4";
+
+/// SGF 节点：属性列表和子节点（第一个子节点为主线，其余为变化）
+#[derive(Debug, Clone, Default)]
+pub struct SgfNode {
+    pub properties: Vec<(String, Vec<String>)>,
+    pub children: Vec<SgfNode>,
+}
+
+impl SgfNode {
+    pub fn get(&self, ident: &str) -> Option<&str> {
+        self.get_all(ident).first().map(String::as_str)
+    }
+
+    pub fn get_all(&self, ident: &str) -> &[String] {
+        self.properties
+            .iter()
+            .find(|(key, _)| key == ident)
+            .map(|(_, values)| values.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn set(&mut self, ident: &str, value: impl Into<String>) {
+        self.push_values(ident, vec![value.into()]);
+    }
+
+    fn push_values(&mut self, ident: &str, values: Vec<String>) {
+        match self.properties.iter_mut().find(|(key, _)| key == ident) {
+            Some((_, existing)) => existing.extend(values),
+            None => self.properties.push((ident.to_string(), values)),
+        }
+    }
+}
+
+/// 棋谱中的一手棋及其后续（第一个子节点为主线）
+#[derive(Debug, Clone)]
+pub struct SgfMove {
+    pub player: Player,
+    pub position: Position,
+    /// 评注（C）、评注符号（TE/BM/IT/DO）和棋盘标记（LB/TR/CR/SQ/MA/SL）
+    pub notes: MoveNotes,
+    pub children: Vec<SgfMove>,
+}
+
+/// 对局信息
+#[derive(Debug, Clone, Default)]
+pub struct SgfGameInfo {
+    pub name: Option<String>,
+    pub black_player: Option<String>,
+    pub white_player: Option<String>,
+    pub rule_set: Option<String>,
+    /// SGF 结果记法，如 `B+`、`W+R`、`0`
+    pub result: Option<String>,
+    /// `YYYY-MM-DD`
+    pub date: Option<String>,
+}
+
+/// 一盘 SGF 五子棋
+#[derive(Debug, Clone)]
+pub struct SgfGame {
+    pub info: SgfGameInfo,
+    pub setup: SetupPosition,
+    pub comment: Option<String>,
+    pub moves: Vec<SgfMove>,
+}
+
+impl SgfGame {
+    /// 解析 SGF 文本（只读取第一盘棋）
+    pub fn parse(text: &str) -> Result<SgfGame, String> {
+        let root = SgfParser::new(text).parse_tree()?;
+        Self::from_node(&root)
+    }
+
+    /// 生成 SGF 文本
+    pub fn write(&self) -> String {
+        let mut out = String::from("(");
+        write_node(&self.to_node(), &mut out);
+        out.push(')');
+        out
+    }
+
+    /// 主线上的落子
+    pub fn main_line(&self) -> Vec<Position> {
+        let mut line = Vec::new();
+        let mut next = self.moves.first();
+        while let Some(m) = next {
+            line.push(m.position.clone());
+            next = m.children.first();
+        }
+        line
+    }
+
+    fn from_node(root: &SgfNode) -> Result<SgfGame, String> {
+        if let Some(game) = root.get("GM") {
+            if game != GAME_GOMOKU {
+                return Err(format!("Not a gomoku SGF (GM[{}])", game));
+            }
+        }
+        if let Some(size) = root.get("SZ") {
+            if size != "15" {
+                return Err(format!("Unsupported board size: {}", size));
+            }
+        }
+
+        let info = SgfGameInfo {
+            name: root.get("GN").map(str::to_string),
+            black_player: root.get("PB").map(str::to_string),
+            white_player: root.get("PW").map(str::to_string),
+            rule_set: root.get("RU").map(str::to_string),
+            result: root.get("RE").map(str::to_string),
+            date: root.get("DT").map(str::to_string),
+        };
+
+        let mut setup = SetupPosition { stones: Vec::new(), to_move: Player::Black };
+        let mut moves = Vec::new();
+        // 根节点本身也可能带有第一手棋
+        Self::collect_moves(root, Some(&mut setup), &mut moves)?;
+
+        Ok(SgfGame {
+            info,
+            setup,
+            comment: root.get("C").map(str::to_string),
+            moves,
+        })
+    }
+
+    /// 收集 `node` 及其后续中的落子；没有落子的节点（只有评注、摆子或 PL）直接跳过，
+    /// 其后续接在上一手之后。`setup` 为 None 表示已经有落子，不能再摆子
+    fn collect_moves(
+        node: &SgfNode,
+        mut setup: Option<&mut SetupPosition>,
+        out: &mut Vec<SgfMove>,
+    ) -> Result<(), String> {
+        for (ident, player) in [("AB", Player::Black), ("AW", Player::White)] {
+            let values = node.get_all(ident);
+            if values.is_empty() {
+                continue;
+            }
+            let Some(setup) = setup.as_deref_mut() else {
+                return Err("Setup stones after the first move are not supported".to_string());
+            };
+            for value in values {
+                setup.stones.push(SetupStone {
+                    position: parse_point(value)?,
+                    player,
+                });
+            }
+        }
+        if let (Some(setup), Some(to_move)) = (setup.as_deref_mut(), node.get("PL")) {
+            setup.to_move = if to_move == "W" { Player::White } else { Player::Black };
+        }
+
+        let (player, point) = match (node.get("B"), node.get("W")) {
+            (Some(point), _) => (Player::Black, point),
+            (_, Some(point)) => (Player::White, point),
+            _ => {
+                for child in &node.children {
+                    Self::collect_moves(child, setup.as_deref_mut(), out)?;
+                }
+                return Ok(());
+            }
+        };
+        let mut children = Vec::new();
+        for child in &node.children {
+            Self::collect_moves(child, None, &mut children)?;
+        }
+
+        out.push(SgfMove {
+            player,
+            position: parse_point(point)?,
+            notes: parse_notes(node)?,
+            children,
+        });
+        Ok(())
+    }
+
+    fn to_node(&self) -> SgfNode {
+        let mut root = SgfNode::default();
+        root.set("FF", "4");
+        root.set("GM", GAME_GOMOKU);
+        root.set("SZ", "15");
+        let info = [
+            ("GN", &self.info.name),
+            ("PB", &self.info.black_player),
+            ("PW", &self.info.white_player),
+            ("RU", &self.info.rule_set),
+            ("RE", &self.info.result),
+            ("DT", &self.info.date),
+            ("C", &self.comment),
+        ];
+        for (ident, value) in info {
+            if let Some(value) = value {
+                root.set(ident, value.clone());
+            }
+        }
+        for stone in &self.setup.stones {
+            let ident = match stone.player {
+                Player::Black => "AB",
+                Player::White => "AW",
+            };
+            root.set(ident, format_point(&stone.position));
+        }
+        if self.setup.to_move == Player::White {
+            root.set("PL", "W");
+        }
+
+        root.children = self.moves.iter().map(Self::move_to_node).collect();
+        root
+    }
+
+    fn move_to_node(m: &SgfMove) -> SgfNode {
+        let mut node = SgfNode::default();
+        let ident = match m.player {
+            Player::Black => "B",
+            Player::White => "W",
+        };
+        node.set(ident, format_point(&m.position));
+        write_notes(&m.notes, &mut node);
+        node.children = m.children.iter().map(Self::move_to_node).collect();
+        node
+    }
+
+    /// 由保存的对局生成 SGF（包括变化）
+    pub fn from_record(record: &GameRecord) -> Result<SgfGame, String> {
+        let setup = record.setup_position();
+        let tree = record.tree()?;
+
+        // 节点编号即落子记录的下标
+        fn to_sgf_move(tree: &GameTree, moves: &[SavedMove], id: NodeId) -> SgfMove {
+            let m = &moves[id];
+            SgfMove {
+                player: if m.player == "white" { Player::White } else { Player::Black },
+                position: Position {
+                    x: m.position_x as usize,
+                    y: m.position_y as usize,
+                },
+                notes: tree.node(id).map(|node| node.notes.clone()).unwrap_or_default(),
+                children: tree
+                    .children(Some(id))
+                    .iter()
+                    .map(|&child| to_sgf_move(tree, moves, child))
+                    .collect(),
+            }
+        }
+        let line = tree
+            .children(None)
+            .iter()
+            .map(|&id| to_sgf_move(&tree, &record.moves, id))
+            .collect();
+
+        let date = Utc
+            .timestamp_opt(record.game.created_at, 0)
+            .single()
+            .map(|time| time.format("%Y-%m-%d").to_string());
+
+        Ok(SgfGame {
+            info: SgfGameInfo {
+                name: Some(record.game.name.clone()).filter(|name| !name.is_empty()),
+                black_player: record.black_player.clone(),
+                white_player: record.white_player.clone(),
+                rule_set: Some(record.game.rule_set.clone()),
+                result: format_result(&record.game.status, record.game.end_reason.as_deref()),
+                date,
+            },
+            setup,
+            comment: None,
+            moves: line,
+        })
+    }
+
+    /// 转换为保存用的对局记录（包括变化），结果和结束原因由 RE 属性给出
+    pub fn to_record(&self) -> GameRecord {
+        let (status, end_reason) = parse_result(self.info.result.as_deref());
+        let created_at = self
+            .info
+            .date
+            .as_deref()
+            .and_then(|date| NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok())
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|time| time.and_utc().timestamp())
+            .unwrap_or_else(|| Utc::now().timestamp());
+        let rule_set_name = self.info.rule_set.clone().unwrap_or_else(|| "freestyle".to_string());
+        // 未知规则由导入时报错，这里只用于计算回合数
+        let rule_set = RuleSet::parse(&rule_set_name).unwrap_or(RuleSet::Freestyle);
+        let game = SavedGame {
+            id: None,
+            // 没有 GN 时由导入方决定名称
+            name: self.info.name.clone().unwrap_or_default(),
+            mode: "pvp".to_string(),
+            difficulty: None,
+            created_at,
+            updated_at: created_at,
+            status: status.to_string(),
+            winner: None,
+            total_moves: 0,
+            rule_set: rule_set_name,
+            end_reason: end_reason.map(|r| r.as_str().to_string()),
+            black_captures: 0,
+            white_captures: 0,
+            first_player: self.setup.to_move.as_str().to_string(),
+            black_player_id: None,
+            white_player_id: None,
+            is_rated: false,
+            is_favorite: false,
+        };
+        let setup = self
+            .setup
+            .stones
+            .iter()
+            .map(|stone| SavedSetupStone {
+                id: None,
+                game_id: 0,
+                player: stone.player.as_str().to_string(),
+                position_x: stone.position.x as i32,
+                position_y: stone.position.y as i32,
+            })
+            .collect();
+
+        // 先序展开，记录编号从 1 开始，供 parent_move_id 引用
+        let mut moves: Vec<SavedMove> = Vec::new();
+        let mut annotations = Vec::new();
+        let mut markup = Vec::new();
+        let mut pending: Vec<(&SgfMove, Option<i64>, i32, bool)> = self
+            .moves
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, m)| (m, None, 1, index == 0))
+            .collect();
+        while let Some((m, parent_move_id, move_number, is_main_line)) = pending.pop() {
+            let id = moves.len() as i64 + 1;
+            moves.push(SavedMove {
+                id: Some(id),
+                game_id: 0,
+                move_number,
+                // 六子棋第一回合之后每回合两子
+                turn_number: (rule_set.turn_of_move(move_number as usize - 1) + 1) as i32,
+                player: m.player.as_str().to_string(),
+                position_x: m.position.x as i32,
+                position_y: m.position.y as i32,
+                timestamp: created_at,
+                parent_move_id,
+                is_main_line,
+            });
+            let (annotation, move_markup) = saved_notes(id, &m.notes);
+            annotations.extend(annotation);
+            markup.extend(move_markup);
+            for (index, child) in m.children.iter().enumerate().rev() {
+                pending.push((child, Some(id), move_number + 1, is_main_line && index == 0));
+            }
+        }
+
+        GameRecord {
+            game,
+            setup,
+            moves,
+            black_player: self.info.black_player.clone(),
+            white_player: self.info.white_player.clone(),
+            annotations,
+            markup,
+        }
+    }
+}
+
+/// 评注符号与 SGF 属性的对应：`!` 为 TE[1]，`!!` 为 TE[2]，`?` 为 BM[1]，`??` 为 BM[2]
+const GLYPH_PROPERTIES: [(Annotation, &str, &str); 6] = [
+    (Annotation::Good, "TE", "1"),
+    (Annotation::Excellent, "TE", "2"),
+    (Annotation::Mistake, "BM", "1"),
+    (Annotation::Blunder, "BM", "2"),
+    (Annotation::Interesting, "IT", ""),
+    (Annotation::Dubious, "DO", ""),
+];
+
+/// 标记与 SGF 属性的对应（高亮使用 SL）
+const MARKUP_PROPERTIES: [(MarkupKind, &str); 6] = [
+    (MarkupKind::Label, "LB"),
+    (MarkupKind::Triangle, "TR"),
+    (MarkupKind::Circle, "CR"),
+    (MarkupKind::Square, "SQ"),
+    (MarkupKind::Cross, "MA"),
+    (MarkupKind::Highlight, "SL"),
+];
+
+fn parse_notes(node: &SgfNode) -> Result<MoveNotes, String> {
+    let annotation = GLYPH_PROPERTIES
+        .iter()
+        .find(|(_, ident, value)| match node.get(ident) {
+            // TE/BM 缺省强度为 1
+            Some(found) => found == *value || (found.is_empty() && *value == "1"),
+            None => false,
+        })
+        .map(|(annotation, _, _)| *annotation);
+
+    let mut markup = Vec::new();
+    for (kind, ident) in MARKUP_PROPERTIES {
+        for value in node.get_all(ident) {
+            if kind == MarkupKind::Label {
+                let (point, label) = value
+                    .split_once(':')
+                    .ok_or_else(|| format!("Invalid label: {}", value))?;
+                markup.push(Markup {
+                    position: parse_point(point)?,
+                    kind,
+                    label: Some(label.to_string()),
+                });
+            } else {
+                for position in parse_point_list(value)? {
+                    markup.push(Markup { position, kind, label: None });
+                }
+            }
+        }
+    }
+
+    Ok(MoveNotes {
+        comment: node.get("C").map(str::to_string),
+        annotation,
+        markup,
+    })
+}
+
+fn write_notes(notes: &MoveNotes, node: &mut SgfNode) {
+    if let Some(comment) = &notes.comment {
+        node.set("C", comment.clone());
+    }
+    if let Some((_, ident, value)) = GLYPH_PROPERTIES
+        .iter()
+        .find(|(annotation, _, _)| Some(*annotation) == notes.annotation)
+    {
+        node.set(ident, *value);
+    }
+    for markup in &notes.markup {
+        let Some((_, ident)) = MARKUP_PROPERTIES.iter().find(|(kind, _)| *kind == markup.kind) else {
+            continue;
+        };
+        let point = format_point(&markup.position);
+        match &markup.label {
+            Some(label) if markup.kind == MarkupKind::Label => node.set(ident, format!("{}:{}", point, label)),
+            _ => node.set(ident, point),
+        }
+    }
+}
+
+/// SGF 坐标：两个小写字母，先列后行，`aa` 为左上角
+fn format_point(pos: &Position) -> String {
+    format!("{}{}", (b'a' + pos.y as u8) as char, (b'a' + pos.x as u8) as char)
+}
+
+fn parse_point(value: &str) -> Result<Position, String> {
+    let bytes = value.as_bytes();
+    if bytes.len() != 2 || !bytes.iter().all(|b| (b'a'..b'a' + 15).contains(b)) {
+        return Err(format!("Invalid SGF point: {}", value));
+    }
+    Ok(Position {
+        x: (bytes[1] - b'a') as usize,
+        y: (bytes[0] - b'a') as usize,
+    })
+}
+
+/// 点列表，支持 `aa:cc` 形式的矩形压缩写法
+fn parse_point_list(value: &str) -> Result<Vec<Position>, String> {
+    let Some((from, to)) = value.split_once(':') else {
+        return Ok(vec![parse_point(value)?]);
+    };
+    let (from, to) = (parse_point(from)?, parse_point(to)?);
+    let mut points = Vec::new();
+    for x in from.x.min(to.x)..=from.x.max(to.x) {
+        for y in from.y.min(to.y)..=from.y.max(to.y) {
+            points.push(Position { x, y });
+        }
+    }
+    Ok(points)
+}
+
+fn format_result(status: &str, end_reason: Option<&str>) -> Option<String> {
+    let winner = match status {
+        "black_win" => "B+",
+        "white_win" => "W+",
+        "draw" => return Some("0".to_string()),
+        _ => return None,
+    };
+    let suffix = match end_reason.and_then(EndReason::parse) {
+        Some(EndReason::Resignation) => "R",
+        Some(EndReason::Timeout) => "T",
+        // 禁手判负总是白胜
+        Some(EndReason::ForbiddenMove) => return Some("W+F".to_string()),
+        _ => "",
+    };
+    Some(format!("{}{}", winner, suffix))
+}
+
+fn parse_result(result: Option<&str>) -> (&'static str, Option<EndReason>) {
+    let Some(result) = result else {
+        return ("in_progress", None);
+    };
+    let status = match result.get(..2) {
+        Some("B+") => "black_win",
+        Some("W+") => "white_win",
+        _ if result == "0" || result.eq_ignore_ascii_case("draw") => return ("draw", None),
+        _ => return ("in_progress", None),
+    };
+    let end_reason = match &result[2..] {
+        "R" | "Resign" => Some(EndReason::Resignation),
+        "T" | "Time" => Some(EndReason::Timeout),
+        // F 表示判负，不一定是禁手
+        _ => None,
+    };
+    (status, end_reason)
+}
+
+fn write_node(node: &SgfNode, out: &mut String) {
+    out.push(';');
+    for (ident, values) in &node.properties {
+        out.push_str(ident);
+        for value in values {
+            out.push('[');
+            for c in value.chars() {
+                if c == ']' || c == '\\' {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+            out.push(']');
+        }
+    }
+
+    match node.children.len() {
+        0 => {}
+        // 只有一个后续时不需要括号
+        1 => write_node(&node.children[0], out),
+        _ => {
+            for child in &node.children {
+                out.push_str("\n(");
+                write_node(child, out);
+                out.push(')');
+            }
+        }
+    }
+}
+
+/// SGF 文本解析器
+struct SgfParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> SgfParser<'a> {
+    fn new(text: &'a str) -> Self {
+        SgfParser {
+            chars: text.chars().peekable(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("Expected '{}' but found '{}'", expected, c)),
+            None => Err(format!("Expected '{}' but reached end of file", expected)),
+        }
+    }
+
+    /// GameTree = "(" Sequence { GameTree } ")"，返回序列的第一个节点
+    fn parse_tree(&mut self) -> Result<SgfNode, String> {
+        self.expect('(')?;
+
+        let mut sequence = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.chars.peek() != Some(&';') {
+                break;
+            }
+            self.chars.next();
+            sequence.push(self.parse_properties()?);
+        }
+        if sequence.is_empty() {
+            return Err("Empty SGF game tree".to_string());
+        }
+
+        let mut variations = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('(') => variations.push(self.parse_tree()?),
+                Some(')') => {
+                    self.chars.next();
+                    break;
+                }
+                Some(c) => return Err(format!("Unexpected '{}' in game tree", c)),
+                None => return Err("Unterminated game tree".to_string()),
+            }
+        }
+
+        // 把序列串成链，变化挂在最后一个节点上
+        let mut node = sequence.pop().unwrap_or_default();
+        node.children = variations;
+        while let Some(mut parent) = sequence.pop() {
+            parent.children = vec![node];
+            node = parent;
+        }
+        Ok(node)
+    }
+
+    fn parse_properties(&mut self) -> Result<SgfNode, String> {
+        let mut node = SgfNode::default();
+        loop {
+            self.skip_whitespace();
+            let mut ident = String::new();
+            while let Some(&c) = self.chars.peek() {
+                if c.is_ascii_uppercase() {
+                    ident.push(c);
+                    self.chars.next();
+                } else if c.is_ascii_lowercase() {
+                    // 旧版 SGF 允许属性名中夹小写字母
+                    self.chars.next();
+                } else {
+                    break;
+                }
+            }
+            if ident.is_empty() {
+                return Ok(node);
+            }
+
+            let mut values = Vec::new();
+            loop {
+                self.skip_whitespace();
+                if self.chars.peek() != Some(&'[') {
+                    break;
+                }
+                self.chars.next();
+                values.push(self.parse_value()?);
+            }
+            if values.is_empty() {
+                return Err(format!("Property {} has no value", ident));
+            }
+            node.push_values(&ident, values);
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<String, String> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some('\\') => {
+                    // 转义后的换行是软换行
+                    match self.chars.next() {
+                        Some('\n') => {}
+                        Some(c) => value.push(c),
+                        None => break,
+                    }
+                }
+                Some(']') => return Ok(value),
+                Some(c) => value.push(c),
+                None => break,
+            }
+        }
+        Err("Unterminated property value".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: usize, y: usize) -> Position {
+        Position { x, y }
+    }
+
+    #[test]
+    fn parses_comments_variations_and_escapes() {
+        let game = SgfGame::parse(
+            "(;FF[4]GM[4]SZ[15]PB[Alice]C[a \\] b]\n\
+             ;B[hh]C[center]TE[1]\n\
+             (;W[hi]C[main\\\\line];B[ii])\n\
+             (;W[ih]BM[2]))",
+        )
+        .unwrap();
+
+        assert_eq!(game.info.black_player.as_deref(), Some("Alice"));
+        assert_eq!(game.comment.as_deref(), Some("a ] b"));
+        assert_eq!(game.main_line(), vec![pos(7, 7), pos(8, 7), pos(8, 8)]);
+
+        let first = &game.moves[0];
+        assert_eq!(first.notes.comment.as_deref(), Some("center"));
+        assert_eq!(first.notes.annotation, Some(Annotation::Good));
+        assert_eq!(first.children.len(), 2);
+        assert_eq!(first.children[0].notes.comment.as_deref(), Some("main\\line"));
+        assert_eq!(first.children[1].player, Player::White);
+        assert_eq!(first.children[1].notes.annotation, Some(Annotation::Blunder));
+    }
+
+    #[test]
+    fn written_sgf_parses_back() {
+        let original = SgfGame::parse(
+            "(;GM[4]C[root]AB[aa]PL[W];W[hh]C[x\\]y]LB[hh:A](;B[hi])(;B[ih]TR[hh:ii]))",
+        )
+        .unwrap();
+        let text = original.write();
+        assert!(text.contains("C[x\\]y]"));
+
+        let parsed = SgfGame::parse(&text).unwrap();
+        assert_eq!(parsed.comment.as_deref(), Some("root"));
+        assert_eq!(parsed.setup.stones.len(), 1);
+        assert_eq!(parsed.setup.to_move, Player::White);
+        assert_eq!(parsed.main_line(), original.main_line());
+        let first = &parsed.moves[0];
+        assert_eq!(first.notes.comment.as_deref(), Some("x]y"));
+        assert_eq!(first.notes.markup.len(), 1);
+        assert_eq!(first.children.len(), 2);
+        assert_eq!(first.children[1].notes.markup.len(), 4);
+    }
+
+    #[test]
+    fn skips_nodes_without_moves() {
+        let game = SgfGame::parse(
+            "(;GM[4];C[opening notes];AB[aa]AW[bb];PL[W];W[hh];C[after white];B[hi])",
+        )
+        .unwrap();
+        assert_eq!(game.setup.stones.len(), 2);
+        assert_eq!(game.setup.to_move, Player::White);
+        assert_eq!(game.main_line(), vec![pos(7, 7), pos(8, 7)]);
+
+        // 变化以空节点开头时，后续的落子仍接在同一手之后
+        let game = SgfGame::parse("(;GM[4];B[hh](;C[main];W[hi])(;W[ih]))").unwrap();
+        assert_eq!(game.moves[0].children.len(), 2);
+    }
+
+    #[test]
+    fn rejects_setup_after_moves() {
+        assert!(SgfGame::parse("(;GM[4];B[hh];AW[aa];W[hi])").is_err());
+    }
+
+    #[test]
+    fn result_codes() {
+        assert_eq!(format_result("white_win", Some("forbidden_move")).as_deref(), Some("W+F"));
+        assert_eq!(format_result("black_win", Some("resignation")).as_deref(), Some("B+R"));
+        assert_eq!(format_result("draw", None).as_deref(), Some("0"));
+        assert_eq!(format_result("in_progress", None), None);
+
+        assert_eq!(parse_result(Some("W+T")), ("white_win", Some(EndReason::Timeout)));
+        assert_eq!(parse_result(Some("B+F")), ("black_win", None));
+        assert_eq!(parse_result(Some("W+Forfeit")), ("white_win", None));
+        assert_eq!(parse_result(Some("Draw")), ("draw", None));
+    }
+}
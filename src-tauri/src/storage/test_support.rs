@@ -0,0 +1,42 @@
+use crate::game::Rating;
+use super::{Database, SavedGame, SavedPlayer};
+
+/// 保存一名初始等级分的人类玩家
+pub fn player(db: &Database, name: &str) -> i64 {
+    let rating = Rating::default();
+    db.save_player(&SavedPlayer {
+        id: None,
+        name: name.to_string(),
+        kind: "human".to_string(),
+        difficulty: None,
+        created_at: 0,
+        rating: rating.rating,
+        rating_deviation: rating.deviation,
+        volatility: rating.volatility,
+    })
+    .unwrap()
+}
+
+/// 自由规则下的一盘 PvP 对局
+pub fn game(status: &str, black_player_id: Option<i64>, white_player_id: Option<i64>) -> SavedGame {
+    SavedGame {
+        id: None,
+        name: "Game".to_string(),
+        mode: "pvp".to_string(),
+        difficulty: None,
+        created_at: 0,
+        updated_at: 0,
+        status: status.to_string(),
+        winner: None,
+        total_moves: 0,
+        rule_set: "freestyle".to_string(),
+        end_reason: None,
+        black_captures: 0,
+        white_captures: 0,
+        first_player: "black".to_string(),
+        black_player_id,
+        white_player_id,
+        is_rated: false,
+        is_favorite: false,
+    }
+}